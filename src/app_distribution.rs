@@ -0,0 +1,229 @@
+use crate::errors::{ApiError, ApiResult};
+use crate::google_api::{self, ApiClient, Operation};
+use crate::Firebase;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+const APP_DISTRIBUTION_API: &str = "https://firebaseappdistribution.googleapis.com";
+
+/// Uploads builds and manages testers through the Firebase App Distribution API. Requires an
+/// OAuth access token with the `cloud-platform` scope.
+#[derive(Debug, Clone)]
+pub struct AppDistribution {
+    api: ApiClient,
+    project: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppRelease {
+    /// `projects/PROJECT_NUMBER/apps/APP_ID/releases/RELEASE_ID`
+    pub name: String,
+    pub display_version: String,
+    pub build_version: String,
+    pub create_time: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TesterGroup {
+    /// `projects/PROJECT_NUMBER/groups/ALIAS`
+    pub name: String,
+    pub display_name: String,
+    pub tester_count: u32,
+}
+
+#[derive(Deserialize)]
+struct GroupList {
+    #[serde(default)]
+    groups: Vec<TesterGroup>,
+}
+
+/// Extracts the release from the response of a finished upload operation.
+fn uploaded_release(response: Value) -> ApiResult<AppRelease> {
+    match response.get("release").cloned() {
+        Some(release) => match serde_json::from_value(release) {
+            Ok(release) => Ok(release),
+            Err(err) => Err(ApiError::InvalidJson(err)),
+        },
+        None => Err(ApiError::Operation(String::from(
+            "upload finished without a release",
+        ))),
+    }
+}
+
+impl Firebase {
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// let distribution = Firebase::new("https://myfirebase.firebaseio.com").unwrap().app_distribution("123456789");
+    /// ```
+    pub fn app_distribution(&self, project_number: &str) -> AppDistribution {
+        AppDistribution {
+            api: ApiClient::new(self),
+            project: format!("projects/{}", project_number),
+        }
+    }
+}
+
+impl AppDistribution {
+    /// Uploads an APK, AAB or IPA and waits until App Distribution has processed it.
+    ///
+    /// ```
+    /// use firebase_rs::{AuthCredential, Firebase};
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// firebase.set_credential(AuthCredential::AccessToken("ya29.token".into()));
+    /// let binary = std::fs::read("app-release.apk").unwrap();
+    /// let distribution = firebase.app_distribution("123456789");
+    /// let release = distribution.upload_release("1:123456789:android:abcdef", "app-release.apk", binary).await.unwrap();
+    /// distribution.distribute(&release, &["qa@example.com"], &["beta"]).await.unwrap();
+    /// # }
+    /// ```
+    pub async fn upload_release(
+        &self,
+        app_id: &str,
+        file_name: &str,
+        binary: Vec<u8>,
+    ) -> ApiResult<AppRelease> {
+        let url = format!(
+            "{}/upload/v1/{}/apps/{}/releases:upload",
+            APP_DISTRIBUTION_API, self.project, app_id
+        );
+        let headers = [
+            ("content-type", "application/octet-stream"),
+            ("x-goog-upload-protocol", "raw"),
+            ("x-goog-upload-file-name", file_name),
+        ];
+        let response = self
+            .api
+            .send_with(http::Method::POST, &url, &headers, binary)
+            .await?;
+        let operation: Operation = google_api::parse(&response)?;
+        let api = format!("{}/v1", APP_DISTRIBUTION_API);
+        let response = self.api.wait(&api, operation).await?;
+
+        uploaded_release(response)
+    }
+
+    pub async fn distribute(
+        &self,
+        release: &AppRelease,
+        tester_emails: &[&str],
+        group_aliases: &[&str],
+    ) -> ApiResult<()> {
+        let url = format!("{}/v1/{}:distribute", APP_DISTRIBUTION_API, release.name);
+        let body = json!({ "testerEmails": tester_emails, "groupAliases": group_aliases });
+        self.call(&url, &body).await
+    }
+
+    pub async fn add_testers(&self, emails: &[&str]) -> ApiResult<()> {
+        let url = format!(
+            "{}/v1/{}/testers:batchAdd",
+            APP_DISTRIBUTION_API, self.project
+        );
+        self.call(&url, &json!({ "emails": emails })).await
+    }
+
+    pub async fn remove_testers(&self, emails: &[&str]) -> ApiResult<()> {
+        let url = format!(
+            "{}/v1/{}/testers:batchRemove",
+            APP_DISTRIBUTION_API, self.project
+        );
+        self.call(&url, &json!({ "emails": emails })).await
+    }
+
+    pub async fn list_groups(&self) -> ApiResult<Vec<TesterGroup>> {
+        let url = format!("{}/v1/{}/groups", APP_DISTRIBUTION_API, self.project);
+        let list: GroupList = self.api.json(http::Method::GET, &url, None).await?;
+        Ok(list.groups)
+    }
+
+    pub async fn create_group(&self, alias: &str, display_name: &str) -> ApiResult<TesterGroup> {
+        let url = format!(
+            "{}/v1/{}/groups?groupId={}",
+            APP_DISTRIBUTION_API, self.project, alias
+        );
+        let body = json!({ "displayName": display_name });
+        self.api.json(http::Method::POST, &url, Some(&body)).await
+    }
+
+    pub async fn delete_group(&self, alias: &str) -> ApiResult<()> {
+        let url = format!(
+            "{}/v1/{}/groups/{}",
+            APP_DISTRIBUTION_API, self.project, alias
+        );
+        let _: Value = self.api.json(http::Method::DELETE, &url, None).await?;
+        Ok(())
+    }
+
+    pub async fn join_group(&self, alias: &str, emails: &[&str]) -> ApiResult<()> {
+        let url = format!(
+            "{}/v1/{}/groups/{}:batchJoin",
+            APP_DISTRIBUTION_API, self.project, alias
+        );
+        self.call(&url, &json!({ "emails": emails })).await
+    }
+
+    pub async fn leave_group(&self, alias: &str, emails: &[&str]) -> ApiResult<()> {
+        let url = format!(
+            "{}/v1/{}/groups/{}:batchLeave",
+            APP_DISTRIBUTION_API, self.project, alias
+        );
+        self.call(&url, &json!({ "emails": emails })).await
+    }
+
+    async fn call(&self, url: &str, body: &Value) -> ApiResult<()> {
+        let _: Value = self.api.json(http::Method::POST, url, Some(body)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_distribution::{uploaded_release, AppRelease};
+    use crate::errors::ApiError;
+    use crate::google_api::Operation;
+    use serde_json::json;
+
+    #[test]
+    fn operation_release() {
+        let pending: Operation = serde_json::from_value(json!({ "name": "operations/1" })).unwrap();
+        assert!(pending.result().unwrap().is_none());
+
+        let done: Operation = serde_json::from_value(json!({
+            "name": "operations/1",
+            "done": true,
+            "response": {
+                "result": "RELEASE_CREATED",
+                "release": {
+                    "name": "projects/1/apps/a/releases/r",
+                    "displayVersion": "1.2.0",
+                    "buildVersion": "42"
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            uploaded_release(done.result().unwrap().unwrap()).unwrap(),
+            AppRelease {
+                name: "projects/1/apps/a/releases/r".to_string(),
+                display_version: "1.2.0".to_string(),
+                build_version: "42".to_string(),
+                create_time: String::new(),
+            }
+        );
+
+        let failed: Operation = serde_json::from_value(json!({
+            "name": "operations/1",
+            "done": true,
+            "error": { "message": "invalid binary" }
+        }))
+        .unwrap();
+        match failed.result() {
+            Err(ApiError::Operation(message)) => assert_eq!(message, "invalid binary"),
+            other => panic!("unexpected result: {:?}", other.is_ok()),
+        }
+    }
+}
@@ -0,0 +1,906 @@
+use crate::clients::{self, HttpClient};
+use crate::credential::AuthCredential;
+use crate::errors::{ApiResult, AuthError, AuthResult, RequestError, RequestResult};
+use crate::google_api::ApiClient;
+use crate::secret::Secret;
+use crate::token_cache::EXPIRY_MARGIN;
+use crate::token_provider::TokenProvider;
+use crate::Firebase;
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use http::header::CONTENT_TYPE;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+const IDENTITY_TOOLKIT_URL: &str = "https://identitytoolkit.googleapis.com/v1";
+const SECURE_TOKEN_URL: &str = "https://securetoken.googleapis.com/v1/token";
+const AUTH_EMULATOR_HOST_VAR: &str = "FIREBASE_AUTH_EMULATOR_HOST";
+
+/// `host:port` of the Auth emulator, if `FIREBASE_AUTH_EMULATOR_HOST` is set.
+pub(crate) fn auth_emulator_host() -> Option<String> {
+    match std::env::var(AUTH_EMULATOR_HOST_VAR) {
+        Ok(host) if !host.is_empty() => Some(host),
+        _ => None,
+    }
+}
+
+/// Routes `url` through the Auth emulator at `host`, which serves every Google endpoint under
+/// its own host name.
+fn emulated(url: &str, host: Option<&str>) -> String {
+    match host {
+        Some(host) => format!("http://{}/{}", host, url.trim_start_matches("https://")),
+        None => url.to_string(),
+    }
+}
+
+/// A signed-in user: the ID token to send with database requests and the refresh token that
+/// renews it. Serializing a session writes both tokens in clear text, so store it like a
+/// password.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthSession {
+    pub local_id: String,
+    pub email: Option<String>,
+    pub id_token: Secret,
+    pub refresh_token: Secret,
+    pub expires_at: SystemTime,
+}
+
+impl AuthSession {
+    /// Resumes a session from a refresh token persisted earlier. The session starts out expired,
+    /// so [`SessionTokens`] exchanges the refresh token before the first request.
+    ///
+    /// ```
+    /// use firebase_rs::{AuthSession, Firebase};
+    ///
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// let session = AuthSession::restore("STORED_REFRESH_TOKEN");
+    /// firebase.set_token_provider(firebase.authentication("API_KEY").session_tokens(session));
+    /// ```
+    pub fn restore(refresh_token: &str) -> Self {
+        Self {
+            local_id: String::new(),
+            email: None,
+            id_token: Secret::new(""),
+            refresh_token: Secret::new(refresh_token),
+            expires_at: SystemTime::UNIX_EPOCH,
+        }
+    }
+
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// let session = firebase.authentication("API_KEY").sign_in_with_email_password("ada@example.com", "hunter22").await.unwrap();
+    /// firebase.set_credential(session.credential());
+    /// # }
+    /// ```
+    pub fn credential(&self) -> AuthCredential {
+        AuthCredential::IdToken(self.id_token.clone())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= SystemTime::now()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionResponse {
+    /// Missing from custom token sign-ins; read from the ID token instead.
+    #[serde(default)]
+    local_id: String,
+    email: Option<String>,
+    id_token: String,
+    refresh_token: String,
+    expires_in: String,
+}
+
+impl SessionResponse {
+    fn into_session(self) -> AuthSession {
+        let expires_in = self.expires_in.parse().unwrap_or(3600);
+        let local_id = if self.local_id.is_empty() {
+            token_user_id(&self.id_token).unwrap_or_default()
+        } else {
+            self.local_id
+        };
+        AuthSession {
+            local_id,
+            email: self.email.filter(|email| !email.is_empty()),
+            id_token: Secret::new(self.id_token),
+            refresh_token: Secret::new(self.refresh_token),
+            expires_at: SystemTime::now() + Duration::from_secs(expires_in),
+        }
+    }
+}
+
+/// The account of a signed-in user, as returned by [`Auth::get_user_data`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserData {
+    pub local_id: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub display_name: Option<String>,
+    pub photo_url: Option<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    /// Milliseconds since the Unix epoch, as a string.
+    pub created_at: Option<String>,
+    pub last_login_at: Option<String>,
+    #[serde(default)]
+    pub provider_user_info: Vec<ProviderUserInfo>,
+}
+
+/// An identity provider linked to an account.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderUserInfo {
+    pub provider_id: String,
+    pub federated_id: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LookupResponse {
+    #[serde(default)]
+    users: Vec<UserData>,
+}
+
+/// A credential from an OAuth identity provider, exchanged by [`Auth::sign_in_with_idp`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdpCredential {
+    /// An OpenID Connect ID token, e.g. from Google or Apple.
+    IdToken { provider_id: String, token: Secret },
+    /// An OAuth access token, e.g. from GitHub or Facebook.
+    AccessToken { provider_id: String, token: Secret },
+}
+
+impl IdpCredential {
+    pub fn google(id_token: &str) -> Self {
+        IdpCredential::IdToken {
+            provider_id: String::from("google.com"),
+            token: Secret::new(id_token),
+        }
+    }
+
+    pub fn apple(id_token: &str) -> Self {
+        IdpCredential::IdToken {
+            provider_id: String::from("apple.com"),
+            token: Secret::new(id_token),
+        }
+    }
+
+    pub fn github(access_token: &str) -> Self {
+        IdpCredential::AccessToken {
+            provider_id: String::from("github.com"),
+            token: Secret::new(access_token),
+        }
+    }
+
+    /// The form-encoded `postBody` of `signInWithIdp`.
+    fn post_body(&self) -> String {
+        let (key, provider_id, token) = match self {
+            IdpCredential::IdToken { provider_id, token } => ("id_token", provider_id, token),
+            IdpCredential::AccessToken { provider_id, token } => {
+                ("access_token", provider_id, token)
+            }
+        };
+        url::form_urlencoded::Serializer::new(String::new())
+            .append_pair(key, token.expose())
+            .append_pair("providerId", provider_id)
+            .finish()
+    }
+}
+
+/// Reads the `user_id` claim of an ID token without verifying it.
+fn token_user_id(id_token: &str) -> Option<String> {
+    let payload = id_token.split('.').nth(1)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let claims: Value = serde_json::from_slice(&payload).ok()?;
+    claims
+        .get("user_id")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+#[derive(Deserialize)]
+struct RefreshResponse {
+    user_id: String,
+    id_token: String,
+    refresh_token: String,
+    expires_in: String,
+}
+
+/// Signs users in through the Identity Toolkit REST API of the project that owns `api_key`, or
+/// through the Auth emulator when `FIREBASE_AUTH_EMULATOR_HOST` is set.
+#[derive(Debug, Clone)]
+pub struct Auth {
+    api_key: Secret,
+    client: Arc<dyn HttpClient>,
+    emulator: Option<String>,
+}
+
+impl Auth {
+    /// ```
+    /// use firebase_rs::Auth;
+    ///
+    /// let auth = Auth::new("API_KEY");
+    /// ```
+    pub fn new(api_key: &str) -> Self {
+        Self::with_client(api_key, clients::default_client())
+    }
+
+    fn with_client(api_key: &str, client: Arc<dyn HttpClient>) -> Self {
+        Self {
+            api_key: Secret::new(api_key),
+            client,
+            emulator: auth_emulator_host(),
+        }
+    }
+
+    /// Sends every call to the Auth emulator at `host` (`localhost:9099`), regardless of
+    /// `FIREBASE_AUTH_EMULATOR_HOST`.
+    ///
+    /// ```
+    /// use firebase_rs::Auth;
+    ///
+    /// let auth = Auth::new("fake-api-key").with_emulator("localhost:9099");
+    /// ```
+    pub fn with_emulator(mut self, host: &str) -> Self {
+        self.emulator = Some(host.to_string());
+        self
+    }
+
+    /// ```
+    /// use firebase_rs::Auth;
+    ///
+    /// # async fn run() {
+    /// let session = Auth::new("API_KEY").sign_up_with_email_password("ada@example.com", "hunter22").await;
+    /// # }
+    /// ```
+    pub async fn sign_up_with_email_password(
+        &self,
+        email: &str,
+        password: &str,
+    ) -> AuthResult<AuthSession> {
+        let body = json!({ "email": email, "password": password, "returnSecureToken": true });
+        let response: SessionResponse = self.post("accounts:signUp", &body).await?;
+        Ok(response.into_session())
+    }
+
+    /// ```
+    /// use firebase_rs::Auth;
+    ///
+    /// # async fn run() {
+    /// let session = Auth::new("API_KEY").sign_in_with_email_password("ada@example.com", "hunter22").await;
+    /// # }
+    /// ```
+    pub async fn sign_in_with_email_password(
+        &self,
+        email: &str,
+        password: &str,
+    ) -> AuthResult<AuthSession> {
+        let body = json!({ "email": email, "password": password, "returnSecureToken": true });
+        let response: SessionResponse = self.post("accounts:signInWithPassword", &body).await?;
+        Ok(response.into_session())
+    }
+
+    /// Creates a new anonymous user. The session has no email and is upgraded by linking a
+    /// provider to it later.
+    ///
+    /// ```
+    /// use firebase_rs::Auth;
+    ///
+    /// # async fn run() {
+    /// let session = Auth::new("API_KEY").sign_in_anonymously().await;
+    /// # }
+    /// ```
+    pub async fn sign_in_anonymously(&self) -> AuthResult<AuthSession> {
+        let body = json!({ "returnSecureToken": true });
+        let response: SessionResponse = self.post("accounts:signUp", &body).await?;
+        Ok(response.into_session())
+    }
+
+    /// Signs in with a custom token minted by a trusted backend, e.g. with
+    /// `ServiceAccount::create_custom_token`.
+    ///
+    /// ```
+    /// use firebase_rs::Auth;
+    ///
+    /// # async fn run() {
+    /// let session = Auth::new("API_KEY").sign_in_with_custom_token("CUSTOM_TOKEN").await;
+    /// # }
+    /// ```
+    pub async fn sign_in_with_custom_token(&self, token: &str) -> AuthResult<AuthSession> {
+        let body = json!({ "token": token, "returnSecureToken": true });
+        let response: SessionResponse = self.post("accounts:signInWithCustomToken", &body).await?;
+        Ok(response.into_session())
+    }
+
+    /// Signs in with a credential obtained from an OAuth provider, creating the user on first
+    /// sign-in.
+    ///
+    /// ```
+    /// use firebase_rs::{Auth, IdpCredential};
+    ///
+    /// # async fn run() {
+    /// let session = Auth::new("API_KEY").sign_in_with_idp(&IdpCredential::github("gho_token")).await;
+    /// # }
+    /// ```
+    pub async fn sign_in_with_idp(&self, credential: &IdpCredential) -> AuthResult<AuthSession> {
+        let body = json!({
+            "postBody": credential.post_body(),
+            "requestUri": "http://localhost",
+            "returnSecureToken": true,
+        });
+        let response: SessionResponse = self.post("accounts:signInWithIdp", &body).await?;
+        Ok(response.into_session())
+    }
+
+    /// Looks up the account of the user signed in with `session`.
+    ///
+    /// ```
+    /// use firebase_rs::Auth;
+    ///
+    /// # async fn run() {
+    /// let auth = Auth::new("API_KEY");
+    /// let session = auth.sign_in_with_email_password("ada@example.com", "hunter22").await.unwrap();
+    /// let user = auth.get_user_data(&session).await.unwrap();
+    /// println!("{:?}", user.display_name);
+    /// # }
+    /// ```
+    pub async fn get_user_data(&self, session: &AuthSession) -> AuthResult<UserData> {
+        let body = json!({ "idToken": session.id_token.expose() });
+        let response: LookupResponse = self.post("accounts:lookup", &body).await?;
+        match response.users.into_iter().next() {
+            Some(user) => Ok(user),
+            None => Err(AuthError::Rejected {
+                status: 400,
+                message: String::from("USER_NOT_FOUND"),
+            }),
+        }
+    }
+
+    /// Sets the display name and photo URL of the user signed in with `session`. `None` leaves a
+    /// field unchanged.
+    ///
+    /// ```
+    /// use firebase_rs::Auth;
+    ///
+    /// # async fn run() {
+    /// let auth = Auth::new("API_KEY");
+    /// let session = auth.sign_in_with_email_password("ada@example.com", "hunter22").await.unwrap();
+    /// auth.update_profile(&session, Some("Ada Lovelace"), None).await.unwrap();
+    /// # }
+    /// ```
+    pub async fn update_profile(
+        &self,
+        session: &AuthSession,
+        display_name: Option<&str>,
+        photo_url: Option<&str>,
+    ) -> AuthResult<()> {
+        let mut body = json!({ "idToken": session.id_token.expose() });
+        if let Some(display_name) = display_name {
+            body["displayName"] = json!(display_name);
+        }
+        if let Some(photo_url) = photo_url {
+            body["photoUrl"] = json!(photo_url);
+        }
+        self.post::<Value>("accounts:update", &body).await?;
+        Ok(())
+    }
+
+    /// Deletes the account of the user signed in with `session`.
+    pub async fn delete_account(&self, session: &AuthSession) -> AuthResult<()> {
+        let body = json!({ "idToken": session.id_token.expose() });
+        self.post::<Value>("accounts:delete", &body).await?;
+        Ok(())
+    }
+
+    /// Exchanges the refresh token of `session` for a new ID token.
+    ///
+    /// ```
+    /// use firebase_rs::Auth;
+    ///
+    /// # async fn run() {
+    /// let auth = Auth::new("API_KEY");
+    /// let session = auth.sign_in_with_email_password("ada@example.com", "hunter22").await.unwrap();
+    /// let renewed = auth.refresh(&session).await;
+    /// # }
+    /// ```
+    pub async fn refresh(&self, session: &AuthSession) -> AuthResult<AuthSession> {
+        let form = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("grant_type", "refresh_token")
+            .append_pair("refresh_token", session.refresh_token.expose())
+            .finish();
+        let url = format!(
+            "{}?key={}",
+            emulated(SECURE_TOKEN_URL, self.emulator.as_deref()),
+            self.api_key.expose()
+        );
+        let response: RefreshResponse = self
+            .send(url, "application/x-www-form-urlencoded", form.into_bytes())
+            .await?;
+
+        let expires_in = response.expires_in.parse().unwrap_or(3600);
+        Ok(AuthSession {
+            local_id: response.user_id,
+            email: session.email.clone(),
+            id_token: Secret::new(response.id_token),
+            refresh_token: Secret::new(response.refresh_token),
+            expires_at: SystemTime::now() + Duration::from_secs(expires_in),
+        })
+    }
+
+    /// [`TokenProvider`] that authenticates requests as the user of `session`, refreshing the ID
+    /// token shortly before it expires.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// let auth = firebase.authentication("API_KEY");
+    /// let session = auth.sign_in_with_email_password("ada@example.com", "hunter22").await.unwrap();
+    /// firebase.set_token_provider(auth.session_tokens(session));
+    /// # }
+    /// ```
+    pub fn session_tokens(&self, session: AuthSession) -> SessionTokens {
+        SessionTokens {
+            auth: self.clone(),
+            session: Arc::new(RwLock::new(session)),
+            refreshing: Arc::default(),
+        }
+    }
+
+    /// Calls `{IDENTITY_TOOLKIT_URL}/{method}` and decodes the JSON answer.
+    async fn post<T>(&self, method: &str, body: &Value) -> AuthResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let url = format!(
+            "{}/{}?key={}",
+            emulated(IDENTITY_TOOLKIT_URL, self.emulator.as_deref()),
+            method,
+            self.api_key.expose()
+        );
+        self.send(url, "application/json", body.to_string().into_bytes())
+            .await
+    }
+
+    async fn send<T>(&self, url: String, content_type: &str, body: Vec<u8>) -> AuthResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let request = http::Request::post(url)
+            .header(CONTENT_TYPE, content_type)
+            .body(body);
+        let request = match request {
+            Ok(request) => request,
+            Err(_) => return Err(AuthError::NetworkError),
+        };
+        let response = match self.client.execute(request).await {
+            Ok(response) => response,
+            Err(_) => return Err(AuthError::NetworkError),
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AuthError::Rejected {
+                status: status.as_u16(),
+                message: error_message(response.body()),
+            });
+        }
+
+        match serde_json::from_slice(response.body()) {
+            Ok(value) => Ok(value),
+            Err(err) => Err(AuthError::InvalidJson(err)),
+        }
+    }
+}
+
+/// Extracts `error.message` (e.g. `EMAIL_EXISTS`) from an Identity Toolkit error body.
+fn error_message(body: &[u8]) -> String {
+    let message = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|value| {
+            value
+                .pointer("/error/message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        });
+    match message {
+        Some(message) => message,
+        None => String::from_utf8_lossy(body).into_owned(),
+    }
+}
+
+/// The ID tokens of one signed-in user, renewed through its refresh token. Clones share the
+/// session.
+#[derive(Debug, Clone)]
+pub struct SessionTokens {
+    auth: Auth,
+    session: Arc<RwLock<AuthSession>>,
+    /// Held while the refresh token is exchanged, so concurrent requests share one refresh.
+    refreshing: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl SessionTokens {
+    /// The current session, including the latest refresh token.
+    pub fn session(&self) -> AuthSession {
+        match self.session.read() {
+            Ok(session) => session.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl TokenProvider for SessionTokens {
+    async fn token(&self) -> RequestResult<AuthCredential> {
+        let session = self.session();
+        if session.expires_at > SystemTime::now() + EXPIRY_MARGIN {
+            return Ok(session.credential());
+        }
+
+        let _refreshing = self.refreshing.lock().await;
+        // Another request may have refreshed the session while this one waited.
+        let session = self.session();
+        if session.expires_at > SystemTime::now() + EXPIRY_MARGIN {
+            return Ok(session.credential());
+        }
+        let renewed = match self.auth.refresh(&session).await {
+            Ok(renewed) => renewed,
+            Err(err) => return Err(RequestError::Auth(err)),
+        };
+        let credential = renewed.credential();
+        match self.session.write() {
+            Ok(mut session) => *session = renewed,
+            Err(poisoned) => *poisoned.into_inner() = renewed,
+        }
+        Ok(credential)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionCookieResponse {
+    session_cookie: String,
+}
+
+impl Firebase {
+    /// Identity Toolkit client that sends requests through this instance's transport.
+    pub fn authentication(&self, api_key: &str) -> Auth {
+        Auth::with_client(api_key, self.client.clone())
+    }
+
+    /// Exchanges a freshly minted ID token for a session cookie valid for `valid_for` (between
+    /// five minutes and two weeks). Requires an OAuth access token of a service account of
+    /// `project_id`; verify the cookie on later requests with
+    /// `Firebase::session_cookie_verifier`.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use firebase_rs::{Credentials, Firebase};
+    ///
+    /// # async fn run() {
+    /// let credentials = Credentials::application_default().unwrap();
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_token_provider(credentials);
+    /// let cookie = firebase.create_session_cookie("my-project", "ID_TOKEN", Duration::from_secs(5 * 24 * 60 * 60)).await;
+    /// # }
+    /// ```
+    pub async fn create_session_cookie(
+        &self,
+        project_id: &str,
+        id_token: &str,
+        valid_for: Duration,
+    ) -> ApiResult<String> {
+        let url = format!(
+            "{}/projects/{}:createSessionCookie",
+            emulated(IDENTITY_TOOLKIT_URL, auth_emulator_host().as_deref()),
+            project_id
+        );
+        let body = json!({ "idToken": id_token, "validDuration": valid_for.as_secs() });
+        let response: SessionCookieResponse = ApiClient::new(self)
+            .json(http::Method::POST, &url, Some(&body))
+            .await?;
+        Ok(response.session_cookie)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::auth::{
+        emulated, AuthSession, IdpCredential, IDENTITY_TOOLKIT_URL, SECURE_TOKEN_URL,
+    };
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::credential::AuthCredential;
+    use crate::errors::{AuthError, RequestResult};
+    use crate::hooks::HttpRequest;
+    use crate::requestable::RequestableExt;
+    use crate::secret::Secret;
+    use crate::token_provider::TokenProvider;
+    use crate::Firebase;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+
+    #[derive(Debug)]
+    struct Toolkit;
+
+    #[derive(Debug, Default)]
+    struct SlowRefresh(AtomicUsize);
+
+    #[async_trait]
+    impl HttpClient for SlowRefresh {
+        async fn execute(&self, _request: HttpRequest) -> RequestResult<HttpResponse> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let session = r#"{"user_id":"uid","id_token":"fresh","refresh_token":"refresh","expires_in":"3600"}"#;
+            Ok(http::Response::new(session.as_bytes().to_vec()))
+        }
+    }
+
+    #[async_trait]
+    impl HttpClient for Toolkit {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            match request.uri().host() {
+                Some("firebase_id.firebaseio.com") => {
+                    let uri = request.uri().to_string();
+                    return Ok(http::Response::new(format!("{:?}", uri).into_bytes()));
+                }
+                Some("securetoken.googleapis.com") => {
+                    let body = String::from_utf8(request.body().clone()).unwrap();
+                    assert_eq!(body, "grant_type=refresh_token&refresh_token=stale_refresh");
+                    let session = r#"{"user_id":"uid","id_token":"fresh","refresh_token":"refresh","expires_in":"3600"}"#;
+                    return Ok(http::Response::new(session.as_bytes().to_vec()));
+                }
+                _ => {}
+            }
+            match request.uri().path() {
+                "/v1/accounts:lookup" => {
+                    let users = r#"{"users":[{"localId":"uid","email":"ada@example.com","emailVerified":true,"displayName":"Ada","providerUserInfo":[{"providerId":"password","email":"ada@example.com"}]}]}"#;
+                    return Ok(http::Response::new(users.as_bytes().to_vec()));
+                }
+                "/v1/accounts:update" => {
+                    let body = String::from_utf8(request.body().clone()).unwrap();
+                    assert_eq!(body, r#"{"displayName":"Ada Lovelace","idToken":"id"}"#);
+                    return Ok(http::Response::new(b"{}".to_vec()));
+                }
+                "/v1/projects/my-project:createSessionCookie" => {
+                    assert_eq!(request.headers()["authorization"], "Bearer ya29.admin");
+                    let body = String::from_utf8(request.body().clone()).unwrap();
+                    assert_eq!(body, r#"{"idToken":"id","validDuration":3600}"#);
+                    return Ok(http::Response::new(
+                        br#"{"sessionCookie":"cookie"}"#.to_vec(),
+                    ));
+                }
+                "/v1/accounts:delete" => {
+                    return Ok(http::Response::new(b"{}".to_vec()));
+                }
+                _ => {}
+            }
+            assert_eq!(request.uri().query(), Some("key=key"));
+            let body = String::from_utf8(request.body().clone()).unwrap();
+            assert!(body.contains(r#""returnSecureToken":true"#));
+
+            if body.contains("taken@example.com") {
+                let error = r#"{"error":{"code":400,"message":"EMAIL_EXISTS"}}"#;
+                return Ok(http::Response::builder()
+                    .status(400)
+                    .body(error.as_bytes().to_vec())
+                    .unwrap());
+            }
+            if body.contains(r#""postBody":"access_token=gho_token&providerId=github.com""#) {
+                let session = r#"{"localId":"octocat","providerId":"github.com","idToken":"id","refreshToken":"refresh","expiresIn":"3600"}"#;
+                return Ok(http::Response::new(session.as_bytes().to_vec()));
+            }
+            if body.contains(r#""token":"custom""#) {
+                // {"user_id":"minted"}
+                let session = r#"{"idToken":"header.eyJ1c2VyX2lkIjoibWludGVkIn0.signature","refreshToken":"refresh","expiresIn":"3600"}"#;
+                return Ok(http::Response::new(session.as_bytes().to_vec()));
+            }
+            if body == r#"{"returnSecureToken":true}"# {
+                let session = r#"{"localId":"anonymous","idToken":"id","refreshToken":"refresh","expiresIn":"3600"}"#;
+                return Ok(http::Response::new(session.as_bytes().to_vec()));
+            }
+            let session = r#"{"localId":"uid","email":"ada@example.com","idToken":"id","refreshToken":"refresh","expiresIn":"3600"}"#;
+            Ok(http::Response::new(session.as_bytes().to_vec()))
+        }
+    }
+
+    #[tokio::test]
+    async fn email_password() {
+        let firebase = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .with_http_client(Toolkit);
+        let auth = firebase.authentication("key");
+
+        let session = auth
+            .sign_in_with_email_password("ada@example.com", "hunter22")
+            .await
+            .unwrap();
+        assert_eq!(session.local_id, "uid");
+        assert_eq!(session.id_token.expose(), "id");
+        assert_eq!(session.refresh_token.expose(), "refresh");
+        assert!(!session.is_expired());
+
+        firebase.set_credential(session.credential());
+        assert_eq!(
+            firebase.get_uri(),
+            "https://firebase_id.firebaseio.com/?auth=id"
+        );
+
+        match auth
+            .sign_up_with_email_password("taken@example.com", "hunter22")
+            .await
+        {
+            Err(AuthError::Rejected { status, message }) => {
+                assert_eq!(status, 400);
+                assert_eq!(message, "EMAIL_EXISTS");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn session_tokens() {
+        let firebase = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .with_http_client(Toolkit);
+        let session = AuthSession {
+            local_id: String::from("uid"),
+            email: None,
+            id_token: Secret::new("stale"),
+            refresh_token: Secret::new("stale_refresh"),
+            expires_at: SystemTime::now(),
+        };
+        let tokens = firebase.authentication("key").session_tokens(session);
+        firebase.set_token_provider(tokens.clone());
+
+        let uri = firebase.at("users").get::<String>().await.unwrap();
+        assert_eq!(
+            uri,
+            "https://firebase_id.firebaseio.com/users.json?auth=fresh"
+        );
+        assert_eq!(tokens.session().id_token.expose(), "fresh");
+
+        firebase.set_auth("secret");
+        let uri = firebase.at("users").get::<String>().await.unwrap();
+        assert_eq!(
+            uri,
+            "https://firebase_id.firebaseio.com/users.json?auth=secret"
+        );
+    }
+
+    #[tokio::test]
+    async fn anonymous() {
+        let firebase = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .with_http_client(Toolkit);
+
+        let session = firebase
+            .authentication("key")
+            .sign_in_anonymously()
+            .await
+            .unwrap();
+        assert_eq!(session.local_id, "anonymous");
+        assert_eq!(session.email, None);
+    }
+
+    #[tokio::test]
+    async fn custom_token() {
+        let firebase = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .with_http_client(Toolkit);
+
+        let session = firebase
+            .authentication("key")
+            .sign_in_with_custom_token("custom")
+            .await
+            .unwrap();
+        assert_eq!(session.local_id, "minted");
+    }
+
+    #[tokio::test]
+    async fn restore() {
+        let firebase = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .with_http_client(Toolkit);
+        let session = AuthSession::restore("stale_refresh");
+        assert!(session.is_expired());
+
+        let stored = serde_json::to_string(&session).unwrap();
+        let session: AuthSession = serde_json::from_str(&stored).unwrap();
+        let tokens = firebase.authentication("key").session_tokens(session);
+        firebase.set_token_provider(tokens.clone());
+
+        let uri = firebase.at("users").get::<String>().await.unwrap();
+        assert_eq!(
+            uri,
+            "https://firebase_id.firebaseio.com/users.json?auth=fresh"
+        );
+        assert_eq!(tokens.session().local_id, "uid");
+    }
+
+    #[tokio::test]
+    async fn idp() {
+        let firebase = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .with_http_client(Toolkit);
+
+        let session = firebase
+            .authentication("key")
+            .sign_in_with_idp(&IdpCredential::github("gho_token"))
+            .await
+            .unwrap();
+        assert_eq!(session.local_id, "octocat");
+    }
+
+    #[tokio::test]
+    async fn one_refresh_at_a_time() {
+        let client = Arc::new(SlowRefresh::default());
+        let firebase = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone());
+        let tokens = firebase
+            .authentication("key")
+            .session_tokens(AuthSession::restore("refresh"));
+
+        let credentials = futures::future::join_all((0..8).map(|_| tokens.token())).await;
+        assert!(credentials.iter().all(|credential| credential.is_ok()));
+        assert_eq!(client.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn profile() {
+        let firebase = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .with_http_client(Toolkit);
+        let auth = firebase.authentication("key");
+        let session = auth
+            .sign_in_with_email_password("ada@example.com", "hunter22")
+            .await
+            .unwrap();
+
+        let user = auth.get_user_data(&session).await.unwrap();
+        assert!(user.email_verified);
+        assert_eq!(user.display_name.as_deref(), Some("Ada"));
+        assert_eq!(user.provider_user_info[0].provider_id, "password");
+
+        auth.update_profile(&session, Some("Ada Lovelace"), None)
+            .await
+            .unwrap();
+        auth.delete_account(&session).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn session_cookie() {
+        let firebase = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .with_http_client(Toolkit);
+        firebase.set_credential(AuthCredential::AccessToken("ya29.admin".into()));
+
+        let cookie = firebase
+            .create_session_cookie("my-project", "id", Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert_eq!(cookie, "cookie");
+    }
+
+    #[test]
+    fn emulator_urls() {
+        assert_eq!(
+            emulated(IDENTITY_TOOLKIT_URL, Some("localhost:9099")),
+            "http://localhost:9099/identitytoolkit.googleapis.com/v1"
+        );
+        assert_eq!(emulated(SECURE_TOKEN_URL, None), SECURE_TOKEN_URL);
+    }
+}
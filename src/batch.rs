@@ -0,0 +1,317 @@
+use crate::constants::{Method, Response};
+use crate::errors::{RequestError, RequestResult};
+use crate::requestable::Requestable;
+use crate::Firebase;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Requests a [`WriteBatch`] runs at once when it can't fan out.
+pub const DEFAULT_BATCH_PARALLELISM: usize = 8;
+
+#[derive(Debug, Clone)]
+enum WriteOp {
+    Set(Option<Value>),
+    Update(Option<Value>),
+    Delete,
+}
+
+/// Outcome of [`WriteBatch::execute`].
+#[derive(Debug)]
+pub enum BatchResult {
+    /// All writes went out as one atomic PATCH.
+    FanOut(RequestResult<Response>),
+    /// Each write was sent on its own; results are in the order the writes were added.
+    Individual(Vec<RequestResult<Response>>),
+}
+
+/// Writes to several paths collected up front and sent together.
+///
+/// [`commit`](WriteBatch::commit) flattens them into one multi-path PATCH at their deepest common
+/// ancestor, which the server applies atomically. That requires that no written path lies
+/// below another; [`run`](WriteBatch::run) sends each write as its own request instead.
+///
+/// ```
+/// use firebase_rs::Firebase;
+/// use serde_json::json;
+///
+/// # async fn run() {
+/// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+/// let result = firebase
+///     .batch()
+///     .set("posts/POST_ID", &json!({ "title": "Hello" }))
+///     .update("users/USER_ID", &json!({ "posts": 1 }))
+///     .delete("drafts/POST_ID")
+///     .commit()
+///     .await;
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WriteBatch {
+    firebase: Firebase,
+    ops: Vec<(Vec<String>, WriteOp)>,
+    parallelism: usize,
+}
+
+impl WriteBatch {
+    pub fn new(firebase: &Firebase) -> Self {
+        Self {
+            firebase: firebase.clone(),
+            ops: Vec::new(),
+            parallelism: DEFAULT_BATCH_PARALLELISM,
+        }
+    }
+
+    /// Overwrites `path` with `data`.
+    pub fn set<T>(mut self, path: &str, data: &T) -> Self
+    where
+        T: Serialize + ?Sized,
+    {
+        let data = serde_json::to_value(data).ok();
+        self.ops.push((segments(path), WriteOp::Set(data)));
+        self
+    }
+
+    /// Merges the fields of `data`, which must serialize to an object, into `path`.
+    pub fn update<T>(mut self, path: &str, data: &T) -> Self
+    where
+        T: Serialize + ?Sized,
+    {
+        let data = serde_json::to_value(data).ok();
+        self.ops.push((segments(path), WriteOp::Update(data)));
+        self
+    }
+
+    pub fn delete(mut self, path: &str) -> Self {
+        self.ops.push((segments(path), WriteOp::Delete));
+        self
+    }
+
+    /// How many requests [`run`](WriteBatch::run) keeps in flight, at least 1.
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Sends all writes as one atomic PATCH. Fails with [`RequestError::OverlappingPaths`]
+    /// without sending anything when a written path lies below another.
+    pub async fn commit(self) -> RequestResult<Response> {
+        let (ancestor, body) = self.fan_out()?;
+        self.firebase
+            .at(&ancestor)
+            .request(Method::PATCH, Some(Value::Object(body)))
+            .await
+    }
+
+    /// Sends each write as its own request, keeping at most the configured parallelism in
+    /// flight. Writes to overlapping paths race with each other.
+    pub async fn run(self) -> Vec<RequestResult<Response>> {
+        let firebase = &self.firebase;
+        stream::iter(self.ops.iter())
+            .map(|(path, op)| async move {
+                let endpoint = firebase.at(&path.join("/"));
+                match op {
+                    WriteOp::Set(Some(data)) => {
+                        endpoint.request(Method::PUT, Some(data.clone())).await
+                    }
+                    WriteOp::Update(Some(data)) => {
+                        endpoint.request(Method::PATCH, Some(data.clone())).await
+                    }
+                    WriteOp::Delete => endpoint.request(Method::DELETE, None).await,
+                    WriteOp::Set(None) | WriteOp::Update(None) => Err(RequestError::SerializeError),
+                }
+            })
+            .buffered(self.parallelism)
+            .collect()
+            .await
+    }
+
+    /// [`commit`](WriteBatch::commit)s when the paths allow it and falls back to
+    /// [`run`](WriteBatch::run) otherwise.
+    pub async fn execute(self) -> BatchResult {
+        match self.fan_out() {
+            Err(RequestError::OverlappingPaths(_)) => BatchResult::Individual(self.run().await),
+            _ => BatchResult::FanOut(self.commit().await),
+        }
+    }
+
+    /// The deepest common ancestor of all writes and the PATCH body relative to it.
+    fn fan_out(&self) -> RequestResult<(String, Map<String, Value>)> {
+        let mut writes: Vec<(Vec<String>, Value)> = Vec::new();
+        for (path, op) in &self.ops {
+            match op {
+                WriteOp::Set(Some(data)) => writes.push((path.clone(), data.clone())),
+                WriteOp::Delete => writes.push((path.clone(), Value::Null)),
+                WriteOp::Update(Some(Value::Object(fields))) => {
+                    for (key, value) in fields {
+                        let mut child = path.clone();
+                        child.extend(segments(key));
+                        writes.push((child, value.clone()));
+                    }
+                }
+                WriteOp::Set(None) | WriteOp::Update(_) => {
+                    return Err(RequestError::SerializeError)
+                }
+            }
+        }
+
+        let mut paths: Vec<&Vec<String>> = writes.iter().map(|(path, _)| path).collect();
+        paths.sort();
+        for pair in paths.windows(2) {
+            if pair[1].starts_with(pair[0]) {
+                return Err(RequestError::OverlappingPaths(pair[1].join("/")));
+            }
+        }
+        // A write to the root would need an empty key, so it overlaps the whole PATCH.
+        if paths.first().is_some_and(|path| path.is_empty()) {
+            return Err(RequestError::OverlappingPaths(String::new()));
+        }
+
+        // Every key must keep at least one segment, so stop above the shortest path.
+        let depth = match writes.split_first() {
+            Some(((first, _), rest)) => rest.iter().fold(first.len() - 1, |depth, (path, _)| {
+                let common = first.iter().zip(path).take_while(|(a, b)| a == b).count();
+                depth.min(common).min(path.len() - 1)
+            }),
+            None => 0,
+        };
+
+        let ancestor = writes
+            .first()
+            .map(|(path, _)| path[..depth].join("/"))
+            .unwrap_or_default();
+        let body = writes
+            .into_iter()
+            .map(|(path, value)| (path[depth..].join("/"), value))
+            .collect();
+        Ok((ancestor, body))
+    }
+}
+
+fn segments(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+impl Firebase {
+    /// Starts a [`WriteBatch`] against this database.
+    pub fn batch(&self) -> WriteBatch {
+        WriteBatch::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::batch::BatchResult;
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::errors::{RequestError, RequestResult};
+    use crate::hooks::HttpRequest;
+    use crate::Firebase;
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Default)]
+    struct Recording(Mutex<Vec<(String, String, Value)>>);
+
+    #[async_trait]
+    impl HttpClient for Recording {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            let body = serde_json::from_slice(request.body()).unwrap_or(Value::Null);
+            self.0.lock().unwrap().push((
+                request.method().to_string(),
+                request.uri().path().to_string(),
+                body,
+            ));
+            Ok(http::Response::new(b"null".to_vec()))
+        }
+    }
+
+    fn recorded() -> (Arc<Recording>, Firebase) {
+        let client = Arc::new(Recording::default());
+        let firebase = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone());
+        (client, firebase)
+    }
+
+    #[tokio::test]
+    async fn fan_out() {
+        let (client, firebase) = recorded();
+        firebase
+            .batch()
+            .set("/shop/posts/1", &json!({ "title": "Hi" }))
+            .update("shop/users/7", &json!({ "posts": 1, "name": "Ada" }))
+            .delete("shop/drafts/1")
+            .commit()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *client.0.lock().unwrap(),
+            vec![(
+                "PATCH".to_string(),
+                "/shop.json".to_string(),
+                json!({
+                    "posts/1": { "title": "Hi" },
+                    "users/7/posts": 1,
+                    "users/7/name": "Ada",
+                    "drafts/1": null
+                })
+            )]
+        );
+
+        let (client, firebase) = recorded();
+        firebase.batch().set("a/b", &1).commit().await.unwrap();
+        assert_eq!(client.0.lock().unwrap()[0].1, "/a.json");
+    }
+
+    #[tokio::test]
+    async fn overlapping_writes() {
+        let (client, firebase) = recorded();
+        let batch = firebase
+            .batch()
+            .set("users/7", &json!({ "name": "Ada" }))
+            .update("users/7/profile", &json!({ "age": 36 }))
+            .with_parallelism(1);
+
+        assert!(matches!(
+            batch.clone().commit().await,
+            Err(RequestError::OverlappingPaths(path)) if path == "users/7/profile/age"
+        ));
+        assert!(client.0.lock().unwrap().is_empty());
+
+        match batch.execute().await {
+            BatchResult::Individual(results) => {
+                assert_eq!(results.len(), 2);
+                assert!(results.iter().all(Result::is_ok));
+            }
+            BatchResult::FanOut(_) => panic!("overlapping writes can't fan out"),
+        }
+        assert_eq!(
+            *client.0.lock().unwrap(),
+            vec![
+                (
+                    "PUT".to_string(),
+                    "/users/7.json".to_string(),
+                    json!({ "name": "Ada" })
+                ),
+                (
+                    "PATCH".to_string(),
+                    "/users/7/profile.json".to_string(),
+                    json!({ "age": 36 })
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use crate::errors::RequestResult;
+use crate::json::from_json;
+use crate::requestable::Requestable;
+use crate::Firebase;
+use futures::stream::{self, StreamExt};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+
+impl Firebase {
+    /// Reads every path in `paths`, at most `max_concurrency` at a time, and collects the
+    /// outcome of each by path. One failing read does not affect the others. Missing nodes decode
+    /// from `null`, so read them as `Option<T>` when some paths may not exist.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, RequestableExt};
+    /// use serde_json::Value;
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::new("https://hacker-news.firebaseio.com").unwrap();
+    /// if let Ok(ids) = firebase.at("v0").at("topstories").get_opt::<Vec<u64>>().await {
+    ///     let paths = ids.unwrap_or_default().iter().map(|id| format!("v0/item/{}", id)).collect::<Vec<_>>();
+    ///     let items = firebase.get_many::<Option<Value>, _>(paths, 16).await;
+    /// }
+    /// # }
+    /// ```
+    pub async fn get_many<T, I>(
+        &self,
+        paths: I,
+        max_concurrency: usize,
+    ) -> HashMap<String, RequestResult<T>>
+    where
+        T: DeserializeOwned,
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        stream::iter(paths)
+            .map(|path| {
+                let path = path.into();
+                let endpoint = self.at(&path);
+                async move {
+                    let result = match endpoint.get_raw().await {
+                        Ok(body) => from_json(body.as_bytes()),
+                        Err(err) => Err(err),
+                    };
+                    (path, result)
+                }
+            })
+            .buffer_unordered(max_concurrency.max(1))
+            .collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::errors::{RequestError, RequestResult};
+    use crate::hooks::HttpRequest;
+    use crate::Firebase;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Echoes the item number back and tracks how many requests are in flight at once.
+    #[derive(Debug, Default)]
+    struct Items {
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl HttpClient for Items {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let path = request.uri().path().to_string();
+            let id = path.trim_start_matches("/item/").trim_end_matches(".json");
+            let body = match id {
+                "missing" => "null".to_string(),
+                "broken" => "{".to_string(),
+                id => id.to_string(),
+            };
+            Ok(http::Response::new(body.into_bytes()))
+        }
+    }
+
+    #[tokio::test]
+    async fn bounded_reads() {
+        let client = std::sync::Arc::new(Items::default());
+        let firebase = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone());
+
+        let mut paths: Vec<String> = (0..10).map(|id| format!("item/{}", id)).collect();
+        paths.push("item/missing".to_string());
+        paths.push("item/broken".to_string());
+        let items = firebase.get_many::<Option<u32>, _>(paths, 3).await;
+
+        assert_eq!(items.len(), 12);
+        assert_eq!(items["item/7"].as_ref().unwrap(), &Some(7));
+        assert_eq!(items["item/missing"].as_ref().unwrap(), &None);
+        assert!(matches!(
+            items["item/broken"],
+            Err(RequestError::Decode { .. })
+        ));
+        assert_eq!(client.peak.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::errors::RequestResult;
+use crate::listener::Listener;
+use crate::mirror::apply_event;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// Child-level change under the listened node, as the client SDKs report them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChildEvent {
+    Added(String, Value),
+    Changed(String, Value),
+    /// Carries the value the child had before it was removed.
+    Removed(String, Value),
+    /// A child whose new value moved it to another position in the chosen [`ChildOrder`].
+    Moved(String, Value),
+}
+
+/// Sort order used to detect [`ChildEvent::Moved`], matching the query's `orderBy`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ChildOrder {
+    #[default]
+    Key,
+    Value,
+    Child(String),
+}
+
+impl ChildOrder {
+    fn compare(&self, a: (&str, &Value), b: (&str, &Value)) -> Ordering {
+        let by_value = match self {
+            ChildOrder::Key => Ordering::Equal,
+            ChildOrder::Value => compare_values(a.1, b.1),
+            ChildOrder::Child(child) => {
+                let a = a.1.get(child).unwrap_or(&Value::Null);
+                let b = b.1.get(child).unwrap_or(&Value::Null);
+                compare_values(a, b)
+            }
+        };
+        by_value.then_with(|| compare_keys(a.0, b.0))
+    }
+}
+
+/// Integer keys sort numerically before all other keys, which sort lexicographically.
+pub(crate) fn compare_keys(a: &str, b: &str) -> Ordering {
+    match (a.parse::<i64>(), b.parse::<i64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+/// `null` < `false` < `true` < numbers < strings < objects.
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) | Value::Object(_) => 5,
+        }
+    }
+
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Lists the child events that turn `before` into `after`: removals first, then additions,
+/// changes and moves.
+///
+/// ```
+/// use firebase_rs::{diff_children, ChildEvent, ChildOrder};
+/// use serde_json::json;
+///
+/// let events = diff_children(&json!({ "a": 1, "b": 2 }), &json!({ "b": 3, "c": 4 }), &ChildOrder::Key);
+/// assert_eq!(events, vec![
+///     ChildEvent::Removed("a".to_string(), json!(1)),
+///     ChildEvent::Added("c".to_string(), json!(4)),
+///     ChildEvent::Changed("b".to_string(), json!(3)),
+/// ]);
+/// ```
+pub fn diff_children(before: &Value, after: &Value, order: &ChildOrder) -> Vec<ChildEvent> {
+    let empty = serde_json::Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+
+    let mut events = Vec::new();
+    for (key, value) in before {
+        if !after.contains_key(key) {
+            events.push(ChildEvent::Removed(key.clone(), value.clone()));
+        }
+    }
+    for (key, value) in after {
+        if !before.contains_key(key) {
+            events.push(ChildEvent::Added(key.clone(), value.clone()));
+        }
+    }
+
+    let kept: Vec<&str> = after
+        .keys()
+        .filter(|key| before.contains_key(*key))
+        .map(String::as_str)
+        .collect();
+    let sorted = |children: &serde_json::Map<String, Value>| {
+        let mut keys = kept.clone();
+        keys.sort_by(|a, b| order.compare((a, &children[*a]), (b, &children[*b])));
+        keys
+    };
+    let old_order = sorted(before);
+    let new_order = sorted(after);
+
+    let mut moved = Vec::new();
+    for (index, key) in new_order.iter().enumerate() {
+        let (old, new) = (&before[*key], &after[*key]);
+        if old == new {
+            continue;
+        }
+        events.push(ChildEvent::Changed(key.to_string(), new.clone()));
+        if old_order.get(index) != Some(key) {
+            moved.push(ChildEvent::Moved(key.to_string(), new.clone()));
+        }
+    }
+    events.extend(moved);
+
+    events
+}
+
+impl Listener {
+    /// Turns the listener into a stream of [`ChildEvent`]s by tracking the node locally.
+    ///
+    /// ```
+    /// use firebase_rs::{ChildEvent, ChildOrder, Firebase};
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run() {
+    /// let messages = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("messages");
+    /// let mut events = messages.listener().child_events(ChildOrder::Key);
+    /// while let Some(Ok(event)) = events.next().await {
+    ///     if let ChildEvent::Added(key, message) = event {
+    ///         println!("{}: {}", key, message);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn child_events(self, order: ChildOrder) -> BoxStream<'static, RequestResult<ChildEvent>> {
+        let mut node = Value::Null;
+        let events = self.start().flat_map(move |event| {
+            let changes = match event {
+                Ok(event) => {
+                    let before = node.clone();
+                    apply_event(&mut node, &event);
+                    diff_children(&before, &node, &order)
+                        .into_iter()
+                        .map(Ok)
+                        .collect()
+                }
+                Err(err) => vec![Err(err)],
+            };
+            futures::stream::iter(changes)
+        });
+
+        Box::pin(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::children::{diff_children, ChildEvent, ChildOrder};
+    use serde_json::json;
+
+    #[test]
+    fn moves_follow_the_order() {
+        let before = json!({ "a": { "score": 1 }, "b": { "score": 2 }, "c": { "score": 3 } });
+        let after = json!({ "a": { "score": 5 }, "b": { "score": 2 }, "c": { "score": 3 } });
+
+        let order = ChildOrder::Child("score".to_string());
+        assert_eq!(
+            diff_children(&before, &after, &order),
+            vec![
+                ChildEvent::Changed("a".to_string(), json!({ "score": 5 })),
+                ChildEvent::Moved("a".to_string(), json!({ "score": 5 })),
+            ]
+        );
+        assert_eq!(
+            diff_children(&before, &after, &ChildOrder::Key),
+            vec![ChildEvent::Changed("a".to_string(), json!({ "score": 5 }))]
+        );
+    }
+
+    #[test]
+    fn key_order() {
+        let before = json!({ "10": 1, "9": 1, "a": 1 });
+        let after = json!({ "10": 1, "9": 1, "a": 1, "2": 0 });
+        assert_eq!(
+            diff_children(&before, &after, &ChildOrder::Value),
+            vec![ChildEvent::Added("2".to_string(), json!(0))]
+        );
+        assert_eq!(
+            ChildOrder::Key.compare(("9", &json!(null)), ("10", &json!(null))),
+            std::cmp::Ordering::Less
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use crate::errors::RequestResult;
+use crate::hooks::HttpRequest;
+use async_trait::async_trait;
+use futures::future::{self, Either};
+use futures::stream::{self, BoxStream};
+#[cfg(feature = "reqwest")]
+pub use reqwest_client::{ReqwestClient, ReqwestOptions};
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "wasi")]
+pub use wasi_client::{WasiClient, WasiOptions};
+#[cfg(all(feature = "workers", target_arch = "wasm32"))]
+pub use workers_client::WorkersClient;
+
+#[cfg(feature = "reqwest")]
+mod reqwest_client;
+#[cfg(feature = "wasi")]
+mod wasi_client;
+#[cfg(all(feature = "workers", target_arch = "wasm32"))]
+mod workers_client;
+
+pub type HttpResponse = http::Response<Vec<u8>>;
+
+/// Response body delivered chunk by chunk as it arrives.
+pub type BodyStream = BoxStream<'static, RequestResult<Vec<u8>>>;
+
+pub type StreamingResponse = http::Response<BodyStream>;
+
+/// Transport that sends finalized requests. Implementations should honor the [`Timeout`]
+/// extension and may read their own backend-specific extensions from
+/// [`http::Request::extensions`].
+#[async_trait]
+pub trait HttpClient: Debug + Send + Sync {
+    async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse>;
+
+    /// Like [`execute`](HttpClient::execute) but hands out the body without buffering it.
+    /// Backends that can't stream return the whole body as a single chunk.
+    async fn execute_streaming(&self, request: HttpRequest) -> RequestResult<StreamingResponse> {
+        let (parts, body) = self.execute(request).await?.into_parts();
+        let body: BodyStream = Box::pin(stream::once(async move { Ok(body) }));
+        Ok(http::Response::from_parts(parts, body))
+    }
+}
+
+#[async_trait]
+impl<C> HttpClient for Arc<C>
+where
+    C: HttpClient + ?Sized,
+{
+    async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+        (**self).execute(request).await
+    }
+
+    async fn execute_streaming(&self, request: HttpRequest) -> RequestResult<StreamingResponse> {
+        (**self).execute_streaming(request).await
+    }
+}
+
+/// Portable upper bound for a whole request, set from [`Firebase::with_timeout`](crate::Firebase::with_timeout).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout(pub Duration);
+
+/// Transport used by [`Firebase::new`](crate::Firebase::new): reqwest by default, otherwise WASI
+/// HTTP or Cloudflare Workers `fetch`, whichever feature is enabled.
+#[cfg(feature = "reqwest")]
+pub(crate) fn default_client() -> Arc<dyn HttpClient> {
+    Arc::new(ReqwestClient::default())
+}
+
+#[cfg(all(feature = "wasi", not(feature = "reqwest")))]
+pub(crate) fn default_client() -> Arc<dyn HttpClient> {
+    Arc::new(WasiClient)
+}
+
+#[cfg(all(
+    feature = "workers",
+    target_arch = "wasm32",
+    not(any(feature = "reqwest", feature = "wasi"))
+))]
+pub(crate) fn default_client() -> Arc<dyn HttpClient> {
+    Arc::new(WorkersClient)
+}
+
+/// Waits for `duration` on the timer of the runtime the default backend runs on, for reconnect
+/// delays and heartbeats.
+#[cfg(feature = "reqwest")]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+#[cfg(all(feature = "wasi", not(feature = "reqwest")))]
+pub(crate) use wasi_client::sleep;
+
+#[cfg(all(
+    feature = "workers",
+    target_arch = "wasm32",
+    not(any(feature = "reqwest", feature = "wasi"))
+))]
+pub(crate) use workers_client::sleep;
+
+/// Runs `future` for at most `duration`, or `None` if it didn't finish in time.
+pub(crate) async fn timeout<F>(duration: Duration, future: F) -> Option<F::Output>
+where
+    F: Future,
+{
+    let sleep = sleep(duration);
+    futures::pin_mut!(future, sleep);
+    match future::select(future, sleep).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+#[cfg(not(any(feature = "reqwest", feature = "wasi", feature = "workers")))]
+compile_error!(
+    "firebase-rs needs an HTTP backend: enable the `reqwest`, `wasi` or `workers` feature"
+);
+
+#[cfg(all(
+    feature = "workers",
+    not(target_arch = "wasm32"),
+    not(any(feature = "reqwest", feature = "wasi"))
+))]
+compile_error!("the `workers` backend only builds for wasm32 targets");
@@ -0,0 +1,171 @@
+use crate::clients::{BodyStream, HttpClient, HttpResponse, StreamingResponse, Timeout};
+use crate::errors::{RequestError, RequestResult};
+use crate::hooks::HttpRequest;
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Per-request options only [`ReqwestClient`] understands, attached as a request extension.
+/// Client-wide settings such as the local address belong on the `reqwest::Client` passed to
+/// [`ReqwestClient::from`].
+///
+/// ```
+/// use std::time::Duration;
+/// use firebase_rs::{Firebase, ReqwestOptions};
+///
+/// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_request_hook(|request| {
+///     request.extensions_mut().insert(ReqwestOptions {
+///         timeout: Some(Duration::from_secs(5)),
+///         version: Some(http::Version::HTTP_2),
+///     });
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReqwestOptions {
+    pub timeout: Option<Duration>,
+    pub version: Option<http::Version>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestClient {
+    client: reqwest::Client,
+}
+
+impl From<reqwest::Client> for ReqwestClient {
+    fn from(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+// reqwest errors name the URL they failed on, query and all, so they are stored without it to
+// keep `auth=` and `access_token=` out of logged errors.
+impl ReqwestClient {
+    fn convert(request: HttpRequest) -> RequestResult<reqwest::Request> {
+        let timeout = request
+            .extensions()
+            .get::<Timeout>()
+            .map(|timeout| timeout.0);
+        let options = request
+            .extensions()
+            .get::<ReqwestOptions>()
+            .copied()
+            .unwrap_or_default();
+
+        let mut request = match reqwest::Request::try_from(request) {
+            Ok(request) => request,
+            Err(err) => return Err(RequestError::transport(err.without_url())),
+        };
+        *request.timeout_mut() = options.timeout.or(timeout);
+        if let Some(version) = options.version {
+            *request.version_mut() = version;
+        }
+
+        Ok(request)
+    }
+
+    async fn send(
+        &self,
+        request: HttpRequest,
+    ) -> RequestResult<(http::response::Builder, reqwest::Response)> {
+        let request = Self::convert(request)?;
+        let response = match self.client.execute(request).await {
+            Ok(response) => response,
+            Err(err) => return Err(RequestError::transport(err.without_url())),
+        };
+
+        let mut builder = http::Response::builder()
+            .status(response.status())
+            .version(response.version());
+        for (name, value) in response.headers() {
+            builder = builder.header(name, value);
+        }
+
+        Ok((builder, response))
+    }
+}
+
+#[async_trait]
+impl HttpClient for ReqwestClient {
+    async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+        let (builder, response) = self.send(request).await?;
+        let body = match response.bytes().await {
+            Ok(body) => body.to_vec(),
+            Err(err) => return Err(RequestError::transport(err.without_url())),
+        };
+
+        match builder.body(body) {
+            Ok(response) => Ok(response),
+            Err(err) => Err(RequestError::transport(err)),
+        }
+    }
+
+    async fn execute_streaming(&self, request: HttpRequest) -> RequestResult<StreamingResponse> {
+        let (builder, response) = self.send(request).await?;
+        let body: BodyStream = Box::pin(futures::stream::unfold(
+            Some(response),
+            |response| async move {
+                let mut response = response?;
+                match response.chunk().await {
+                    Ok(Some(chunk)) => Some((Ok(chunk.to_vec()), Some(response))),
+                    Ok(None) => None,
+                    Err(err) => Some((Err(RequestError::transport(err.without_url())), None)),
+                }
+            },
+        ));
+
+        match builder.body(body) {
+            Ok(response) => Ok(response),
+            Err(err) => Err(RequestError::transport(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{ReqwestClient, ReqwestOptions, Timeout};
+    use crate::requestable::Requestable;
+    use crate::{AuthCredential, Firebase};
+    use std::time::Duration;
+
+    #[test]
+    fn reqwest_options() {
+        let request = || {
+            let mut request = http::Request::builder()
+                .uri("https://firebase_id.firebaseio.com/users.json")
+                .body(Vec::new())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(Timeout(Duration::from_secs(30)));
+            request
+        };
+
+        let converted = ReqwestClient::convert(request()).unwrap();
+        assert_eq!(converted.timeout(), Some(&Duration::from_secs(30)));
+
+        let mut request = request();
+        request.extensions_mut().insert(ReqwestOptions {
+            timeout: Some(Duration::from_secs(5)),
+            version: Some(http::Version::HTTP_2),
+        });
+        let converted = ReqwestClient::convert(request).unwrap();
+        assert_eq!(converted.timeout(), Some(&Duration::from_secs(5)));
+        assert_eq!(converted.version(), http::Version::HTTP_2);
+    }
+
+    #[tokio::test]
+    async fn transport_errors_hide_credentials() {
+        let firebase = Firebase::auth("https://127.0.0.1:1", "DATABASE_SECRET").unwrap();
+        let err = firebase.at("users").get_raw().await.unwrap_err();
+        for rendered in [err.to_string(), format!("{:?}", err)] {
+            assert!(!rendered.contains("auth="), "{}", rendered);
+            assert!(!rendered.contains("DATABASE_SECRET"), "{}", rendered);
+        }
+
+        firebase.set_credential(AuthCredential::AccessToken("ACCESS_TOKEN".into()));
+        let err = firebase.at("users").get_raw().await.unwrap_err();
+        for rendered in [err.to_string(), format!("{:?}", err)] {
+            assert!(!rendered.contains("access_token="), "{}", rendered);
+            assert!(!rendered.contains("ACCESS_TOKEN"), "{}", rendered);
+        }
+    }
+}
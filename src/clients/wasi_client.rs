@@ -0,0 +1,154 @@
+use crate::clients::{HttpClient, HttpResponse, Timeout};
+use crate::errors::{RequestError, RequestResult};
+use crate::hooks::HttpRequest;
+use async_trait::async_trait;
+use std::time::Duration;
+use wasi::clocks::monotonic_clock;
+use wasi::http::outgoing_handler;
+use wasi::http::types::{Fields, Method, OutgoingBody, OutgoingRequest, RequestOptions, Scheme};
+use wasi::io::streams::StreamError;
+
+/// Largest chunk `blocking-write-and-flush` accepts in one call.
+const WRITE_CHUNK: usize = 4096;
+const READ_CHUNK: u64 = 64 * 1024;
+
+/// Per-request timeouts of the `wasi:http` outgoing handler, attached as a request extension.
+/// They take precedence over [`Timeout`], which bounds connecting and the first response byte.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WasiOptions {
+    pub connect_timeout: Option<Duration>,
+    pub first_byte_timeout: Option<Duration>,
+    pub between_bytes_timeout: Option<Duration>,
+}
+
+/// Transport for the WASI 0.2 HTTP world (`wasi:http/outgoing-handler`), for components running
+/// on `wasm32-wasip2` runtimes such as Wasmtime or Spin. Requests block the component until the
+/// response body has been read.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WasiClient;
+
+impl WasiClient {
+    fn send(request: HttpRequest) -> RequestResult<HttpResponse> {
+        let timeout = request
+            .extensions()
+            .get::<Timeout>()
+            .map(|timeout| timeout.0);
+        let options = request
+            .extensions()
+            .get::<WasiOptions>()
+            .copied()
+            .unwrap_or_default();
+        let (parts, body) = request.into_parts();
+
+        let headers = Fields::new();
+        for (name, value) in parts.headers.iter() {
+            if let Err(err) = headers.append(name.as_str(), value.as_bytes()) {
+                return Err(RequestError::transport(err));
+            }
+        }
+
+        let outgoing = OutgoingRequest::new(headers);
+        let method = match parts.method.as_str() {
+            "GET" => Method::Get,
+            "HEAD" => Method::Head,
+            "POST" => Method::Post,
+            "PUT" => Method::Put,
+            "DELETE" => Method::Delete,
+            "PATCH" => Method::Patch,
+            other => Method::Other(other.to_string()),
+        };
+        let scheme = match parts.uri.scheme_str() {
+            Some("http") => Scheme::Http,
+            _ => Scheme::Https,
+        };
+        let path = parts.uri.path_and_query().map(|path| path.as_str());
+        let authority = parts.uri.authority().map(|authority| authority.as_str());
+        if outgoing.set_method(&method).is_err()
+            || outgoing.set_scheme(Some(&scheme)).is_err()
+            || outgoing.set_authority(authority).is_err()
+            || outgoing.set_path_with_query(path).is_err()
+        {
+            return Err(RequestError::NetworkError);
+        }
+
+        let request_options = RequestOptions::new();
+        let nanos = |duration: Duration| duration.as_nanos().min(u64::MAX as u128) as u64;
+        // Unsupported timeouts are reported as errors by the host and are safe to ignore.
+        let _ = request_options.set_connect_timeout(options.connect_timeout.or(timeout).map(nanos));
+        let _ = request_options
+            .set_first_byte_timeout(options.first_byte_timeout.or(timeout).map(nanos));
+        let _ = request_options.set_between_bytes_timeout(options.between_bytes_timeout.map(nanos));
+
+        let outgoing_body = match outgoing.body() {
+            Ok(body) => body,
+            Err(_) => return Err(RequestError::NetworkError),
+        };
+        let pending = match outgoing_handler::handle(outgoing, Some(request_options)) {
+            Ok(pending) => pending,
+            Err(err) => return Err(RequestError::transport(err)),
+        };
+        {
+            let stream = match outgoing_body.write() {
+                Ok(stream) => stream,
+                Err(_) => return Err(RequestError::NetworkError),
+            };
+            for chunk in body.chunks(WRITE_CHUNK) {
+                if let Err(err) = stream.blocking_write_and_flush(chunk) {
+                    return Err(RequestError::transport(err));
+                }
+            }
+        }
+        if let Err(err) = OutgoingBody::finish(outgoing_body, None) {
+            return Err(RequestError::transport(err));
+        }
+
+        pending.subscribe().block();
+        let incoming = match pending.get() {
+            Some(Ok(Ok(incoming))) => incoming,
+            Some(Ok(Err(err))) => return Err(RequestError::transport(err)),
+            _ => return Err(RequestError::NetworkError),
+        };
+
+        let mut builder = http::Response::builder().status(incoming.status());
+        for (name, value) in incoming.headers().entries() {
+            builder = builder.header(name, value);
+        }
+
+        let mut data = Vec::new();
+        {
+            let incoming_body = match incoming.consume() {
+                Ok(body) => body,
+                Err(_) => return Err(RequestError::NetworkError),
+            };
+            let stream = match incoming_body.stream() {
+                Ok(stream) => stream,
+                Err(_) => return Err(RequestError::NetworkError),
+            };
+            loop {
+                match stream.blocking_read(READ_CHUNK) {
+                    Ok(chunk) => data.extend_from_slice(&chunk),
+                    Err(StreamError::Closed) => break,
+                    Err(err) => return Err(RequestError::transport(err)),
+                }
+            }
+        }
+
+        match builder.body(data) {
+            Ok(response) => Ok(response),
+            Err(err) => Err(RequestError::transport(err)),
+        }
+    }
+}
+
+/// Blocks the component for `duration`, like the requests themselves.
+pub(crate) async fn sleep(duration: Duration) {
+    let nanos = duration.as_nanos().min(u64::MAX as u128) as u64;
+    monotonic_clock::subscribe_duration(nanos).block();
+}
+
+#[async_trait]
+impl HttpClient for WasiClient {
+    async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+        Self::send(request)
+    }
+}
@@ -0,0 +1,126 @@
+use crate::clients::{BodyStream, HttpClient, HttpResponse, StreamingResponse, Timeout};
+use crate::errors::{RequestError, RequestResult};
+use crate::hooks::HttpRequest;
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use std::time::Duration;
+use worker::js_sys::Uint8Array;
+use worker::send::{SendFuture, SendWrapper};
+use worker::{AbortSignal, Delay, Fetch, Headers, Request, RequestInit, Response};
+
+/// Transport for Cloudflare Workers, built on [`worker::Fetch`].
+/// [`execute_streaming`](HttpClient::execute_streaming) reads the response body as a
+/// [`worker::ByteStream`]; [`execute`](HttpClient::execute) buffers it.
+///
+/// The `worker` types are not `Send`, which [`HttpClient`] asks of its futures and body
+/// streams; they are wrapped in [`SendFuture`] and [`SendWrapper`], which is sound because a
+/// Workers isolate runs the module on a single thread.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WorkersClient;
+
+/// [`worker::Error`] may hold a JS value, which cannot cross into a `Send` error, so only its
+/// message is kept.
+fn transport(err: worker::Error) -> RequestError {
+    RequestError::transport(std::io::Error::other(err.to_string()))
+}
+
+/// Sends `request` and returns the response with its status and headers copied into a builder.
+async fn send(request: HttpRequest) -> RequestResult<(http::response::Builder, Response)> {
+    let timeout = request
+        .extensions()
+        .get::<Timeout>()
+        .map(|timeout| timeout.0);
+    let (parts, body) = request.into_parts();
+
+    let headers = Headers::new();
+    for (name, value) in parts.headers.iter() {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => return Err(RequestError::NetworkError),
+        };
+        if let Err(err) = headers.append(name.as_str(), value) {
+            return Err(transport(err));
+        }
+    }
+
+    let mut init = RequestInit::new();
+    init.with_method(parts.method.as_str().to_string().into())
+        .with_headers(headers);
+    if !body.is_empty() {
+        init.with_body(Some(Uint8Array::from(body.as_slice()).into()));
+    }
+
+    let request = match Request::new_with_init(&parts.uri.to_string(), &init) {
+        Ok(request) => Fetch::Request(request),
+        Err(err) => return Err(transport(err)),
+    };
+    let response = match timeout {
+        Some(timeout) => {
+            let millis = timeout.as_millis().min(u32::MAX as u128) as u32;
+            let signal = AbortSignal::from(worker::web_sys::AbortSignal::timeout_with_u32(millis));
+            request.send_with_signal(&signal).await
+        }
+        None => request.send().await,
+    };
+    let response = match response {
+        Ok(response) => response,
+        Err(err) => return Err(transport(err)),
+    };
+
+    let mut builder = http::Response::builder().status(response.status_code());
+    for (name, value) in response.headers().entries() {
+        builder = builder.header(name, value);
+    }
+
+    Ok((builder, response))
+}
+
+async fn fetch(request: HttpRequest) -> RequestResult<HttpResponse> {
+    let (builder, mut response) = send(request).await?;
+    let data = match response.bytes().await {
+        Ok(data) => data,
+        Err(err) => return Err(transport(err)),
+    };
+
+    match builder.body(data) {
+        Ok(response) => Ok(response),
+        Err(_) => Err(RequestError::NetworkError),
+    }
+}
+
+async fn fetch_streaming(request: HttpRequest) -> RequestResult<StreamingResponse> {
+    let (builder, mut response) = send(request).await?;
+    // Bodyless responses such as 204 have no stream at all.
+    let chunks = response.stream().ok().map(SendWrapper::new);
+
+    let body: BodyStream = Box::pin(stream::unfold(chunks, |chunks| {
+        SendFuture::new(async move {
+            let mut chunks = chunks?;
+            match chunks.next().await? {
+                Ok(chunk) => Some((Ok(chunk), Some(chunks))),
+                Err(err) => Some((Err(transport(err)), None)),
+            }
+        })
+    }));
+
+    match builder.body(body) {
+        Ok(response) => Ok(response),
+        Err(_) => Err(RequestError::NetworkError),
+    }
+}
+
+/// Resolves after `duration` through [`worker::Delay`].
+pub(crate) async fn sleep(duration: Duration) {
+    SendFuture::new(Delay::from(duration)).await
+}
+
+#[async_trait]
+impl HttpClient for WorkersClient {
+    async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+        SendFuture::new(fetch(request)).await
+    }
+
+    async fn execute_streaming(&self, request: HttpRequest) -> RequestResult<StreamingResponse> {
+        SendFuture::new(fetch_streaming(request)).await
+    }
+}
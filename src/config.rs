@@ -0,0 +1,119 @@
+use crate::credential::AuthCredential;
+use crate::errors::UrlParseResult;
+use crate::secret::Secret;
+use crate::Firebase;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Everything needed to rebuild a [`Firebase`] instance, in a form that can live in a config file.
+///
+/// ```
+/// use firebase_rs::FirebaseConfig;
+///
+/// let config: FirebaseConfig = serde_json::from_str(r#"{
+///     "url": "https://myfirebase.firebaseio.com",
+///     "auth": { "mode": "database_secret", "secret": "my_auth_key" },
+///     "options": { "timeout_secs": 30 }
+/// }"#).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirebaseConfig {
+    pub url: String,
+    #[serde(default)]
+    pub auth: AuthMode,
+    #[serde(default)]
+    pub options: FirebaseOptions,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", content = "secret", rename_all = "snake_case")]
+pub enum AuthMode {
+    #[default]
+    None,
+    DatabaseSecret(Secret),
+    IdToken(Secret),
+    AccessToken(Secret),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirebaseOptions {
+    /// Upper bound for a whole request, from connecting until the body has been read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl AuthMode {
+    fn credential(&self) -> Option<AuthCredential> {
+        match self {
+            AuthMode::None => None,
+            AuthMode::DatabaseSecret(secret) => {
+                Some(AuthCredential::DatabaseSecret(secret.clone()))
+            }
+            AuthMode::IdToken(token) => Some(AuthCredential::IdToken(token.clone())),
+            AuthMode::AccessToken(token) => Some(AuthCredential::AccessToken(token.clone())),
+        }
+    }
+}
+
+impl Firebase {
+    /// ```
+    /// use firebase_rs::{AuthMode, Firebase, FirebaseConfig, FirebaseOptions};
+    ///
+    /// let config = FirebaseConfig {
+    ///     url: "https://myfirebase.firebaseio.com".to_string(),
+    ///     auth: AuthMode::DatabaseSecret("my_auth_key".into()),
+    ///     options: FirebaseOptions::default(),
+    /// };
+    /// let firebase = Firebase::from_config(&config).unwrap();
+    /// ```
+    pub fn from_config(config: &FirebaseConfig) -> UrlParseResult<Self> {
+        let mut firebase = Firebase::new(&config.url)?;
+        if let Some(credential) = config.auth.credential() {
+            firebase.set_credential(credential);
+        }
+        if let Some(secs) = config.options.timeout_secs {
+            firebase = firebase.with_timeout(Duration::from_secs(secs));
+        }
+
+        Ok(firebase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{AuthMode, FirebaseConfig, FirebaseOptions};
+    use crate::Firebase;
+
+    #[test]
+    fn round_trip() {
+        let config = FirebaseConfig {
+            url: "https://firebase_id.firebaseio.com".to_string(),
+            auth: AuthMode::IdToken("id_token".into()),
+            options: FirebaseOptions {
+                timeout_secs: Some(10),
+            },
+        };
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            r#"{"url":"https://firebase_id.firebaseio.com","auth":{"mode":"id_token","secret":"id_token"},"options":{"timeout_secs":10}}"#
+        );
+        let restored: FirebaseConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, config);
+
+        let firebase = Firebase::from_config(&restored).unwrap();
+        assert_eq!(
+            firebase.get_uri(),
+            "https://firebase_id.firebaseio.com/?auth=id_token"
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        let config: FirebaseConfig =
+            serde_json::from_str(r#"{ "url": "https://firebase_id.firebaseio.com" }"#).unwrap();
+        assert_eq!(config.auth, AuthMode::None);
+        assert_eq!(config.options, FirebaseOptions::default());
+    }
+}
@@ -1,27 +1,65 @@
+use serde::{Deserialize, Serialize};
+
 pub const AUTH: &str = "auth";
+pub const ACCESS_TOKEN: &str = "access_token";
 pub const ORDER_BY: &str = "orderBy";
 pub const LIMIT_TO_FIRST: &str = "limitToFirst";
 pub const LIMIT_TO_LAST: &str = "limitToLast";
 pub const START_AT: &str = "startAt";
 pub const END_AT: &str = "endAt";
+pub const START_AFTER: &str = "startAfter";
+pub const END_BEFORE: &str = "endBefore";
 pub const EQUAL_TO: &str = "equalTo";
 pub const SHALLOW: &str = "shallow";
+pub const TIMEOUT: &str = "timeout";
+pub const WRITE_SIZE_LIMIT: &str = "writeSizeLimit";
+pub const DOWNLOAD: &str = "download";
 pub const FORMAT: &str = "format";
 pub const EXPORT: &str = "export";
+pub const PRINT: &str = "print";
+pub const PRETTY: &str = "pretty";
+pub const SILENT: &str = "silent";
+pub const SERVER_VALUE: &str = ".sv";
+pub const TIMESTAMP: &str = "timestamp";
+pub const SETTINGS: &str = ".settings";
+pub const SECRETS: &str = "secrets";
+pub const REDACTED: &str = "***";
+pub const METHOD_OVERRIDE: &str = "x-http-method-override";
 
-#[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Method {
     GET,
     POST,
+    PUT,
     DELETE,
     PATCH,
 }
 
-#[derive(Debug)]
+impl Method {
+    pub fn as_http(&self) -> http::Method {
+        match self {
+            Method::GET => http::Method::GET,
+            Method::POST => http::Method::POST,
+            Method::PUT => http::Method::PUT,
+            Method::DELETE => http::Method::DELETE,
+            Method::PATCH => http::Method::PATCH,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct Response {
     pub data: String,
 }
 
+/// Body of a successful push: the key the server generated for the new child.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushResponse {
+    #[serde(rename = "name")]
+    pub key: String,
+}
+
 impl Response {
     pub fn new() -> Self {
         Self {
@@ -0,0 +1,123 @@
+use crate::clients::HttpClient;
+use crate::constants::{ACCESS_TOKEN, AUTH, REDACTED};
+use crate::errors::RequestResult;
+use crate::secret::Secret;
+use crate::token_provider::TokenProvider;
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AuthCredential {
+    DatabaseSecret(Secret),
+    IdToken(Secret),
+    AccessToken(Secret),
+}
+
+impl AuthCredential {
+    /// ```
+    /// use firebase_rs::AuthCredential;
+    ///
+    /// let credential = AuthCredential::AccessToken("ya29.token".into());
+    /// assert_eq!(credential.query_param(), ("access_token", "ya29.token"));
+    /// ```
+    pub fn query_param(&self) -> (&'static str, &str) {
+        match self {
+            AuthCredential::DatabaseSecret(secret) => (AUTH, secret.expose()),
+            AuthCredential::IdToken(token) => (AUTH, token.expose()),
+            AuthCredential::AccessToken(token) => (ACCESS_TOKEN, token.expose()),
+        }
+    }
+
+    /// Like [`query_param`](AuthCredential::query_param) with the value masked, for logging.
+    ///
+    /// ```
+    /// use firebase_rs::AuthCredential;
+    ///
+    /// let credential = AuthCredential::AccessToken("ya29.token".into());
+    /// assert_eq!(credential.redacted_query_param(), ("access_token", "***"));
+    /// ```
+    pub fn redacted_query_param(&self) -> (&'static str, &'static str) {
+        (self.query_param().0, REDACTED)
+    }
+}
+
+#[derive(Debug, Default)]
+struct AuthState {
+    credential: Option<AuthCredential>,
+    provider: Option<Arc<dyn TokenProvider>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SharedAuth {
+    state: Arc<RwLock<AuthState>>,
+}
+
+impl SharedAuth {
+    pub fn new(credential: Option<AuthCredential>) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(AuthState {
+                credential,
+                provider: None,
+            })),
+        }
+    }
+
+    pub fn get(&self) -> Option<AuthCredential> {
+        match self.state.read() {
+            Ok(state) => state.credential.clone(),
+            Err(poisoned) => poisoned.into_inner().credential.clone(),
+        }
+    }
+
+    /// Installs a fixed credential, replacing any token provider.
+    pub fn set(&self, credential: Option<AuthCredential>) {
+        self.update(|state| {
+            state.credential = credential;
+            state.provider = None;
+        });
+    }
+
+    pub fn has_provider(&self) -> bool {
+        match self.state.read() {
+            Ok(state) => state.provider.is_some(),
+            Err(poisoned) => poisoned.into_inner().provider.is_some(),
+        }
+    }
+
+    pub fn set_provider(&self, provider: Arc<dyn TokenProvider>) {
+        self.update(|state| {
+            state.credential = None;
+            state.provider = Some(provider);
+        });
+    }
+
+    /// Asks the token provider, if any, for the credential of the next request, letting it fetch
+    /// over `client`.
+    pub async fn refresh(&self, client: &dyn HttpClient) -> RequestResult<()> {
+        let provider = match self.state.read() {
+            Ok(state) => state.provider.clone(),
+            Err(poisoned) => poisoned.into_inner().provider.clone(),
+        };
+        let provider = match provider {
+            Some(provider) => provider,
+            None => return Ok(()),
+        };
+
+        let credential = provider.token_via(client).await?;
+        self.update(|state| {
+            // Keep whatever replaced the provider while the token was being fetched.
+            if let Some(current) = &state.provider {
+                if Arc::ptr_eq(current, &provider) {
+                    state.credential = Some(credential);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn update(&self, f: impl FnOnce(&mut AuthState)) {
+        match self.state.write() {
+            Ok(mut state) => f(&mut state),
+            Err(poisoned) => f(&mut poisoned.into_inner()),
+        }
+    }
+}
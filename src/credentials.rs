@@ -0,0 +1,364 @@
+use crate::clients::{self, HttpClient};
+use crate::credential::AuthCredential;
+use crate::errors::{CredentialError, CredentialResult};
+use crate::external_account::{send_json, ExternalAccount};
+use crate::secret::Secret;
+#[cfg(feature = "jwt")]
+use crate::service_account::ServiceAccount;
+use crate::token_cache::{self, CacheKey};
+use crate::Firebase;
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+const APPLICATION_CREDENTIALS_VAR: &str = "GOOGLE_APPLICATION_CREDENTIALS";
+const METADATA_HOST_VAR: &str = "GCE_METADATA_HOST";
+const METADATA_HOST: &str = "metadata.google.internal";
+
+pub const CLOUD_PLATFORM_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+pub const DATABASE_SCOPES: [&str; 2] = [
+    "https://www.googleapis.com/auth/firebase.database",
+    "https://www.googleapis.com/auth/userinfo.email",
+];
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum Credentials {
+    ExternalAccount(ExternalAccount),
+    #[cfg(feature = "jwt")]
+    ServiceAccount(ServiceAccount),
+    /// The service account attached to the Compute Engine, Cloud Run or GKE workload, served
+    /// by the metadata server.
+    ComputeMetadata,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessToken {
+    pub token: Secret,
+    pub expires_at: SystemTime,
+}
+
+#[derive(Deserialize)]
+struct MetadataToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct CredentialsType {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+impl Credentials {
+    /// ```
+    /// use firebase_rs::Credentials;
+    ///
+    /// let credentials = Credentials::from_json(r#"{
+    ///     "type": "external_account",
+    ///     "audience": "//iam.googleapis.com/projects/1/locations/global/workloadIdentityPools/pool/providers/github",
+    ///     "subject_token_type": "urn:ietf:params:oauth:token-type:jwt",
+    ///     "token_url": "https://sts.googleapis.com/v1/token",
+    ///     "credential_source": { "file": "/var/run/secrets/token" }
+    /// }"#).unwrap();
+    /// ```
+    pub fn from_json(json: &str) -> CredentialResult<Self> {
+        let kind = match serde_json::from_str::<CredentialsType>(json) {
+            Ok(kind) => kind.kind,
+            Err(err) => return Err(CredentialError::InvalidJson(err)),
+        };
+
+        match kind.as_str() {
+            "external_account" => match serde_json::from_str::<ExternalAccount>(json) {
+                Ok(account) => Ok(Credentials::ExternalAccount(account)),
+                Err(err) => Err(CredentialError::InvalidJson(err)),
+            },
+            #[cfg(feature = "jwt")]
+            "service_account" => match serde_json::from_str::<ServiceAccount>(json) {
+                Ok(account) => Ok(Credentials::ServiceAccount(account)),
+                Err(err) => Err(CredentialError::InvalidJson(err)),
+            },
+            _ => Err(CredentialError::UnsupportedType(kind)),
+        }
+    }
+
+    /// ```
+    /// use firebase_rs::Credentials;
+    ///
+    /// let credentials = Credentials::from_file("/etc/firebase/credentials.json");
+    /// ```
+    pub fn from_file(path: impl AsRef<Path>) -> CredentialResult<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => Self::from_json(&json),
+            Err(err) => Err(CredentialError::Io(err)),
+        }
+    }
+
+    /// Application Default Credentials: the key file named by `GOOGLE_APPLICATION_CREDENTIALS`
+    /// if it is set, otherwise the metadata server of the Google Cloud workload.
+    ///
+    /// ```
+    /// use firebase_rs::Credentials;
+    ///
+    /// let credentials = Credentials::application_default();
+    /// ```
+    pub fn application_default() -> CredentialResult<Self> {
+        Self::application_default_from(std::env::var_os(APPLICATION_CREDENTIALS_VAR))
+    }
+
+    /// [`application_default`](Credentials::application_default) with the value of
+    /// `GOOGLE_APPLICATION_CREDENTIALS` passed in.
+    fn application_default_from(path: Option<OsString>) -> CredentialResult<Self> {
+        match path {
+            Some(path) if !path.is_empty() => Self::from_file(path),
+            _ => Ok(Credentials::ComputeMetadata),
+        }
+    }
+
+    /// ```
+    /// use firebase_rs::{Credentials, DATABASE_SCOPES};
+    ///
+    /// # async fn run() {
+    /// let credentials = Credentials::from_file("/etc/firebase/credentials.json").unwrap();
+    /// let token = credentials.access_token(&DATABASE_SCOPES).await;
+    /// # }
+    /// ```
+    pub async fn access_token(&self, scopes: &[&str]) -> CredentialResult<AccessToken> {
+        self.access_token_via(clients::default_client().as_ref(), scopes)
+            .await
+    }
+
+    pub(crate) async fn access_token_via(
+        &self,
+        client: &dyn HttpClient,
+        scopes: &[&str],
+    ) -> CredentialResult<AccessToken> {
+        let key = CacheKey::new(self.principal(), scopes);
+        if let Some(token) = token_cache::get(&key) {
+            return Ok(token);
+        }
+
+        let flight = token_cache::flight(&key);
+        let _fetching = flight.lock().await;
+        if let Some(token) = token_cache::get(&key) {
+            return Ok(token);
+        }
+
+        let token = match self {
+            Credentials::ExternalAccount(account) => {
+                account.access_token_via(client, scopes).await?
+            }
+            #[cfg(feature = "jwt")]
+            Credentials::ServiceAccount(account) => {
+                account.access_token_via(client, scopes).await?
+            }
+            Credentials::ComputeMetadata => metadata_token(client, scopes).await?,
+        };
+        token_cache::insert(key, token.clone());
+
+        Ok(token)
+    }
+
+    /// Drops the cached token for `scopes`, e.g. after the server revoked it.
+    pub(crate) fn forget(&self, scopes: &[&str]) {
+        token_cache::remove(&CacheKey::new(self.principal(), scopes));
+    }
+
+    /// Identifies who the credentials act as; instances built from the same principal share
+    /// cached access tokens.
+    pub fn principal(&self) -> String {
+        match self {
+            Credentials::ExternalAccount(account) => {
+                match &account.service_account_impersonation_url {
+                    Some(url) => format!("{}#{}", account.audience, url),
+                    None => account.audience.clone(),
+                }
+            }
+            #[cfg(feature = "jwt")]
+            Credentials::ServiceAccount(account) => account.client_email.clone(),
+            Credentials::ComputeMetadata => format!("{}#default", metadata_host()),
+        }
+    }
+}
+
+fn metadata_host() -> String {
+    match std::env::var(METADATA_HOST_VAR) {
+        Ok(host) if !host.is_empty() => host,
+        _ => METADATA_HOST.to_string(),
+    }
+}
+
+/// Fetches a token for the default service account from the metadata server.
+async fn metadata_token(client: &dyn HttpClient, scopes: &[&str]) -> CredentialResult<AccessToken> {
+    let url = format!(
+        "http://{}/computeMetadata/v1/instance/service-accounts/default/token?scopes={}",
+        metadata_host(),
+        scopes.join(",")
+    );
+    let request = http::Request::get(url).header("Metadata-Flavor", "Google");
+    let token: MetadataToken = send_json(client, request, Vec::new()).await?;
+
+    Ok(AccessToken {
+        token: Secret::new(token.access_token),
+        expires_at: SystemTime::now() + Duration::from_secs(token.expires_in),
+    })
+}
+
+impl Firebase {
+    /// Fetches an access token for the database scopes and installs it as the shared credential.
+    ///
+    /// ```
+    /// use firebase_rs::{Credentials, Firebase};
+    ///
+    /// # async fn run() {
+    /// let credentials = Credentials::from_file("/etc/firebase/credentials.json").unwrap();
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// firebase.authorize(&credentials).await.unwrap();
+    /// # }
+    /// ```
+    pub async fn authorize(&self, credentials: &Credentials) -> CredentialResult<AccessToken> {
+        let token = credentials
+            .access_token_via(self.client.as_ref(), &DATABASE_SCOPES)
+            .await?;
+        self.set_credential(AuthCredential::AccessToken(token.token.clone()));
+
+        Ok(token)
+    }
+
+    /// Connects to `uri` with [Application Default Credentials](Credentials::application_default),
+    /// renewing the access token before it expires.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// let firebase = Firebase::with_application_default_credentials("https://myfirebase.firebaseio.com");
+    /// ```
+    pub fn with_application_default_credentials(uri: &str) -> CredentialResult<Self> {
+        let firebase = match Firebase::new(uri) {
+            Ok(firebase) => firebase,
+            Err(err) => return Err(CredentialError::Url(err)),
+        };
+        Ok(firebase.with_token_provider(Credentials::application_default()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::credentials::{AccessToken, Credentials, DATABASE_SCOPES};
+    use crate::errors::{CredentialError, RequestResult};
+    use crate::hooks::HttpRequest;
+    use crate::secret::Secret;
+    use crate::token_cache::{self, CacheKey};
+    use crate::{Firebase, RequestableExt};
+    use async_trait::async_trait;
+    use std::ffi::OsString;
+    use std::time::{Duration, SystemTime};
+
+    #[derive(Debug)]
+    struct MetadataServer;
+
+    #[async_trait]
+    impl HttpClient for MetadataServer {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            assert_eq!(
+                request.uri(),
+                "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token?scopes=a,b"
+            );
+            assert_eq!(request.headers()["metadata-flavor"], "Google");
+            let body =
+                r#"{"access_token":"ya29.metadata","expires_in":3599,"token_type":"Bearer"}"#;
+            Ok(http::Response::new(body.as_bytes().to_vec()))
+        }
+    }
+
+    #[tokio::test]
+    async fn access_tokens_are_shared() {
+        let json = r#"{
+            "type": "external_account",
+            "audience": "//iam.googleapis.com/projects/1/locations/global/workloadIdentityPools/cached/providers/test",
+            "subject_token_type": "urn:ietf:params:oauth:token-type:jwt",
+            "token_url": "https://sts.googleapis.com/v1/token",
+            "credential_source": { "file": "/nonexistent/token" }
+        }"#;
+        let first = Credentials::from_json(json).unwrap();
+        let second = Credentials::from_json(json).unwrap();
+
+        let token = AccessToken {
+            token: Secret::new("cached_token"),
+            expires_at: SystemTime::now() + Duration::from_secs(3600),
+        };
+        token_cache::insert(CacheKey::new(first.principal(), &["scope"]), token.clone());
+
+        assert_eq!(second.access_token(&["scope"]).await.unwrap(), token);
+    }
+
+    #[test]
+    fn unsupported_type() {
+        let credentials = Credentials::from_json(r#"{ "type": "authorized_user" }"#);
+        match credentials {
+            Err(CredentialError::UnsupportedType(kind)) => assert_eq!(kind, "authorized_user"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn application_default() {
+        let path = Some(OsString::from("/nonexistent/key.json"));
+        match Credentials::application_default_from(path) {
+            Err(CredentialError::Io(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let empty = Some(OsString::new());
+        assert!(matches!(
+            Credentials::application_default_from(empty),
+            Ok(Credentials::ComputeMetadata)
+        ));
+        let credentials = Credentials::application_default_from(None).unwrap();
+        token_cache::remove(&CacheKey::new(credentials.principal(), &["a", "b"]));
+        let token = credentials
+            .access_token_via(&MetadataServer, &["a", "b"])
+            .await
+            .unwrap();
+        assert_eq!(token.token.expose(), "ya29.metadata");
+    }
+
+    /// Answers both the metadata server and the database, recording every request.
+    #[derive(Debug, Default)]
+    struct Workload(std::sync::Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl HttpClient for Workload {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            self.0.lock().unwrap().push(request.uri().to_string());
+            let body = match request.uri().host() {
+                Some("metadata.google.internal") => {
+                    r#"{"access_token":"ya29.workload","expires_in":3599}"#
+                }
+                _ => "1",
+            };
+            Ok(http::Response::new(body.as_bytes().to_vec()))
+        }
+    }
+
+    #[tokio::test]
+    async fn provider_fetches_through_the_instance_client() {
+        let credentials = Credentials::ComputeMetadata;
+        token_cache::remove(&CacheKey::new(credentials.principal(), &DATABASE_SCOPES));
+        let client = std::sync::Arc::new(Workload::default());
+        let firebase = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone())
+            .with_token_provider(credentials);
+
+        assert_eq!(firebase.at("counter").get::<u32>().await.unwrap(), 1);
+        let requests = client.0.lock().unwrap();
+        assert!(requests[0].starts_with("http://metadata.google.internal/"));
+        assert_eq!(
+            requests[1],
+            "https://myfirebase.firebaseio.com/counter.json?access_token=ya29.workload"
+        );
+    }
+}
@@ -0,0 +1,1047 @@
+use crate::clients::{BodyStream, HttpClient, HttpResponse, StreamingResponse, Timeout};
+use crate::constants::{Method, Response, LIMIT_TO_FIRST, LIMIT_TO_LAST, ORDER_BY};
+use crate::credential::{AuthCredential, SharedAuth};
+use crate::errors::{RequestError, RequestResult};
+use crate::hooks::{HttpRequest, RequestHooks};
+use crate::multiplex::Hubs;
+use crate::ordered::parse_children;
+use crate::params::{append_query_pair, Paramable, Params};
+use crate::query::QUERY_KEYS;
+use crate::queue::Priority;
+use crate::requestable::{Requestable, RequestableExt};
+use crate::secret::Secret;
+use crate::Firebase;
+use async_trait::async_trait;
+use futures::StreamExt;
+use http::header::CONTENT_TYPE;
+use http::HeaderMap;
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use url::Url;
+
+/// A database location together with its query parameters and credentials.
+///
+/// Paths and parameters live on the same builder, so [`at`](Endpoint::at) and the
+/// [`Paramable`] methods can be interleaved in any order; a request uses whatever the endpoint
+/// holds when it is sent.
+///
+/// ```
+/// use firebase_rs::{Firebase, Paramable};
+///
+/// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+/// let a = firebase.at("teams").order_by("score").at("red").limit_to_last(3);
+/// let b = firebase.with_params().limit_to_last(3).at("teams/red").order_by("score");
+/// assert_eq!(a.get_uri(), b.get_uri());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    uri: Url,
+    path: Vec<String>,
+    params: Params,
+    auth: SharedAuth,
+    hooks: RequestHooks,
+    client: Arc<dyn HttpClient>,
+    timeout: Option<Duration>,
+    priority: Option<Priority>,
+    listeners: Hubs,
+}
+
+impl Endpoint {
+    pub(crate) fn new(firebase: &Firebase) -> Self {
+        Self {
+            uri: firebase.uri.clone(),
+            path: Vec::new(),
+            params: Params::default(),
+            auth: firebase.auth.clone(),
+            hooks: firebase.hooks.clone(),
+            client: firebase.client.clone(),
+            timeout: firebase.timeout,
+            priority: None,
+            listeners: firebase.listeners.clone(),
+        }
+    }
+
+    /// This location with all query and output parameters dropped, for internal requests whose
+    /// response format the caller's parameters must not change.
+    pub(crate) fn without_params(mut self) -> Self {
+        self.params = Params::default();
+        self
+    }
+
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID").at("f69111a8a5258c15286d3d0bd4688c55");
+    /// ```
+    pub fn at(mut self, path: &str) -> Self {
+        self.path.extend(
+            path.trim_end_matches(".json")
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .map(String::from),
+        );
+        self
+    }
+
+    pub fn path(&self) -> String {
+        self.path.join("/")
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    pub(crate) fn extend_params(mut self, params: &Params) -> Self {
+        self.params.extend(params);
+        self
+    }
+
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let uri = firebase.get_uri();
+    /// ```
+    pub fn get_uri(&self) -> String {
+        self.request_uri().to_string()
+    }
+
+    /// Like [`get_uri`](Endpoint::get_uri) with the credential masked, safe to write to logs.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// let users = Firebase::auth("https://myfirebase.firebaseio.com", "my_auth_key").unwrap().at("users");
+    /// assert_eq!(users.get_uri_redacted(), "https://myfirebase.firebaseio.com/users.json?auth=***");
+    /// ```
+    pub fn get_uri_redacted(&self) -> String {
+        let mut uri = self.unauthenticated_uri();
+        if let Some(credential) = self.auth.get() {
+            let (key, value) = credential.redacted_query_param();
+            append_query_pair(&mut uri, key, value);
+        }
+        uri.to_string()
+    }
+
+    pub(crate) fn request_uri(&self) -> Url {
+        let mut uri = self.unauthenticated_uri();
+        if let Some(credential) = self.auth.get() {
+            let (key, value) = credential.query_param();
+            append_query_pair(&mut uri, key, value);
+        }
+        uri
+    }
+
+    fn unauthenticated_uri(&self) -> Url {
+        let mut uri = self.uri.clone();
+        uri.set_path(&format!("{}.json", self.path()));
+        self.params.set_params(&mut uri);
+        uri
+    }
+
+    /// Returns a copy of this endpoint that authenticates with `auth_key` instead of the shared credentials.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, RequestableExt};
+    /// use serde_json::Value;
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::auth("https://myfirebase.firebaseio.com", "my_auth_key").unwrap().at("users").at("USER_ID");
+    /// let user = firebase.as_user("USER_ID_TOKEN").get::<Value>().await;
+    /// # }
+    /// ```
+    pub fn as_user(&self, auth_key: &str) -> Self {
+        self.with_credential(AuthCredential::IdToken(Secret::new(auth_key)))
+    }
+
+    /// A copy of this endpoint with its own fixed `credential`, detached from the shared auth.
+    pub(crate) fn with_credential(&self, credential: AuthCredential) -> Self {
+        let mut endpoint = self.clone();
+        endpoint.auth = SharedAuth::new(Some(credential));
+        endpoint
+    }
+
+    /// Returns a copy of this endpoint that authenticates with the credentials of `other`,
+    /// following any later rotation of them.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// let admin = Firebase::auth("https://myfirebase.firebaseio.com", "my_database_secret").unwrap();
+    /// let user = Firebase::auth("https://myfirebase.firebaseio.com", "USER_ID_TOKEN").unwrap().at("users").at("USER_ID");
+    /// let as_admin = user.with_credentials(&admin);
+    /// ```
+    pub fn with_credentials(&self, other: &Firebase) -> Self {
+        let mut endpoint = self.clone();
+        endpoint.auth = other.auth.clone();
+        endpoint
+    }
+
+    /// Tags requests from this endpoint for [`Firebase::with_concurrency_limit`].
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, Priority};
+    ///
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_concurrency_limit(4);
+    /// let migration = firebase.at("archive").with_priority(Priority::Batch);
+    /// ```
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Fetches this location with `params` layered over the endpoint's own for this request only.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, Paramable, Params};
+    /// use serde_json::Value;
+    ///
+    /// # async fn run() {
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let keys = users.get_with::<Value>(&Params::new().shallow(true)).await;
+    /// # }
+    /// ```
+    pub async fn get_with<T>(&self, params: &Params) -> RequestResult<T>
+    where
+        T: Serialize + DeserializeOwned + Debug,
+    {
+        let mut endpoint = self.clone();
+        endpoint.params.extend(params);
+        endpoint.get().await
+    }
+
+    /// Pushes `data` like [`push`](RequestableExt::push) and returns an endpoint at the new
+    /// child, keeping this endpoint's credentials and parameters.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, RequestableExt};
+    /// use serde_json::json;
+    ///
+    /// # async fn run() {
+    /// let messages = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("messages");
+    /// if let Ok(message) = messages.push_child(&json!({ "text": "hello" })).await {
+    ///     message.update(&json!({ "read": false })).await;
+    /// }
+    /// # }
+    /// ```
+    pub async fn push_child<T>(&self, data: &T) -> RequestResult<Endpoint>
+    where
+        T: Serialize + Sync + ?Sized,
+    {
+        let pushed = self.push(data).await?;
+        Ok(self.clone().at(&pushed.key))
+    }
+
+    /// Sends `body` as is, without going through `serde_json`, and returns the response body
+    /// as text. An empty body is sent as no body at all.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, Method};
+    ///
+    /// # async fn run() {
+    /// let stats = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("stats");
+    /// let json = br#"{"visits":1042,"updated":"2024-05-01"}"#.to_vec();
+    /// stats.request_raw(Method::PATCH, json).await;
+    /// # }
+    /// ```
+    pub async fn request_raw(&self, method: Method, body: Vec<u8>) -> RequestResult<Response> {
+        let body = if body.is_empty() { None } else { Some(body) };
+        let response = check_status(self.send(method, body).await?)?;
+        match String::from_utf8(response.into_body()) {
+            Ok(data) => Ok(Response { data }),
+            Err(_) => Err(RequestError::NoUTF8),
+        }
+    }
+
+    /// Overwrites this location with already serialized JSON, e.g. `"\"Ada\""` for a bare
+    /// string. The server rejects bodies that aren't valid JSON.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// # async fn run() {
+    /// let name = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID").at("name");
+    /// name.put_raw("\"Ada\"").await;
+    /// # }
+    /// ```
+    pub async fn put_raw(&self, json: &str) -> RequestResult<Response> {
+        self.request_raw(Method::PUT, json.as_bytes().to_vec())
+            .await
+    }
+
+    /// Lists the keys of the children of this location with a `shallow=true` read, without
+    /// transferring their values. A missing node or a leaf has no keys.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// # async fn run() {
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let ids = users.keys().await;
+    /// # }
+    /// ```
+    pub async fn keys(&self) -> RequestResult<Vec<String>> {
+        let body = self.shallow_read().get_raw().await?;
+        match parse_children::<IgnoredAny>(&body) {
+            Ok(children) => Ok(children.into_iter().map(|(key, _)| key).collect()),
+            Err(err) => match serde_json::from_str::<IgnoredAny>(&body) {
+                Ok(_) => Ok(Vec::new()),
+                Err(_) => Err(err),
+            },
+        }
+    }
+
+    /// Checks whether this location holds any data. Plain locations are read with
+    /// `shallow=true`; ordered queries are cut to their first match with `limitToFirst=1`
+    /// unless they already carry a limit.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// # async fn run() {
+    /// let user = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID");
+    /// let registered = user.exists().await;
+    /// # }
+    /// ```
+    pub async fn exists(&self) -> RequestResult<bool> {
+        let probe = if self.params.get(ORDER_BY).is_none() {
+            self.shallow_read()
+        } else if self.params.get(LIMIT_TO_FIRST).is_none()
+            && self.params.get(LIMIT_TO_LAST).is_none()
+        {
+            self.clone().limit_to_first(1)
+        } else {
+            self.clone()
+        };
+
+        // Queries without matches come back as an empty object rather than `null`.
+        let body = probe.get_raw().await?;
+        Ok(!matches!(body.trim(), "null" | "{}" | "[]"))
+    }
+
+    /// A copy of this endpoint for a `shallow=true` read. The server refuses `shallow` combined
+    /// with any query parameter, so those are dropped.
+    fn shallow_read(&self) -> Self {
+        QUERY_KEYS
+            .into_iter()
+            .fold(self.clone(), |endpoint, key| endpoint.remove_param(key))
+            .shallow(true)
+    }
+
+    /// Streams the response body into `writer` without holding it in memory and returns the
+    /// number of bytes written.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// # async fn run() {
+    /// let mut file = tokio::fs::File::create("users.json").await.unwrap();
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let written = users.download_to(&mut file).await;
+    /// # }
+    /// ```
+    pub async fn download_to<W>(&self, mut writer: W) -> RequestResult<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut body = self.get_bytes_stream().await?;
+        let mut written = 0;
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk?;
+            if let Err(err) = writer.write_all(&chunk).await {
+                return Err(RequestError::Io(err));
+            }
+            written += chunk.len() as u64;
+        }
+        match writer.flush().await {
+            Ok(()) => Ok(written),
+            Err(err) => Err(RequestError::Io(err)),
+        }
+    }
+
+    /// Streams the data at this location into a new file at `path`, replacing any existing one,
+    /// and returns the number of bytes written. Handy for backups of a subtree. The file is
+    /// created before the read starts, so a failed read leaves it empty.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// # async fn run() {
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let written = users.download_to_file("backups/users.json").await;
+    /// # }
+    /// ```
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn download_to_file(&self, path: impl AsRef<std::path::Path>) -> RequestResult<u64> {
+        match tokio::fs::File::create(path).await {
+            Ok(file) => self.download_to(tokio::io::BufWriter::new(file)).await,
+            Err(err) => Err(RequestError::Io(err)),
+        }
+    }
+
+    /// Starts a read and hands over the response body chunk by chunk as it arrives, for nodes
+    /// too large to buffer. Failed requests are reported before the stream is returned.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run() {
+    /// let logs = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("logs");
+    /// if let Ok(mut chunks) = logs.get_bytes_stream().await {
+    ///     while let Some(Ok(chunk)) = chunks.next().await {
+    ///         println!("received {} bytes", chunk.len());
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn get_bytes_stream(&self) -> RequestResult<BodyStream> {
+        self.auth.refresh(self.client()).await?;
+        let request = self.build_request(Method::GET, None)?;
+        let response =
+            check_streaming_status(self.client.execute_streaming(request).await?).await?;
+        Ok(response.into_body())
+    }
+
+    pub(crate) fn auth(&self) -> &SharedAuth {
+        &self.auth
+    }
+
+    pub(crate) fn listeners(&self) -> &Hubs {
+        &self.listeners
+    }
+
+    pub(crate) fn client(&self) -> &dyn HttpClient {
+        self.client.as_ref()
+    }
+
+    pub(crate) fn build_request(
+        &self,
+        method: Method,
+        body: Option<Vec<u8>>,
+    ) -> RequestResult<HttpRequest> {
+        self.build_request_with(method.as_http(), body, HeaderMap::new())
+    }
+
+    /// Like [`build_request`](Endpoint::build_request) but for any HTTP method and with extra
+    /// `headers`, all in place before the request hooks see the request.
+    pub(crate) fn build_request_with(
+        &self,
+        method: http::Method,
+        body: Option<Vec<u8>>,
+        headers: HeaderMap,
+    ) -> RequestResult<HttpRequest> {
+        if let Err(err) = self.params.validate() {
+            return Err(RequestError::InvalidQuery(err));
+        }
+
+        let mut builder = http::Request::builder()
+            .method(method)
+            .uri(self.request_uri().as_str());
+        if body.is_some() {
+            builder = builder.header(CONTENT_TYPE, "application/json");
+        }
+
+        let mut request = match builder.body(body.unwrap_or_default()) {
+            Ok(request) => request,
+            Err(_) => return Err(RequestError::SerializeError),
+        };
+        request.headers_mut().extend(headers);
+        if let Some(timeout) = self.timeout {
+            request.extensions_mut().insert(Timeout(timeout));
+        }
+        if let Some(priority) = self.priority {
+            request.extensions_mut().insert(priority);
+        }
+        self.hooks.apply(&mut request);
+
+        Ok(request)
+    }
+
+    pub(crate) async fn send(
+        &self,
+        method: Method,
+        body: Option<Vec<u8>>,
+    ) -> RequestResult<HttpResponse> {
+        self.auth.refresh(self.client()).await?;
+        let request = self.build_request(method, body)?;
+        self.client.execute(request).await
+    }
+}
+
+impl Paramable for Endpoint {
+    fn add_param<T>(mut self, key: &str, value: T) -> Self
+    where
+        T: ToString,
+    {
+        self.params.insert(key, value);
+        self
+    }
+
+    fn remove_param(mut self, key: &str) -> Self {
+        self.params.remove(key);
+        self
+    }
+}
+
+#[async_trait]
+impl Requestable for Endpoint {
+    async fn request(&self, method: Method, data: Option<Value>) -> RequestResult<Response> {
+        let body = match (method, data) {
+            (Method::POST | Method::PUT | Method::PATCH, None) => {
+                return Err(RequestError::SerializeError)
+            }
+            (_, Some(data)) => match serde_json::to_vec(&data) {
+                Ok(body) => Some(body),
+                Err(err) => return Err(RequestError::Serialize(err)),
+            },
+            (_, None) => None,
+        };
+
+        let response = check_status(self.send(method, body).await?)?;
+
+        match method {
+            Method::GET => {
+                if response.status() == http::StatusCode::OK {
+                    match String::from_utf8(response.into_body()) {
+                        Ok(data) => {
+                            if data.as_str() == "null" {
+                                return Err(RequestError::NotFoundOrNullBody);
+                            }
+                            Ok(Response { data })
+                        }
+                        Err(_) => Err(RequestError::NotJSON),
+                    }
+                } else {
+                    Err(RequestError::NetworkError)
+                }
+            }
+            Method::DELETE => Ok(Response {
+                data: String::default(),
+            }),
+            Method::POST | Method::PUT | Method::PATCH => {
+                match String::from_utf8(response.into_body()) {
+                    Ok(data) => Ok(Response { data }),
+                    Err(_) => Err(RequestError::NoUTF8),
+                }
+            }
+        }
+    }
+
+    async fn get_raw(&self) -> RequestResult<String> {
+        let response = check_status(self.send(Method::GET, None).await?)?;
+        if response.status() != http::StatusCode::OK {
+            return Err(RequestError::NetworkError);
+        }
+
+        match String::from_utf8(response.into_body()) {
+            Ok(data) => Ok(data),
+            Err(_) => Err(RequestError::NoUTF8),
+        }
+    }
+}
+
+/// Turns a non-2xx response into a [`RequestError`] for its status.
+pub(crate) fn check_status(response: HttpResponse) -> RequestResult<HttpResponse> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(rejected(response.status(), response.body()))
+    }
+}
+
+/// Like [`check_status`], reading the body of a failed streaming response for its message.
+pub(crate) async fn check_streaming_status(
+    response: StreamingResponse,
+) -> RequestResult<StreamingResponse> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status();
+    let mut chunks = response.into_body();
+    let mut body = Vec::new();
+    while let Some(Ok(chunk)) = chunks.next().await {
+        body.extend(chunk);
+    }
+    Err(rejected(status, &body))
+}
+
+/// Error bodies look like `{"error": "Permission denied"}`.
+fn rejected(status: http::StatusCode, body: &[u8]) -> RequestError {
+    let message = match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Object(error)) => error.get("error").and_then(Value::as_str).map(String::from),
+        _ => None,
+    };
+    let message = message.unwrap_or_else(|| String::from_utf8_lossy(body).trim().to_string());
+
+    match status {
+        http::StatusCode::UNAUTHORIZED | http::StatusCode::FORBIDDEN => {
+            RequestError::PermissionDenied(message)
+        }
+        http::StatusCode::NOT_FOUND => RequestError::NotFound(message),
+        http::StatusCode::BAD_REQUEST if message.to_ascii_lowercase().contains("invalid path") => {
+            RequestError::InvalidPath(message)
+        }
+        http::StatusCode::PRECONDITION_FAILED => RequestError::PreconditionFailed(message),
+        _ => RequestError::Api {
+            status: status.as_u16(),
+            message,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::constants::{Method, ORDER_BY};
+    use crate::errors::{RequestError, RequestResult};
+    use crate::hooks::HttpRequest;
+    use crate::params::{Paramable, Params};
+    use crate::requestable::{Requestable, RequestableExt};
+    use crate::Firebase;
+    use async_trait::async_trait;
+    use serde_json::Value;
+    use std::sync::{Arc, Mutex};
+
+    const URI: &str = "https://firebase_id.firebaseio.com";
+
+    #[derive(Debug)]
+    struct Fixed(&'static str);
+
+    #[derive(Debug, Default)]
+    struct Recording(Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl HttpClient for Recording {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            self.0.lock().unwrap().push(request.uri().to_string());
+            Ok(http::Response::new(b"true".to_vec()))
+        }
+    }
+
+    #[async_trait]
+    impl HttpClient for Fixed {
+        async fn execute(&self, _request: HttpRequest) -> RequestResult<HttpResponse> {
+            Ok(http::Response::new(self.0.as_bytes().to_vec()))
+        }
+    }
+
+    #[test]
+    fn paths_and_params() {
+        let firebase = Firebase::auth(URI, "auth_key").unwrap();
+        let users = firebase.at("users/").at("USER_ID.json").order_by("name");
+
+        assert_eq!(users.path(), "users/USER_ID");
+        assert_eq!(
+            users.get_uri(),
+            format!(
+                "{}/users/USER_ID.json?orderBy=%22name%22&auth=auth_key",
+                URI
+            )
+        );
+        assert_eq!(
+            firebase.with_params().get_uri(),
+            format!("{}/.json?auth=auth_key", URI)
+        );
+    }
+
+    #[test]
+    fn request_hooks() {
+        let endpoint = Firebase::auth(URI, "auth_key")
+            .unwrap()
+            .with_request_hook(|request| {
+                let signature = format!("{}:{}", request.method(), request.body().len());
+                request
+                    .headers_mut()
+                    .insert("x-signature", signature.parse().unwrap());
+            })
+            .at("users")
+            .as_user("id_token");
+
+        let request = endpoint
+            .build_request(Method::PUT, Some(b"true".to_vec()))
+            .unwrap();
+        assert_eq!(request.headers()["x-signature"], "PUT:4");
+        assert_eq!(
+            request.uri(),
+            "https://firebase_id.firebaseio.com/users.json?auth=id_token"
+        );
+    }
+
+    #[test]
+    fn method_override() {
+        let endpoint = Firebase::new(URI)
+            .unwrap()
+            .with_method_override()
+            .at("users");
+
+        let update = endpoint
+            .build_request(Method::PATCH, Some(b"{}".to_vec()))
+            .unwrap();
+        assert_eq!(update.method(), http::Method::POST);
+        assert_eq!(update.headers()["x-http-method-override"], "PATCH");
+
+        let push = endpoint
+            .build_request(Method::POST, Some(b"{}".to_vec()))
+            .unwrap();
+        assert!(!push.headers().contains_key("x-http-method-override"));
+    }
+
+    #[tokio::test]
+    async fn raw_text() {
+        let firebase = Firebase::new(URI).unwrap();
+
+        let endpoint = firebase
+            .clone()
+            .with_http_client(Fixed("null"))
+            .at("missing");
+        assert_eq!(endpoint.get_raw().await.unwrap(), "null");
+        assert!(endpoint.get_as_string().await.is_err());
+
+        let endpoint = firebase.with_http_client(Fixed("{\"a\":1}")).at("object");
+        assert_eq!(endpoint.get_raw().await.unwrap(), "{\"a\":1}");
+    }
+
+    #[tokio::test]
+    async fn per_request_params() {
+        let recording = Arc::new(Recording::default());
+        let users = Firebase::new(URI)
+            .unwrap()
+            .with_http_client(recording.clone())
+            .at("users")
+            .order_by("name");
+
+        users
+            .get_with::<bool>(&Params::new().shallow(true))
+            .await
+            .unwrap();
+        users.get::<bool>().await.unwrap();
+
+        assert_eq!(
+            *recording.0.lock().unwrap(),
+            vec![
+                format!("{}/users.json?orderBy=%22name%22&shallow=true", URI),
+                format!("{}/users.json?orderBy=%22name%22", URI),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn download_to_writer() {
+        use futures::StreamExt;
+
+        let endpoint = Firebase::new(URI)
+            .unwrap()
+            .with_http_client(Fixed("{\"a\":1}"))
+            .at("export");
+
+        let mut buffer = Vec::new();
+        let written = endpoint.download_to(&mut buffer).await.unwrap();
+        assert_eq!(written, 7);
+        assert_eq!(buffer, b"{\"a\":1}");
+
+        let chunks: Vec<Vec<u8>> = endpoint
+            .get_bytes_stream()
+            .await
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(chunks.concat(), b"{\"a\":1}");
+
+        let endpoint = endpoint.download("export.json");
+        assert!(endpoint
+            .get_uri()
+            .ends_with("/export.json?download=export.json"));
+        let path = std::env::temp_dir().join("firebase-rs-download_to_file.json");
+        let written = endpoint.download_to_file(&path).await.unwrap();
+        assert_eq!(written, 7);
+        assert_eq!(std::fs::read(&path).unwrap(), b"{\"a\":1}");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn event_stream() {
+        use futures::StreamExt;
+
+        let endpoint = Firebase::new(URI)
+            .unwrap()
+            .with_http_client(Fixed(
+                "event: put\ndata: {\"path\":\"/\",\"data\":1}\n\nevent: keep-alive\ndata: null\n\n",
+            ))
+            .at("users");
+
+        let events: Vec<_> = endpoint.stream().await.unwrap().collect().await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].as_ref().unwrap().event, "put");
+        assert_eq!(events[1].as_ref().unwrap().data, "null");
+    }
+
+    #[tokio::test]
+    async fn shallow_keys() {
+        let recording = Arc::new(Recording::default());
+        let firebase = Firebase::new(URI).unwrap();
+        firebase
+            .clone()
+            .with_http_client(recording.clone())
+            .at("users")
+            .order_by("name")
+            .limit_to_first(5)
+            .keys()
+            .await
+            .unwrap();
+        assert_eq!(
+            *recording.0.lock().unwrap(),
+            vec![format!("{}/users.json?shallow=true", URI)]
+        );
+
+        let users = firebase
+            .clone()
+            .with_http_client(Fixed("{\"ada\":true,\"alan\":true}"))
+            .at("users");
+        assert_eq!(users.keys().await.unwrap(), vec!["ada", "alan"]);
+
+        let leaf = firebase.with_http_client(Fixed("\"Ada\"")).at("name");
+        assert!(leaf.keys().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn existence_probes() {
+        let recording = Arc::new(Recording::default());
+        let users = Firebase::new(URI)
+            .unwrap()
+            .with_http_client(recording.clone())
+            .at("users");
+        assert!(users.exists().await.unwrap());
+        assert!(users.clone().order_by("age").exists().await.unwrap());
+        assert!(users
+            .clone()
+            .order_by("age")
+            .limit_to_last(3)
+            .exists()
+            .await
+            .unwrap());
+        assert_eq!(
+            *recording.0.lock().unwrap(),
+            vec![
+                format!("{}/users.json?shallow=true", URI),
+                format!("{}/users.json?limitToFirst=1&orderBy=%22age%22", URI),
+                format!("{}/users.json?limitToLast=3&orderBy=%22age%22", URI),
+            ]
+        );
+
+        let firebase = Firebase::new(URI).unwrap();
+        let missing = firebase.clone().with_http_client(Fixed("null")).at("x");
+        assert!(!missing.exists().await.unwrap());
+        let no_match = firebase.with_http_client(Fixed("{}")).at("x");
+        assert!(!no_match.order_by("age").exists().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn shallow_existence_probes_drop_query_parameters() {
+        let recording = Arc::new(Recording::default());
+        let users = Firebase::new(URI)
+            .unwrap()
+            .with_http_client(recording.clone())
+            .at("users");
+        let ordered = users.clone().order_by("age").start_at(18);
+        assert!(ordered.clone().exists().await.unwrap());
+        assert!(ordered
+            .remove_param(ORDER_BY)
+            .limit_to_last(2)
+            .exists()
+            .await
+            .unwrap());
+        assert_eq!(
+            *recording.0.lock().unwrap(),
+            vec![
+                format!("{}/users.json?limitToFirst=1&orderBy=%22age%22&startAt=18", URI),
+                format!("{}/users.json?shallow=true", URI),
+            ]
+        );
+    }
+
+    #[derive(Debug, Default)]
+    struct NoContent(Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl HttpClient for NoContent {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            self.0.lock().unwrap().push(request.uri().to_string());
+            let mut response = http::Response::new(Vec::new());
+            *response.status_mut() = http::StatusCode::NO_CONTENT;
+            Ok(response)
+        }
+    }
+
+    #[tokio::test]
+    async fn silent_writes() {
+        let client = Arc::new(NoContent::default());
+        let events = Firebase::new(URI)
+            .unwrap()
+            .with_http_client(client.clone())
+            .at("events")
+            .silent();
+
+        let response = events.update(&serde_json::json!({ "a": 1 })).await.unwrap();
+        assert_eq!(response.data, "");
+        assert_eq!(
+            *client.0.lock().unwrap(),
+            vec![format!("{}/events.json?print=silent", URI)]
+        );
+    }
+
+    #[test]
+    fn interleaved_paths_and_params() {
+        let firebase = Firebase::new(URI).unwrap();
+        let expected = format!(
+            "{}/teams/red/players.json?limitToLast=3&orderBy=%22score%22",
+            URI
+        );
+
+        let endpoint = firebase
+            .with_params()
+            .order_by("score")
+            .at("teams")
+            .limit_to_last(3)
+            .at("red/players");
+        assert_eq!(endpoint.get_uri(), expected);
+        assert_eq!(endpoint.path(), "teams/red/players");
+
+        let endpoint = firebase
+            .at("teams/red")
+            .limit_to_last(3)
+            .at("players")
+            .order_by("score");
+        assert_eq!(endpoint.get_uri(), expected);
+    }
+
+    #[tokio::test]
+    async fn pushed_child() {
+        let messages = Firebase::new(URI)
+            .unwrap()
+            .with_http_client(Fixed("{\"name\":\"-Nabc123\"}"))
+            .at("messages");
+
+        let message = messages.push_child(&"hello").await.unwrap();
+        assert_eq!(message.path(), "messages/-Nabc123");
+    }
+
+    #[derive(Debug, Default)]
+    struct Echo(Mutex<Vec<(String, Option<String>)>>);
+
+    #[async_trait]
+    impl HttpClient for Echo {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            let content_type = request
+                .headers()
+                .get("content-type")
+                .map(|value| value.to_str().unwrap().to_string());
+            self.0
+                .lock()
+                .unwrap()
+                .push((request.method().to_string(), content_type));
+            Ok(http::Response::new(request.body().clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn raw_bodies() {
+        let client = Arc::new(Echo::default());
+        let name = Firebase::new(URI)
+            .unwrap()
+            .with_http_client(client.clone())
+            .at("users/1/name");
+
+        assert_eq!(name.put_raw("\"Ada\"").await.unwrap().data, "\"Ada\"");
+        assert_eq!(
+            name.request_raw(Method::DELETE, Vec::new())
+                .await
+                .unwrap()
+                .data,
+            ""
+        );
+        assert_eq!(
+            *client.0.lock().unwrap(),
+            vec![
+                ("PUT".to_string(), Some("application/json".to_string())),
+                ("DELETE".to_string(), None),
+            ]
+        );
+    }
+
+    /// Rejects every request the way the database does for the requested path.
+    #[derive(Debug)]
+    struct Denied;
+
+    #[async_trait]
+    impl HttpClient for Denied {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            let (status, body): (u16, &[u8]) = match request.uri().path() {
+                "/secret.json" => (401, b"{\"error\" : \"Permission denied\"}"),
+                "/missing.json" => (404, b"{\"error\" : \"Not Found\"}"),
+                "/bad.json" => (
+                    400,
+                    b"{\"error\" : \"Invalid path: Invalid token in path\"}",
+                ),
+                "/stale.json" => (412, b"{\"error\" : \"ETag mismatch\"}"),
+                _ => (503, b"Service Unavailable\n"),
+            };
+            let mut response = http::Response::new(body.to_vec());
+            *response.status_mut() = http::StatusCode::from_u16(status).unwrap();
+            Ok(response)
+        }
+    }
+
+    #[tokio::test]
+    async fn rejected_requests() {
+        let secret = Firebase::new(URI)
+            .unwrap()
+            .with_http_client(Denied)
+            .at("secret");
+
+        let denied = |result: RequestResult<_>| match result {
+            Err(RequestError::PermissionDenied(message)) => message,
+            other => panic!("unexpected result: {:?}", other.map(|_: ()| ())),
+        };
+        let expected = "Permission denied".to_string();
+        assert_eq!(denied(secret.get::<Value>().await.map(|_| ())), expected);
+        assert_eq!(denied(secret.put(&1).await.map(|_| ())), expected);
+        assert_eq!(
+            denied(secret.download_to(Vec::new()).await.map(|_| ())),
+            expected
+        );
+        assert_eq!(
+            denied(secret.get_full::<Value>().await.map(|_| ())),
+            expected
+        );
+
+        let firebase = Firebase::new(URI).unwrap().with_http_client(Denied);
+        let read = |path: &str| {
+            let endpoint = firebase.at(path);
+            async move { endpoint.get::<Value>().await }
+        };
+        assert!(matches!(
+            read("missing").await,
+            Err(RequestError::NotFound(message)) if message == "Not Found"
+        ));
+        assert!(matches!(
+            read("bad").await,
+            Err(RequestError::InvalidPath(message)) if message.starts_with("Invalid path")
+        ));
+        assert!(matches!(
+            read("stale").await,
+            Err(RequestError::PreconditionFailed(_))
+        ));
+        assert!(matches!(
+            read("busy").await,
+            Err(RequestError::Api { status: 503, message }) if message == "Service Unavailable"
+        ));
+    }
+}
@@ -1,4 +1,6 @@
 use std::fmt::{Display, Formatter};
+use std::io;
+use std::sync::Arc;
 
 pub type UrlParseResult<T> = Result<T, UrlParseError>;
 
@@ -28,6 +30,111 @@ pub enum RequestError {
     NetworkError,
     SerializeError,
     NotFoundOrNullBody,
+    InvalidQuery(QueryError),
+    Io(std::io::Error),
+    Credentials(CredentialError),
+    Auth(AuthError),
+    /// Other clients kept changing the value; carries the number of attempts made.
+    TransactionConflict(u32),
+    /// A create-only write found the location already taken.
+    AlreadyExists,
+    /// `401`/`403`: the security rules or credentials don't allow the request.
+    PermissionDenied(String),
+    /// `404`, e.g. for a database that doesn't exist.
+    NotFound(String),
+    /// `400` for a path with characters the database doesn't allow, such as `.` or `#`.
+    InvalidPath(String),
+    /// `412`: a conditional write's ETag no longer matched.
+    PreconditionFailed(String),
+    /// Any other non-2xx status. Like the variants above, `message` is the `error` field of the
+    /// response, or the raw body when there is none.
+    Api {
+        status: u16,
+        message: String,
+    },
+    /// The body didn't decode into the requested type. `body` is the response as received;
+    /// `path` locates the mismatch, e.g. `users.ada.age`, with the `serde_path_to_error` feature.
+    Decode {
+        path: Option<String>,
+        message: String,
+        body: String,
+    },
+    /// A multi-path write touches this path as well as one of its ancestors.
+    OverlappingPaths(String),
+    /// The HTTP backend failed to send the request or read the response. Shared so that a
+    /// failed listener connection can hand the same cause to each of its listeners.
+    Transport(Arc<dyn std::error::Error + Send + Sync>),
+    /// The data to write couldn't be serialized to JSON.
+    Serialize(serde_json::Error),
+}
+
+impl RequestError {
+    /// The raw response body of a [`RequestError::Decode`], to see what the server actually sent.
+    pub fn body(&self) -> Option<&str> {
+        match self {
+            RequestError::Decode { body, .. } => Some(body),
+            _ => None,
+        }
+    }
+
+    /// A copy for handing one failure to several listeners. Causes that can't be cloned, such
+    /// as I/O errors, are rebuilt from their kind and message.
+    pub(crate) fn duplicate(&self) -> RequestError {
+        match self {
+            RequestError::NotJSON => RequestError::NotJSON,
+            RequestError::NoUTF8 => RequestError::NoUTF8,
+            RequestError::NetworkError => RequestError::NetworkError,
+            RequestError::SerializeError => RequestError::SerializeError,
+            RequestError::NotFoundOrNullBody => RequestError::NotFoundOrNullBody,
+            RequestError::InvalidQuery(e) => RequestError::InvalidQuery(e.clone()),
+            RequestError::Io(e) => RequestError::Io(duplicate_io(e)),
+            RequestError::Credentials(e) => RequestError::Credentials(e.duplicate()),
+            RequestError::Auth(e) => RequestError::Auth(e.duplicate()),
+            RequestError::TransactionConflict(attempts) => {
+                RequestError::TransactionConflict(*attempts)
+            }
+            RequestError::AlreadyExists => RequestError::AlreadyExists,
+            RequestError::PermissionDenied(message) => {
+                RequestError::PermissionDenied(message.clone())
+            }
+            RequestError::NotFound(message) => RequestError::NotFound(message.clone()),
+            RequestError::InvalidPath(message) => RequestError::InvalidPath(message.clone()),
+            RequestError::PreconditionFailed(message) => {
+                RequestError::PreconditionFailed(message.clone())
+            }
+            RequestError::Api { status, message } => RequestError::Api {
+                status: *status,
+                message: message.clone(),
+            },
+            RequestError::Decode {
+                path,
+                message,
+                body,
+            } => RequestError::Decode {
+                path: path.clone(),
+                message: message.clone(),
+                body: body.clone(),
+            },
+            RequestError::OverlappingPaths(path) => RequestError::OverlappingPaths(path.clone()),
+            RequestError::Transport(e) => RequestError::Transport(e.clone()),
+            RequestError::Serialize(e) => RequestError::Serialize(duplicate_json(e)),
+        }
+    }
+
+    pub(crate) fn transport<E>(err: E) -> RequestError
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        RequestError::Transport(Arc::new(err))
+    }
+}
+
+fn duplicate_io(e: &io::Error) -> io::Error {
+    io::Error::new(e.kind(), e.to_string())
+}
+
+fn duplicate_json(e: &serde_json::Error) -> serde_json::Error {
+    serde::de::Error::custom(e)
 }
 
 impl Display for RequestError {
@@ -38,6 +145,439 @@ impl Display for RequestError {
             RequestError::NetworkError => write!(f, "Network error"),
             RequestError::SerializeError => write!(f, "Serialize error"),
             RequestError::NotFoundOrNullBody => write!(f, "Body is null or record is not found"),
+            RequestError::InvalidQuery(e) => write!(f, "Invalid query: {}", e),
+            RequestError::Io(e) => write!(f, "Error while writing the response: {}", e),
+            RequestError::Credentials(e) => write!(f, "Could not refresh credentials: {}", e),
+            RequestError::Auth(e) => write!(f, "Could not refresh the ID token: {}", e),
+            RequestError::TransactionConflict(attempts) => {
+                write!(
+                    f,
+                    "Transaction still conflicting after {} attempts",
+                    attempts
+                )
+            }
+            RequestError::AlreadyExists => write!(f, "The location already holds data"),
+            RequestError::PermissionDenied(message) => write!(f, "Permission denied: {}", message),
+            RequestError::NotFound(message) => write!(f, "Not found: {}", message),
+            RequestError::InvalidPath(message) => write!(f, "{}", message),
+            RequestError::PreconditionFailed(message) => {
+                write!(f, "Precondition failed: {}", message)
+            }
+            RequestError::Api { status, message } => {
+                write!(f, "Request rejected with status {}: {}", status, message)
+            }
+            RequestError::Decode {
+                path: Some(path),
+                message,
+                ..
+            } => write!(f, "Invalid data at `{}`: {}", path, message),
+            RequestError::Decode { message, .. } => write!(f, "Invalid data: {}", message),
+            RequestError::OverlappingPaths(path) => {
+                write!(f, "`/{}` is also written by one of its ancestors", path)
+            }
+            RequestError::Transport(e) => write!(f, "Network error: {}", e),
+            RequestError::Serialize(e) => write!(f, "Serialize error: {}", e),
+        }
+    }
+}
+
+pub type QueryResult<T> = Result<T, QueryError>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    ZeroLimit(&'static str),
+    /// The limit is above the largest one the server accepts.
+    LimitTooLarge {
+        key: &'static str,
+        max: u32,
+    },
+    ConflictingLimits,
+    MissingOrderBy,
+    EmptyRange {
+        start: String,
+        end: String,
+    },
+    /// The server `timeout` isn't between 1 millisecond and 15 minutes.
+    InvalidTimeout(String),
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryError::ZeroLimit(key) => write!(f, "`{}` must be at least 1", key),
+            QueryError::LimitTooLarge { key, max } => {
+                write!(f, "`{}` must be at most {}", key, max)
+            }
+            QueryError::ConflictingLimits => {
+                write!(f, "`limitToFirst` and `limitToLast` can't be combined")
+            }
+            QueryError::MissingOrderBy => write!(f, "Filtering requires `orderBy`"),
+            QueryError::EmptyRange { start, end } => {
+                write!(f, "`startAt` ({}) is after `endAt` ({})", start, end)
+            }
+            QueryError::InvalidTimeout(timeout) => {
+                write!(
+                    f,
+                    "`timeout` must be between 1ms and 15min, got `{}`",
+                    timeout
+                )
+            }
+        }
+    }
+}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+/// Failure of a call to one of the Google management APIs (Hosting, App Distribution, ML).
+#[derive(Debug)]
+pub enum ApiError {
+    MissingAccessToken,
+    NetworkError,
+    /// The request to the API failed, e.g. in the transport or while refreshing credentials.
+    Request(RequestError),
+    Status {
+        status: u16,
+        body: String,
+    },
+    InvalidJson(serde_json::Error),
+    Io(std::io::Error),
+    Operation(String),
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::MissingAccessToken => write!(f, "The API requires an OAuth access token"),
+            ApiError::NetworkError => write!(f, "Network error"),
+            ApiError::Request(e) => write!(f, "{}", e),
+            ApiError::Status { status, body } => write!(f, "API returned {}: {}", status, body),
+            ApiError::InvalidJson(e) => write!(f, "Invalid API response: {}", e),
+            ApiError::Io(e) => write!(f, "Error while reading files: {}", e),
+            ApiError::Operation(e) => write!(f, "Long-running operation failed: {}", e),
+        }
+    }
+}
+
+pub type TokenResult<T> = Result<T, TokenError>;
+
+/// Why a signed token (App Check, ID token) was rejected.
+#[derive(Debug)]
+pub enum TokenError {
+    Malformed(String),
+    UnknownKey(String),
+    InvalidSignature,
+    Expired,
+    WrongIssuer,
+    WrongAudience,
+    NetworkError,
+    InvalidJson(serde_json::Error),
+}
+
+impl Display for TokenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenError::Malformed(e) => write!(f, "Malformed token: {}", e),
+            TokenError::UnknownKey(kid) => write!(f, "Token is signed with unknown key `{}`", kid),
+            TokenError::InvalidSignature => write!(f, "Invalid token signature"),
+            TokenError::Expired => write!(f, "Token has expired"),
+            TokenError::WrongIssuer => write!(f, "Token has an unexpected issuer"),
+            TokenError::WrongAudience => write!(f, "Token has an unexpected audience"),
+            TokenError::NetworkError => write!(f, "Network error while fetching signing keys"),
+            TokenError::InvalidJson(e) => write!(f, "Invalid signing keys: {}", e),
+        }
+    }
+}
+
+pub type CredentialResult<T> = Result<T, CredentialError>;
+
+#[derive(Debug)]
+pub enum CredentialError {
+    Io(std::io::Error),
+    InvalidJson(serde_json::Error),
+    UnsupportedType(String),
+    MissingField(&'static str),
+    NetworkError,
+    TokenExchange(String),
+    InvalidKey(String),
+    Url(UrlParseError),
+}
+
+impl Display for CredentialError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CredentialError::Io(e) => write!(f, "Error while reading credentials: {}", e),
+            CredentialError::InvalidJson(e) => write!(f, "Invalid credentials JSON: {}", e),
+            CredentialError::UnsupportedType(t) => write!(f, "Unsupported credentials type: {}", t),
+            CredentialError::MissingField(field) => {
+                write!(f, "Credentials are missing the `{}` field", field)
+            }
+            CredentialError::NetworkError => write!(f, "Network error"),
+            CredentialError::TokenExchange(e) => write!(f, "Token exchange failed: {}", e),
+            CredentialError::InvalidKey(e) => write!(f, "Invalid private key: {}", e),
+            CredentialError::Url(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl CredentialError {
+    fn duplicate(&self) -> CredentialError {
+        match self {
+            CredentialError::Io(e) => CredentialError::Io(duplicate_io(e)),
+            CredentialError::InvalidJson(e) => CredentialError::InvalidJson(duplicate_json(e)),
+            CredentialError::UnsupportedType(t) => CredentialError::UnsupportedType(t.clone()),
+            CredentialError::MissingField(field) => CredentialError::MissingField(field),
+            CredentialError::NetworkError => CredentialError::NetworkError,
+            CredentialError::TokenExchange(e) => CredentialError::TokenExchange(e.clone()),
+            CredentialError::InvalidKey(e) => CredentialError::InvalidKey(e.clone()),
+            CredentialError::Url(e) => CredentialError::Url(match e {
+                UrlParseError::NoPath => UrlParseError::NoPath,
+                UrlParseError::NotHttps => UrlParseError::NotHttps,
+                UrlParseError::Parser(e) => UrlParseError::Parser(*e),
+            }),
+        }
+    }
+}
+
+pub type AuthResult<T> = Result<T, AuthError>;
+
+/// Failure of an Identity Toolkit call.
+#[derive(Debug)]
+pub enum AuthError {
+    NetworkError,
+    /// The API refused the request, e.g. with `EMAIL_EXISTS` or `INVALID_PASSWORD`.
+    Rejected {
+        status: u16,
+        message: String,
+    },
+    InvalidJson(serde_json::Error),
+}
+
+impl AuthError {
+    fn duplicate(&self) -> AuthError {
+        match self {
+            AuthError::NetworkError => AuthError::NetworkError,
+            AuthError::Rejected { status, message } => AuthError::Rejected {
+                status: *status,
+                message: message.clone(),
+            },
+            AuthError::InvalidJson(e) => AuthError::InvalidJson(duplicate_json(e)),
+        }
+    }
+}
+
+impl Display for AuthError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::NetworkError => write!(f, "Network error"),
+            AuthError::Rejected { status, message } => {
+                write!(f, "Authentication failed ({}): {}", status, message)
+            }
+            AuthError::InvalidJson(e) => write!(f, "Invalid authentication response: {}", e),
+        }
+    }
+}
+
+pub type RealtimeResult<T> = Result<T, RealtimeError>;
+
+/// Failure on the realtime WebSocket connection.
+#[derive(Debug)]
+pub enum RealtimeError {
+    WebSocket(String),
+    Handshake(String),
+    Closed,
+    /// The server answered a request with a status other than `ok`, e.g. `permission_denied`.
+    Rejected {
+        status: String,
+        message: String,
+    },
+    SerializeError,
+    /// The credential could not be resolved before connecting, e.g. a failing
+    /// [`TokenProvider`](crate::TokenProvider).
+    Credential(RequestError),
+}
+
+impl Display for RealtimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RealtimeError::WebSocket(e) => write!(f, "WebSocket error: {}", e),
+            RealtimeError::Handshake(e) => write!(f, "Unexpected handshake: {}", e),
+            RealtimeError::Closed => write!(f, "The realtime connection is closed"),
+            RealtimeError::Rejected { status, message } => write!(f, "{}: {}", status, message),
+            RealtimeError::SerializeError => write!(f, "Serialize error"),
+            RealtimeError::Credential(e) => write!(f, "Could not resolve the credential: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for UrlParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UrlParseError::Parser(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for RequestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RequestError::InvalidQuery(e) => Some(e),
+            RequestError::Io(e) => Some(e),
+            RequestError::Credentials(e) => Some(e),
+            RequestError::Auth(e) => Some(e),
+            RequestError::Transport(e) => Some(e.as_ref()),
+            RequestError::Serialize(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApiError::InvalidJson(e) => Some(e),
+            ApiError::Io(e) => Some(e),
+            ApiError::Request(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for TokenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TokenError::InvalidJson(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for CredentialError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CredentialError::Io(e) => Some(e),
+            CredentialError::InvalidJson(e) => Some(e),
+            CredentialError::Url(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for AuthError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AuthError::InvalidJson(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for RealtimeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RealtimeError::Credential(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+pub type FirebaseResult<T> = Result<T, FirebaseError>;
+
+/// Any error this crate returns, for code that calls into several parts of it and wants a single
+/// error type to propagate with `?`. Each variant wraps the error of one area unchanged.
+#[derive(Debug)]
+pub enum FirebaseError {
+    Url(UrlParseError),
+    Request(RequestError),
+    Query(QueryError),
+    Api(ApiError),
+    Token(TokenError),
+    Credentials(CredentialError),
+    Auth(AuthError),
+    Realtime(RealtimeError),
+}
+
+impl Display for FirebaseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FirebaseError::Url(e) => write!(f, "{}", e),
+            FirebaseError::Request(e) => write!(f, "{}", e),
+            FirebaseError::Query(e) => write!(f, "{}", e),
+            FirebaseError::Api(e) => write!(f, "{}", e),
+            FirebaseError::Token(e) => write!(f, "{}", e),
+            FirebaseError::Credentials(e) => write!(f, "{}", e),
+            FirebaseError::Auth(e) => write!(f, "{}", e),
+            FirebaseError::Realtime(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for FirebaseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FirebaseError::Url(e) => e.source(),
+            FirebaseError::Request(e) => e.source(),
+            FirebaseError::Query(e) => e.source(),
+            FirebaseError::Api(e) => e.source(),
+            FirebaseError::Token(e) => e.source(),
+            FirebaseError::Credentials(e) => e.source(),
+            FirebaseError::Auth(e) => e.source(),
+            FirebaseError::Realtime(e) => e.source(),
         }
     }
 }
+
+macro_rules! into_firebase_error {
+    ($($error:ident => $variant:ident),*) => {
+        $(impl From<$error> for FirebaseError {
+            fn from(e: $error) -> Self {
+                FirebaseError::$variant(e)
+            }
+        })*
+    };
+}
+
+into_firebase_error!(
+    UrlParseError => Url,
+    RequestError => Request,
+    QueryError => Query,
+    ApiError => Api,
+    TokenError => Token,
+    CredentialError => Credentials,
+    AuthError => Auth,
+    RealtimeError => Realtime
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::{FirebaseError, FirebaseResult, RequestError};
+    use crate::{Firebase, RequestableExt};
+    use std::error::Error;
+
+    #[tokio::test]
+    async fn unified_errors() {
+        async fn read(uri: &str) -> FirebaseResult<u32> {
+            Ok(Firebase::new(uri)?.at("counter").get::<u32>().await?)
+        }
+
+        let err = read("http://myfirebase.firebaseio.com").await.unwrap_err();
+        assert!(matches!(err, FirebaseError::Url(_)));
+        assert_eq!(err.to_string(), "The URL protocol should be https.");
+
+        let io = std::io::Error::other("disk full");
+        let err = FirebaseError::from(RequestError::Io(io));
+        assert_eq!(err.source().unwrap().to_string(), "disk full");
+
+        let refused = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused");
+        let err = FirebaseError::from(RequestError::transport(refused));
+        assert_eq!(err.to_string(), "Network error: refused");
+        assert_eq!(err.source().unwrap().to_string(), "refused");
+
+        let err = FirebaseError::from(RequestError::Serialize(
+            serde_json::to_vec(&std::collections::HashMap::from([((1, 2), 3)])).unwrap_err(),
+        ));
+        assert_eq!(err.source().unwrap().to_string(), "key must be a string");
+    }
+}
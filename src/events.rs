@@ -0,0 +1,129 @@
+use crate::endpoint::Endpoint;
+use crate::errors::RequestResult;
+use crate::json::from_json;
+use crate::sse::SseEvent;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// A change notification from a streaming listener.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// `data` replaces everything at `path`, relative to the listened location.
+    Put {
+        path: String,
+        data: Value,
+    },
+    /// `data` holds children to merge into the node at `path`.
+    Patch {
+        path: String,
+        data: Value,
+    },
+    KeepAlive,
+    /// The security rules no longer allow reading this location; the server closes the stream.
+    Cancel,
+    /// The credential expired or was revoked; the server closes the stream.
+    AuthRevoked,
+}
+
+pub type EventStream = BoxStream<'static, RequestResult<Event>>;
+
+#[derive(Deserialize)]
+struct Change {
+    path: String,
+    data: Value,
+}
+
+impl Event {
+    /// Interprets a raw frame. Event types this crate doesn't know about yield `None`.
+    pub fn from_sse(event: &SseEvent) -> RequestResult<Option<Event>> {
+        let change = || from_json::<Change>(event.data.as_bytes());
+
+        let event = match event.event.as_str() {
+            "put" => {
+                let Change { path, data } = change()?;
+                Event::Put { path, data }
+            }
+            "patch" => {
+                let Change { path, data } = change()?;
+                Event::Patch { path, data }
+            }
+            "keep-alive" => Event::KeepAlive,
+            "cancel" => Event::Cancel,
+            "auth_revoked" => Event::AuthRevoked,
+            _ => return Ok(None),
+        };
+
+        Ok(Some(event))
+    }
+}
+
+impl Endpoint {
+    /// Like [`stream`](Endpoint::stream), with each frame parsed into an [`Event`].
+    ///
+    /// ```
+    /// use firebase_rs::{Event, Firebase};
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run() {
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let mut events = users.listen().await.unwrap();
+    /// while let Some(Ok(event)) = events.next().await {
+    ///     if let Event::Put { path, data } = event {
+    ///         println!("{} = {}", path, data);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn listen(&self) -> RequestResult<EventStream> {
+        let frames = self.stream().await?;
+        let events = frames.filter_map(|frame| async move {
+            match frame {
+                Ok(frame) => Event::from_sse(&frame).transpose(),
+                Err(err) => Some(Err(err)),
+            }
+        });
+
+        Ok(Box::pin(events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::Event;
+    use crate::sse::SseEvent;
+    use serde_json::json;
+
+    fn frame(event: &str, data: &str) -> SseEvent {
+        SseEvent {
+            event: event.to_string(),
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_frames() {
+        assert_eq!(
+            Event::from_sse(&frame("patch", r#"{"path":"/a","data":{"b":1}}"#)).unwrap(),
+            Some(Event::Patch {
+                path: "/a".to_string(),
+                data: json!({ "b": 1 })
+            })
+        );
+        assert_eq!(
+            Event::from_sse(&frame("put", r#"{"path":"/","data":null}"#)).unwrap(),
+            Some(Event::Put {
+                path: "/".to_string(),
+                data: json!(null)
+            })
+        );
+        assert_eq!(
+            Event::from_sse(&frame("auth_revoked", "credential is no longer valid")).unwrap(),
+            Some(Event::AuthRevoked)
+        );
+        assert_eq!(Event::from_sse(&frame("rules_debug", "")).unwrap(), None);
+        let err = Event::from_sse(&frame("put", "not json")).unwrap_err();
+        assert_eq!(err.body(), Some("not json"));
+    }
+}
@@ -0,0 +1,216 @@
+use crate::constants::{Method, Response};
+use crate::endpoint::Endpoint;
+use crate::errors::{RequestError, RequestResult};
+use crate::json::from_json;
+use crate::params::{OutputMode, Paramable};
+use crate::requestable::{Requestable, RequestableExt};
+use serde::de::{DeserializeOwned, Error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+const PRIORITY_KEY: &str = ".priority";
+const VALUE_KEY: &str = ".value";
+
+/// A value together with its priority, in the shape of `format=export` responses: objects carry
+/// a `.priority` key, primitives are wrapped as `{".value": …, ".priority": …}`. Serializing
+/// produces the same shape, which the server accepts as a write.
+///
+/// Only the priority of the node itself is extracted; priorities of nested children stay in the
+/// data and are ignored by most `Deserialize` implementations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exported<T> {
+    pub value: T,
+    pub priority: Option<Value>,
+}
+
+impl<T> Exported<T> {
+    pub fn new(value: T, priority: impl Into<Value>) -> Self {
+        Self {
+            value,
+            priority: Some(priority.into()),
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for Exported<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = serde_json::to_value(&self.value).map_err(serde::ser::Error::custom)?;
+        let priority = match &self.priority {
+            Some(priority) => priority.clone(),
+            None => return value.serialize(serializer),
+        };
+
+        let mut map = match value {
+            Value::Object(map) => map,
+            value => {
+                let mut map = Map::new();
+                map.insert(VALUE_KEY.to_string(), value);
+                map
+            }
+        };
+        map.insert(PRIORITY_KEY.to_string(), priority);
+        map.serialize(serializer)
+    }
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Exported<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = Value::deserialize(deserializer)?;
+        let mut priority = None;
+        if let Value::Object(map) = &mut value {
+            priority = map
+                .remove(PRIORITY_KEY)
+                .filter(|priority| !priority.is_null());
+            if let Some(inner) = map.remove(VALUE_KEY) {
+                value = inner;
+            }
+        }
+
+        match T::deserialize(value) {
+            Ok(value) => Ok(Exported { value, priority }),
+            Err(err) => Err(D::Error::custom(err)),
+        }
+    }
+}
+
+impl Endpoint {
+    /// Reads this location with `format=export` and splits off its priority.
+    ///
+    /// ```
+    /// use firebase_rs::{Exported, Firebase};
+    ///
+    /// # async fn run() {
+    /// let task = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("tasks").at("TASK_ID");
+    /// if let Ok(Exported { value, priority }) = task.get_exported::<String>().await {
+    ///     println!("{} (priority {:?})", value, priority);
+    /// }
+    /// # }
+    /// ```
+    pub async fn get_exported<T>(&self) -> RequestResult<Exported<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let body = self.clone().output(OutputMode::Export).get_raw().await?;
+        from_json(body.as_bytes())
+    }
+
+    /// Overwrites this location with `data` and gives it `priority`, a number, string or null.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// # async fn run() {
+    /// let task = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("tasks").at("TASK_ID");
+    /// task.put_with_priority(&"write docs", 3).await;
+    /// # }
+    /// ```
+    pub async fn put_with_priority<T>(
+        &self,
+        data: &T,
+        priority: impl Into<Value>,
+    ) -> RequestResult<Response>
+    where
+        T: Serialize + Sync + ?Sized,
+    {
+        let exported = Exported::new(data, priority);
+        match serde_json::to_value(&exported) {
+            Ok(body) => self.request(Method::PUT, Some(body)).await,
+            Err(err) => Err(RequestError::Serialize(err)),
+        }
+    }
+
+    /// Changes only the priority of this location, leaving its data alone.
+    pub async fn set_priority(&self, priority: impl Into<Value>) -> RequestResult<Response> {
+        self.clone().at(PRIORITY_KEY).put(&priority.into()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::errors::RequestResult;
+    use crate::export::Exported;
+    use crate::hooks::HttpRequest;
+    use crate::Firebase;
+    use async_trait::async_trait;
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use std::sync::Mutex;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Task {
+        title: String,
+    }
+
+    #[test]
+    fn export_shapes() {
+        let task: Exported<Task> =
+            serde_json::from_value(json!({ "title": "docs", ".priority": 2.0 })).unwrap();
+        assert_eq!(task.value.title, "docs");
+        assert_eq!(task.priority, Some(json!(2.0)));
+
+        let name: Exported<String> =
+            serde_json::from_value(json!({ ".value": "Ada", ".priority": "a" })).unwrap();
+        assert_eq!(name, Exported::new("Ada".to_string(), "a"));
+
+        let plain: Exported<u32> = serde_json::from_value(json!(5)).unwrap();
+        assert_eq!(plain.priority, None);
+
+        assert_eq!(
+            serde_json::to_value(Exported::new(json!({ "title": "docs" }), 1)).unwrap(),
+            json!({ "title": "docs", ".priority": 1 })
+        );
+        assert_eq!(
+            serde_json::to_value(Exported::new(7, "b")).unwrap(),
+            json!({ ".value": 7, ".priority": "b" })
+        );
+    }
+
+    #[derive(Debug, Default)]
+    struct Recording(Mutex<Vec<(String, Value)>>);
+
+    #[async_trait]
+    impl HttpClient for Recording {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            let body = serde_json::from_slice(request.body()).unwrap_or(Value::Null);
+            self.0
+                .lock()
+                .unwrap()
+                .push((request.uri().to_string(), body));
+            let response = r#"{".value":"docs",".priority":4}"#;
+            Ok(http::Response::new(response.as_bytes().to_vec()))
+        }
+    }
+
+    #[tokio::test]
+    async fn priority_requests() {
+        let client = std::sync::Arc::new(Recording::default());
+        let task = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone())
+            .at("tasks/1");
+
+        let exported = task.get_exported::<String>().await.unwrap();
+        assert_eq!(exported, Exported::new("docs".to_string(), 4));
+        task.put_with_priority(&"docs", 4).await.unwrap();
+        task.set_priority(5).await.unwrap();
+
+        assert_eq!(
+            *client.0.lock().unwrap(),
+            vec![
+                (
+                    "https://myfirebase.firebaseio.com/tasks/1.json?format=export".to_string(),
+                    Value::Null
+                ),
+                (
+                    "https://myfirebase.firebaseio.com/tasks/1.json".to_string(),
+                    json!({ ".value": "docs", ".priority": 4 })
+                ),
+                (
+                    "https://myfirebase.firebaseio.com/tasks/1/.priority.json".to_string(),
+                    json!(5)
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,542 @@
+use crate::clients::{self, HttpClient};
+use crate::credentials::{AccessToken, CLOUD_PLATFORM_SCOPE};
+use crate::errors::{CredentialError, CredentialResult};
+use crate::secret::Secret;
+use crate::utils::{format_basic_utc, parse_rfc3339_utc};
+use hmac::{Hmac, Mac};
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use http::request::Builder;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+use url::Url;
+
+const TOKEN_EXCHANGE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+const AWS_SIGNATURE_ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const AWS_IMDSV2_TTL_HEADER: &str = "x-aws-ec2-metadata-token-ttl-seconds";
+const AWS_IMDSV2_TOKEN_HEADER: &str = "x-aws-ec2-metadata-token";
+
+/// Workload identity federation credentials (`"type": "external_account"`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExternalAccount {
+    pub audience: String,
+    pub subject_token_type: String,
+    pub token_url: String,
+    pub service_account_impersonation_url: Option<String>,
+    pub credential_source: CredentialSource,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CredentialSource {
+    pub file: Option<String>,
+    pub url: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub format: Option<SubjectTokenFormat>,
+    pub environment_id: Option<String>,
+    pub region_url: Option<String>,
+    pub regional_cred_verification_url: Option<String>,
+    pub imdsv2_session_token_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubjectTokenFormat {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub subject_token_field_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StsResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImpersonationResponse {
+    access_token: String,
+    expire_time: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AwsSecurityCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    token: Option<String>,
+}
+
+impl ExternalAccount {
+    pub async fn access_token(&self, scopes: &[&str]) -> CredentialResult<AccessToken> {
+        self.access_token_via(clients::default_client().as_ref(), scopes)
+            .await
+    }
+
+    pub(crate) async fn access_token_via(
+        &self,
+        client: &dyn HttpClient,
+        scopes: &[&str],
+    ) -> CredentialResult<AccessToken> {
+        let subject_token = self.subject_token(client).await?;
+
+        let sts_scope = match self.service_account_impersonation_url {
+            Some(_) => CLOUD_PLATFORM_SCOPE.to_string(),
+            None => scopes.join(" "),
+        };
+        let form = [
+            ("grant_type", TOKEN_EXCHANGE_GRANT_TYPE),
+            ("audience", &self.audience),
+            ("scope", &sts_scope),
+            ("requested_token_type", ACCESS_TOKEN_TYPE),
+            ("subject_token", subject_token.expose()),
+            ("subject_token_type", &self.subject_token_type),
+        ];
+        let form = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(form)
+            .finish();
+        let request = http::Request::post(&self.token_url)
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded");
+        let sts: StsResponse = send_json(client, request, form.into_bytes()).await?;
+        let expires_at = SystemTime::now() + Duration::from_secs(sts.expires_in.unwrap_or(3600));
+
+        let impersonation_url = match &self.service_account_impersonation_url {
+            Some(url) => url,
+            None => {
+                return Ok(AccessToken {
+                    token: Secret::new(sts.access_token),
+                    expires_at,
+                })
+            }
+        };
+
+        let request = http::Request::post(impersonation_url)
+            .header(AUTHORIZATION, format!("Bearer {}", sts.access_token))
+            .header(CONTENT_TYPE, "application/json");
+        let body = json!({ "scope": scopes, "lifetime": "3600s" }).to_string();
+        let impersonated: ImpersonationResponse =
+            send_json(client, request, body.into_bytes()).await?;
+        let expires_at = match parse_rfc3339_utc(&impersonated.expire_time) {
+            Some(expires_at) => expires_at,
+            None => {
+                return Err(CredentialError::TokenExchange(format!(
+                    "invalid expireTime `{}`",
+                    impersonated.expire_time
+                )))
+            }
+        };
+
+        Ok(AccessToken {
+            token: Secret::new(impersonated.access_token),
+            expires_at,
+        })
+    }
+
+    async fn subject_token(&self, client: &dyn HttpClient) -> CredentialResult<Secret> {
+        let source = &self.credential_source;
+
+        if let Some(environment_id) = &source.environment_id {
+            if environment_id.starts_with("aws") {
+                return self.aws_subject_token(client).await;
+            }
+            return Err(CredentialError::UnsupportedType(environment_id.clone()));
+        }
+
+        let raw = if let Some(file) = &source.file {
+            // Wasm builds have no tokio::fs; where a filesystem exists at all it is std's.
+            #[cfg(not(target_family = "wasm"))]
+            let read = tokio::fs::read_to_string(file).await;
+            #[cfg(target_family = "wasm")]
+            let read = std::fs::read_to_string(file);
+            match read {
+                Ok(raw) => raw,
+                Err(err) => return Err(CredentialError::Io(err)),
+            }
+        } else if let Some(url) = &source.url {
+            let mut request = http::Request::get(url);
+            for (key, value) in &source.headers {
+                request = request.header(key, value);
+            }
+            send_text(client, request, Vec::new()).await?
+        } else {
+            return Err(CredentialError::MissingField("credential_source"));
+        };
+
+        match &source.format {
+            Some(format) if format.kind == "json" => {
+                let field = match &format.subject_token_field_name {
+                    Some(field) => field,
+                    None => return Err(CredentialError::MissingField("subject_token_field_name")),
+                };
+                let value: Value = match serde_json::from_str(&raw) {
+                    Ok(value) => value,
+                    Err(err) => return Err(CredentialError::InvalidJson(err)),
+                };
+                match value.get(field).and_then(Value::as_str) {
+                    Some(token) => Ok(Secret::new(token)),
+                    None => Err(CredentialError::MissingField("subject_token_field_name")),
+                }
+            }
+            _ => Ok(Secret::new(raw.trim())),
+        }
+    }
+
+    async fn aws_subject_token(&self, client: &dyn HttpClient) -> CredentialResult<Secret> {
+        let source = &self.credential_source;
+
+        let session_token = match &source.imdsv2_session_token_url {
+            Some(url) => {
+                let request = http::Request::put(url).header(AWS_IMDSV2_TTL_HEADER, "300");
+                Some(send_text(client, request, Vec::new()).await?)
+            }
+            None => None,
+        };
+        let metadata = |url: &str| {
+            let request = http::Request::get(url);
+            match &session_token {
+                Some(token) => request.header(AWS_IMDSV2_TOKEN_HEADER, token),
+                None => request,
+            }
+        };
+
+        let region =
+            match std::env::var("AWS_REGION").or_else(|_| std::env::var("AWS_DEFAULT_REGION")) {
+                Ok(region) => region,
+                Err(_) => {
+                    let region_url = match &source.region_url {
+                        Some(url) => url,
+                        None => return Err(CredentialError::MissingField("region_url")),
+                    };
+                    let zone = send_text(client, metadata(region_url), Vec::new()).await?;
+                    region_of_zone(&zone).to_string()
+                }
+            };
+
+        let credentials = match (
+            std::env::var("AWS_ACCESS_KEY_ID"),
+            std::env::var("AWS_SECRET_ACCESS_KEY"),
+        ) {
+            (Ok(access_key_id), Ok(secret_access_key)) => AwsSecurityCredentials {
+                access_key_id,
+                secret_access_key,
+                token: std::env::var("AWS_SESSION_TOKEN").ok(),
+            },
+            _ => {
+                let url = match &source.url {
+                    Some(url) => url.trim_end_matches('/'),
+                    None => return Err(CredentialError::MissingField("url")),
+                };
+                let role = send_text(client, metadata(url), Vec::new()).await?;
+                let credentials = format!("{}/{}", url, role.trim());
+                send_json(client, metadata(&credentials), Vec::new()).await?
+            }
+        };
+
+        let verification_url = match &source.regional_cred_verification_url {
+            Some(url) => url.replace("{region}", &region),
+            None => {
+                return Err(CredentialError::MissingField(
+                    "regional_cred_verification_url",
+                ))
+            }
+        };
+        let request = sign_aws_request(
+            &verification_url,
+            &region,
+            &self.audience,
+            &credentials,
+            SystemTime::now(),
+        )?;
+
+        Ok(Secret::new(
+            url::form_urlencoded::byte_serialize(request.to_string().as_bytes())
+                .collect::<String>(),
+        ))
+    }
+}
+
+/// The region of an availability zone such as `us-east-1b`, which is the zone without its letter.
+fn region_of_zone(zone: &str) -> &str {
+    let mut region = zone.trim().chars();
+    region.next_back();
+    region.as_str()
+}
+
+/// Builds the signed `GetCallerIdentity` request that serves as the AWS subject token.
+fn sign_aws_request(
+    url: &str,
+    region: &str,
+    audience: &str,
+    credentials: &AwsSecurityCredentials,
+    now: SystemTime,
+) -> CredentialResult<Value> {
+    let parsed = match Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(_) => {
+            return Err(CredentialError::MissingField(
+                "regional_cred_verification_url",
+            ))
+        }
+    };
+    let host = parsed.host_str().unwrap_or_default().to_string();
+
+    let mut headers = vec![
+        ("host".to_string(), host),
+        ("x-amz-date".to_string(), format_basic_utc(now)),
+        (
+            "x-goog-cloud-target-resource".to_string(),
+            audience.to_string(),
+        ),
+    ];
+    if let Some(token) = &credentials.token {
+        headers.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+
+    let authorization =
+        aws_authorization("POST", &parsed, &headers, "", region, "sts", credentials);
+
+    let mut signed_headers = vec![json!({ "key": "Authorization", "value": authorization })];
+    for (key, value) in headers {
+        signed_headers.push(json!({ "key": key, "value": value }));
+    }
+
+    Ok(json!({
+        "url": url,
+        "method": "POST",
+        "headers": signed_headers,
+    }))
+}
+
+/// AWS Signature Version 4 `Authorization` header. `headers` must contain `x-amz-date`.
+fn aws_authorization(
+    method: &str,
+    url: &Url,
+    headers: &[(String, String)],
+    payload: &str,
+    region: &str,
+    service: &str,
+    credentials: &AwsSecurityCredentials,
+) -> String {
+    let mut headers: Vec<(String, String)> = headers
+        .iter()
+        .map(|(k, v)| (k.to_lowercase(), v.trim().to_string()))
+        .collect();
+    headers.sort();
+
+    let amz_date = headers
+        .iter()
+        .find(|(k, _)| k == "x-amz-date")
+        .map(|(_, v)| v.clone())
+        .unwrap_or_default();
+    let date = &amz_date[..8.min(amz_date.len())];
+
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (aws_encode(&k), aws_encode(&v)))
+        .collect();
+    query.sort();
+    let canonical_query = query
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(k, v)| format!("{}:{}\n", k, v))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(k, _)| k.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let path = if url.path().is_empty() {
+        "/"
+    } else {
+        url.path()
+    };
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method,
+        path,
+        canonical_query,
+        canonical_headers,
+        signed_headers,
+        hex::encode(Sha256::digest(payload.as_bytes()))
+    );
+
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "{}\n{}\n{}\n{}",
+        AWS_SIGNATURE_ALGORITHM,
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = format!("AWS4{}", credentials.secret_access_key);
+    let key = hmac_sha256(key.as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    let key = hmac_sha256(&key, b"aws4_request");
+    let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    format!(
+        "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+        AWS_SIGNATURE_ALGORITHM, credentials.access_key_id, scope, signed_headers, signature
+    )
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn aws_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+async fn send_text(
+    client: &dyn HttpClient,
+    request: Builder,
+    body: Vec<u8>,
+) -> CredentialResult<String> {
+    let request = match request.body(body) {
+        Ok(request) => request,
+        Err(_) => return Err(CredentialError::NetworkError),
+    };
+    let response = match client.execute(request).await {
+        Ok(response) => response,
+        Err(_) => return Err(CredentialError::NetworkError),
+    };
+    let status = response.status();
+
+    match String::from_utf8(response.into_body()) {
+        Ok(text) if status.is_success() => Ok(text),
+        Ok(text) => Err(CredentialError::TokenExchange(text)),
+        Err(_) => Err(CredentialError::NetworkError),
+    }
+}
+
+pub(crate) async fn send_json<T>(
+    client: &dyn HttpClient,
+    request: Builder,
+    body: Vec<u8>,
+) -> CredentialResult<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let text = send_text(client, request, body).await?;
+    match serde_json::from_str(&text) {
+        Ok(value) => Ok(value),
+        Err(err) => Err(CredentialError::InvalidJson(err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::errors::RequestResult;
+    use crate::external_account::{
+        aws_authorization, region_of_zone, AwsSecurityCredentials, ExternalAccount,
+    };
+    use crate::hooks::HttpRequest;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::time::{Duration, UNIX_EPOCH};
+    use url::Url;
+
+    #[test]
+    fn aws_sigv4_get_vanilla() {
+        let credentials = AwsSecurityCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            token: None,
+        };
+        let headers = vec![
+            ("Host".to_string(), "example.amazonaws.com".to_string()),
+            ("X-Amz-Date".to_string(), "20150830T123600Z".to_string()),
+        ];
+        let url = Url::parse("https://example.amazonaws.com/").unwrap();
+
+        assert_eq!(
+            aws_authorization(
+                "GET",
+                &url,
+                &headers,
+                "",
+                "us-east-1",
+                "service",
+                &credentials
+            ),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn region_drops_the_zone_letter() {
+        assert_eq!(region_of_zone("us-east-1b\n"), "us-east-1");
+        assert_eq!(region_of_zone("zoneé"), "zone");
+        assert_eq!(region_of_zone(""), "");
+    }
+
+    /// Serves the subject token, the STS exchange and the impersonation call.
+    #[derive(Debug)]
+    struct Federation;
+
+    #[async_trait]
+    impl HttpClient for Federation {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            let body = match request.uri().host() {
+                Some("metadata.internal") => "subject_token".to_string(),
+                Some("sts.googleapis.com") => {
+                    json!({ "access_token": "federated", "expires_in": 3600 }).to_string()
+                }
+                Some("iamcredentials.googleapis.com") => {
+                    assert_eq!(request.headers()["authorization"], "Bearer federated");
+                    json!({ "accessToken": "impersonated", "expireTime": "2030-01-01T00:00:00Z" })
+                        .to_string()
+                }
+                other => panic!("unexpected host {:?}", other),
+            };
+            Ok(http::Response::new(body.into_bytes()))
+        }
+    }
+
+    #[tokio::test]
+    async fn impersonated_token_expiry() {
+        let account: ExternalAccount = serde_json::from_value(json!({
+            "audience": "//iam.googleapis.com/projects/1/locations/global/workloadIdentityPools/pool/providers/oidc",
+            "subject_token_type": "urn:ietf:params:oauth:token-type:jwt",
+            "token_url": "https://sts.googleapis.com/v1/token",
+            "service_account_impersonation_url": "https://iamcredentials.googleapis.com/v1/projects/-/serviceAccounts/sa@project.iam.gserviceaccount.com:generateAccessToken",
+            "credential_source": { "url": "https://metadata.internal/token" }
+        }))
+        .unwrap();
+
+        let token = account
+            .access_token_via(&Federation, &["scope"])
+            .await
+            .unwrap();
+        assert_eq!(token.token.expose(), "impersonated");
+        assert_eq!(
+            token.expires_at,
+            UNIX_EPOCH + Duration::from_secs(1_893_456_000)
+        );
+    }
+}
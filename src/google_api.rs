@@ -0,0 +1,167 @@
+use crate::clients::HttpClient;
+use crate::credential::{AuthCredential, SharedAuth};
+use crate::errors::{ApiError, ApiResult};
+use crate::Firebase;
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_POLLS: u32 = 150;
+
+/// A long-running operation (`google.longrunning.Operation`).
+#[derive(Deserialize)]
+pub(crate) struct Operation {
+    pub name: String,
+    #[serde(default)]
+    pub done: bool,
+    pub error: Option<OperationError>,
+    pub response: Option<Value>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct OperationError {
+    pub message: String,
+}
+
+impl Operation {
+    /// The response once the operation is done.
+    pub fn result(self) -> ApiResult<Option<Value>> {
+        if !self.done {
+            return Ok(None);
+        }
+        if let Some(error) = self.error {
+            return Err(ApiError::Operation(error.message));
+        }
+
+        match self.response {
+            Some(response) => Ok(Some(response)),
+            None => Err(ApiError::Operation(format!(
+                "{} finished without a response",
+                self.name
+            ))),
+        }
+    }
+}
+
+/// Calls Google REST APIs with the transport and the OAuth access token of a [`Firebase`].
+#[derive(Debug, Clone)]
+pub(crate) struct ApiClient {
+    client: Arc<dyn HttpClient>,
+    auth: SharedAuth,
+}
+
+impl ApiClient {
+    pub fn new(firebase: &Firebase) -> Self {
+        Self {
+            client: firebase.client.clone(),
+            auth: firebase.auth.clone(),
+        }
+    }
+
+    pub async fn send(
+        &self,
+        method: http::Method,
+        url: &str,
+        content_type: &str,
+        body: Vec<u8>,
+    ) -> ApiResult<Vec<u8>> {
+        self.send_with(method, url, &[(CONTENT_TYPE.as_str(), content_type)], body)
+            .await
+    }
+
+    pub async fn send_with(
+        &self,
+        method: http::Method,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> ApiResult<Vec<u8>> {
+        if self.auth.refresh(self.client.as_ref()).await.is_err() {
+            return Err(ApiError::MissingAccessToken);
+        }
+        let token = match self.auth.get() {
+            Some(AuthCredential::AccessToken(token)) => token,
+            _ => return Err(ApiError::MissingAccessToken),
+        };
+
+        let mut request = http::Request::builder()
+            .method(method)
+            .uri(url)
+            .header(AUTHORIZATION, format!("Bearer {}", token.expose()));
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let request = request.body(body);
+        let request = match request {
+            Ok(request) => request,
+            Err(_) => return Err(ApiError::NetworkError),
+        };
+        let response = match self.client.execute(request).await {
+            Ok(response) => response,
+            Err(err) => return Err(ApiError::Request(err)),
+        };
+
+        let status = response.status();
+        let body = response.into_body();
+        if !status.is_success() {
+            return Err(ApiError::Status {
+                status: status.as_u16(),
+                body: String::from_utf8_lossy(&body).into_owned(),
+            });
+        }
+
+        Ok(body)
+    }
+
+    pub async fn json<T>(
+        &self,
+        method: http::Method,
+        url: &str,
+        body: Option<&Value>,
+    ) -> ApiResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let body = match body {
+            Some(body) => body.to_string().into_bytes(),
+            None => Vec::new(),
+        };
+        let response = self.send(method, url, "application/json", body).await?;
+        parse(&response)
+    }
+
+    /// Polls `operation` at `{api}/{name}` until it is done and returns its response.
+    pub async fn wait(&self, api: &str, mut operation: Operation) -> ApiResult<Value> {
+        for _ in 0..MAX_POLLS {
+            let name = operation.name.clone();
+            if let Some(response) = operation.result()? {
+                return Ok(response);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let url = format!("{}/{}", api, name);
+            operation = self.json(http::Method::GET, &url, None).await?;
+        }
+
+        Err(ApiError::Operation(format!(
+            "{} did not finish in time",
+            operation.name
+        )))
+    }
+}
+
+pub(crate) fn parse<T>(body: &[u8]) -> ApiResult<T>
+where
+    T: DeserializeOwned,
+{
+    // Some endpoints answer with an empty body where callers expect `{}`.
+    let body = if body.is_empty() { b"{}" } else { body };
+    match serde_json::from_slice(body) {
+        Ok(value) => Ok(value),
+        Err(err) => Err(ApiError::InvalidJson(err)),
+    }
+}
@@ -0,0 +1,29 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+pub type HttpRequest = http::Request<Vec<u8>>;
+
+pub type RequestHook = Arc<dyn Fn(&mut HttpRequest) + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub(crate) struct RequestHooks {
+    hooks: Arc<Vec<RequestHook>>,
+}
+
+impl RequestHooks {
+    pub fn push(&mut self, hook: RequestHook) {
+        Arc::make_mut(&mut self.hooks).push(hook);
+    }
+
+    pub fn apply(&self, request: &mut HttpRequest) {
+        for hook in self.hooks.iter() {
+            hook(request);
+        }
+    }
+}
+
+impl Debug for RequestHooks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RequestHooks({})", self.hooks.len())
+    }
+}
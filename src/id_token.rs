@@ -0,0 +1,280 @@
+use crate::auth::auth_emulator_host;
+use crate::clients::{self, HttpClient};
+use crate::errors::{TokenError, TokenResult};
+use crate::jwks::Jwks;
+use crate::utils::unix_seconds;
+use crate::Firebase;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::{Algorithm, Validation};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+const ID_TOKEN_JWKS_URL: &str =
+    "https://www.googleapis.com/service_accounts/v1/jwk/securetoken@system.gserviceaccount.com";
+const ID_TOKEN_ISSUER: &str = "https://securetoken.google.com/";
+const SESSION_COOKIE_JWKS_URL: &str =
+    "https://identitytoolkit.googleapis.com/v1/sessionCookiePublicKeys";
+const SESSION_COOKIE_ISSUER: &str = "https://session.firebase.google.com/";
+
+/// Claims of a verified Firebase ID token or session cookie.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct IdTokenClaims {
+    /// The user's ID (`localId`).
+    #[serde(rename = "sub")]
+    pub uid: String,
+    pub iss: String,
+    pub aud: String,
+    pub exp: u64,
+    pub iat: u64,
+    pub auth_time: u64,
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+    /// Sign-in provider details and any custom claims.
+    #[serde(flatten)]
+    pub claims: HashMap<String, Value>,
+}
+
+/// Verifies Firebase ID tokens (or session cookies) sent by clients against Google's public
+/// signing keys. Keys are cached between calls; clone the verifier to share the cache.
+#[derive(Debug, Clone)]
+pub struct IdTokenVerifier {
+    project_id: String,
+    issuer: &'static str,
+    jwks: Arc<Jwks>,
+    /// Accept the unsigned tokens of the Auth emulator.
+    emulated: bool,
+}
+
+impl IdTokenVerifier {
+    /// ```
+    /// use firebase_rs::IdTokenVerifier;
+    ///
+    /// # async fn run() {
+    /// let verifier = IdTokenVerifier::new("my-project");
+    /// match verifier.verify("ID_TOKEN").await {
+    ///     Ok(claims) => println!("request from {}", claims.uid),
+    ///     Err(err) => println!("rejected: {}", err),
+    /// }
+    /// # }
+    /// ```
+    pub fn new(project_id: &str) -> Self {
+        Self::with_client(project_id, clients::default_client())
+    }
+
+    /// Verifier for cookies created by
+    /// [`Firebase::create_session_cookie`](crate::Firebase::create_session_cookie).
+    ///
+    /// ```
+    /// use firebase_rs::IdTokenVerifier;
+    ///
+    /// # async fn run() {
+    /// let verifier = IdTokenVerifier::session_cookies("my-project");
+    /// let claims = verifier.verify("SESSION_COOKIE").await;
+    /// # }
+    /// ```
+    pub fn session_cookies(project_id: &str) -> Self {
+        Self::cookies_with_client(project_id, clients::default_client())
+    }
+
+    fn with_client(project_id: &str, client: Arc<dyn HttpClient>) -> Self {
+        Self {
+            project_id: project_id.to_string(),
+            issuer: ID_TOKEN_ISSUER,
+            jwks: Arc::new(Jwks::new(ID_TOKEN_JWKS_URL, client)),
+            emulated: auth_emulator_host().is_some(),
+        }
+    }
+
+    fn cookies_with_client(project_id: &str, client: Arc<dyn HttpClient>) -> Self {
+        Self {
+            project_id: project_id.to_string(),
+            issuer: SESSION_COOKIE_ISSUER,
+            jwks: Arc::new(Jwks::new(SESSION_COOKIE_JWKS_URL, client)),
+            emulated: auth_emulator_host().is_some(),
+        }
+    }
+
+    pub async fn verify(&self, token: &str) -> TokenResult<IdTokenClaims> {
+        if self.emulated {
+            return self.verify_emulated(token);
+        }
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_issuer(&[format!("{}{}", self.issuer, self.project_id)]);
+        validation.set_audience(&[&self.project_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims: IdTokenClaims = self.jwks.decode(token, &validation).await?;
+        if claims.uid.is_empty() {
+            return Err(TokenError::Malformed(String::from("empty `sub`")));
+        }
+        if claims.auth_time > unix_seconds(SystemTime::now()) {
+            return Err(TokenError::Malformed(String::from(
+                "`auth_time` is in the future",
+            )));
+        }
+
+        Ok(claims)
+    }
+
+    /// Checks the claims of an unsigned emulator token.
+    fn verify_emulated(&self, token: &str) -> TokenResult<IdTokenClaims> {
+        let payload = match token.split('.').nth(1) {
+            Some(payload) => payload,
+            None => return Err(TokenError::Malformed(String::from("not a JWT"))),
+        };
+        let payload = match URL_SAFE_NO_PAD.decode(payload) {
+            Ok(payload) => payload,
+            Err(err) => return Err(TokenError::Malformed(err.to_string())),
+        };
+        let claims: IdTokenClaims = match serde_json::from_slice(&payload) {
+            Ok(claims) => claims,
+            Err(err) => return Err(TokenError::Malformed(err.to_string())),
+        };
+
+        if claims.iss != format!("{}{}", self.issuer, self.project_id) {
+            return Err(TokenError::WrongIssuer);
+        }
+        if claims.aud != self.project_id {
+            return Err(TokenError::WrongAudience);
+        }
+        if claims.exp <= unix_seconds(SystemTime::now()) {
+            return Err(TokenError::Expired);
+        }
+        if claims.uid.is_empty() {
+            return Err(TokenError::Malformed(String::from("empty `sub`")));
+        }
+
+        Ok(claims)
+    }
+}
+
+impl Firebase {
+    /// Verifier that fetches keys through this instance's transport.
+    pub fn id_token_verifier(&self, project_id: &str) -> IdTokenVerifier {
+        IdTokenVerifier::with_client(project_id, self.client.clone())
+    }
+
+    /// Session cookie verifier that fetches keys through this instance's transport.
+    pub fn session_cookie_verifier(&self, project_id: &str) -> IdTokenVerifier {
+        IdTokenVerifier::cookies_with_client(project_id, self.client.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::TokenError;
+    use crate::jwks::testing::{keys, sign};
+    use crate::utils::unix_seconds;
+    use crate::Firebase;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use serde_json::json;
+    use std::time::{Duration, SystemTime};
+
+    #[tokio::test]
+    async fn verify_tokens() {
+        let verifier = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .id_token_verifier("my-project");
+        verifier.jwks.store(keys(), Duration::from_secs(60));
+
+        let now = unix_seconds(SystemTime::now());
+        let claims = |iss: &str, exp: u64| {
+            json!({
+                "sub": "uid",
+                "iss": iss,
+                "aud": "my-project",
+                "exp": exp,
+                "iat": now,
+                "auth_time": now,
+                "email": "ada@example.com",
+                "admin": true,
+            })
+        };
+
+        let verified = verifier
+            .verify(&sign(claims(
+                "https://securetoken.google.com/my-project",
+                now + 3600,
+            )))
+            .await
+            .unwrap();
+        assert_eq!(verified.uid, "uid");
+        assert_eq!(verified.email.as_deref(), Some("ada@example.com"));
+        assert_eq!(verified.claims["admin"], json!(true));
+
+        match verifier
+            .verify(&sign(claims(
+                "https://securetoken.google.com/other",
+                now + 3600,
+            )))
+            .await
+        {
+            Err(TokenError::WrongIssuer) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match verifier
+            .verify(&sign(claims(
+                "https://securetoken.google.com/my-project",
+                now - 3600,
+            )))
+            .await
+        {
+            Err(TokenError::Expired) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn session_cookies() {
+        let verifier = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .session_cookie_verifier("my-project");
+        verifier.jwks.store(keys(), Duration::from_secs(60));
+
+        let now = unix_seconds(SystemTime::now());
+        let claims = |iss: &str| json!({ "sub": "uid", "iss": iss, "aud": "my-project", "exp": now + 3600, "iat": now, "auth_time": now });
+
+        let verified = verifier
+            .verify(&sign(claims(
+                "https://session.firebase.google.com/my-project",
+            )))
+            .await
+            .unwrap();
+        assert_eq!(verified.uid, "uid");
+        match verifier
+            .verify(&sign(claims("https://securetoken.google.com/my-project")))
+            .await
+        {
+            Err(TokenError::WrongIssuer) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn emulator_tokens() {
+        let mut verifier = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .id_token_verifier("demo-project");
+        verifier.emulated = true;
+
+        let now = unix_seconds(SystemTime::now());
+        let claims = json!({
+            "sub": "uid",
+            "iss": "https://securetoken.google.com/demo-project",
+            "aud": "demo-project",
+            "exp": now + 3600,
+            "iat": now,
+            "auth_time": now,
+        });
+        let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+        let token = format!("eyJhbGciOiJub25lIiwidHlwIjoiSldUIn0.{}.", payload);
+
+        assert_eq!(verifier.verify(&token).await.unwrap().uid, "uid");
+    }
+}
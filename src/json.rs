@@ -0,0 +1,73 @@
+use crate::errors::{RequestError, RequestResult};
+use serde::de::DeserializeOwned;
+
+/// Decodes a response body, keeping the body in the [`RequestError::Decode`] it fails with. The
+/// `serde_path_to_error` feature adds the path of the field that didn't match.
+pub(crate) fn from_json<T: DeserializeOwned>(body: &[u8]) -> RequestResult<T> {
+    let decode_error = |path, message: String| RequestError::Decode {
+        path,
+        message,
+        body: String::from_utf8_lossy(body).into_owned(),
+    };
+
+    #[cfg(feature = "serde_path_to_error")]
+    {
+        let mut deserializer = serde_json::Deserializer::from_slice(body);
+        let value = match serde_path_to_error::deserialize(&mut deserializer) {
+            Ok(value) => value,
+            Err(err) => {
+                let path = Some(err.path().to_string());
+                return Err(decode_error(path, err.into_inner().to_string()));
+            }
+        };
+        match deserializer.end() {
+            Ok(()) => Ok(value),
+            Err(err) => Err(decode_error(Some(String::from(".")), err.to_string())),
+        }
+    }
+
+    #[cfg(not(feature = "serde_path_to_error"))]
+    match serde_json::from_slice(body) {
+        Ok(value) => Ok(value),
+        Err(err) => Err(decode_error(None, err.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::RequestError;
+    use crate::json::from_json;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize)]
+    struct User {
+        #[allow(dead_code)]
+        age: u32,
+    }
+
+    #[test]
+    fn decode_errors_keep_body() {
+        let body = br#"{"ada":{"age":36},"bob":{"age":"old"}}"#;
+        let err = from_json::<HashMap<String, User>>(body).unwrap_err();
+        assert_eq!(
+            err.body(),
+            Some(r#"{"ada":{"age":36},"bob":{"age":"old"}}"#)
+        );
+        match err {
+            RequestError::Decode { path, message, .. } => {
+                #[cfg(feature = "serde_path_to_error")]
+                assert_eq!(path.as_deref(), Some("bob.age"));
+                #[cfg(not(feature = "serde_path_to_error"))]
+                assert_eq!(path, None);
+                assert!(message.starts_with("invalid type: string \"old\""));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        assert!(matches!(
+            from_json::<u32>(b"1 2"),
+            Err(RequestError::Decode { .. })
+        ));
+    }
+}
@@ -0,0 +1,247 @@
+use crate::clients::HttpClient;
+use crate::errors::{TokenError, TokenResult};
+use http::header::CACHE_CONTROL;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{DecodingKey, Header, Validation};
+use serde::de::DeserializeOwned;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Used when the key endpoint doesn't say how long its keys stay valid.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Shortest gap between two fetches triggered by an unknown `kid`, so that tokens with made-up
+/// key IDs can't make every verification call out to the key endpoint.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Public signing keys published at a JWKS endpoint, cached for as long as the endpoint allows.
+#[derive(Debug)]
+pub(crate) struct Jwks {
+    url: &'static str,
+    client: Arc<dyn HttpClient>,
+    cache: RwLock<Option<CachedKeys>>,
+}
+
+#[derive(Debug)]
+struct CachedKeys {
+    keys: JwkSet,
+    fetched_at: Instant,
+    expires_at: Instant,
+}
+
+impl Jwks {
+    pub fn new(url: &'static str, client: Arc<dyn HttpClient>) -> Self {
+        Self {
+            url,
+            client,
+            cache: RwLock::new(None),
+        }
+    }
+
+    /// Checks the header and signature of `token` and decodes its claims with `validation`.
+    pub async fn decode<T>(&self, token: &str, validation: &Validation) -> TokenResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let header = match jsonwebtoken::decode_header(token) {
+            Ok(header) => header,
+            Err(err) => return Err(TokenError::Malformed(err.to_string())),
+        };
+        let key = self.key(&header).await?;
+
+        match jsonwebtoken::decode::<T>(token, &key, validation) {
+            Ok(data) => Ok(data.claims),
+            Err(err) => Err(match err.kind() {
+                ErrorKind::InvalidSignature => TokenError::InvalidSignature,
+                ErrorKind::ExpiredSignature => TokenError::Expired,
+                ErrorKind::InvalidIssuer => TokenError::WrongIssuer,
+                ErrorKind::InvalidAudience => TokenError::WrongAudience,
+                _ => TokenError::Malformed(err.to_string()),
+            }),
+        }
+    }
+
+    async fn key(&self, header: &Header) -> TokenResult<DecodingKey> {
+        let kid = match &header.kid {
+            Some(kid) => kid,
+            None => return Err(TokenError::Malformed(String::from("missing `kid`"))),
+        };
+        if let Some(key) = self.cached(kid) {
+            return key;
+        }
+        if self.recently_fetched() {
+            return Err(TokenError::UnknownKey(kid.clone()));
+        }
+
+        self.refresh().await?;
+        match self.cached(kid) {
+            Some(key) => key,
+            None => Err(TokenError::UnknownKey(kid.clone())),
+        }
+    }
+
+    fn cached(&self, kid: &str) -> Option<TokenResult<DecodingKey>> {
+        let cache = match self.cache.read() {
+            Ok(cache) => cache,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let cached = cache.as_ref()?;
+        if cached.expires_at <= Instant::now() {
+            return None;
+        }
+
+        let jwk = cached.keys.find(kid)?;
+        Some(match DecodingKey::from_jwk(jwk) {
+            Ok(key) => Ok(key),
+            Err(err) => Err(TokenError::Malformed(err.to_string())),
+        })
+    }
+
+    /// Whether the cached keys are still valid and were fetched less than
+    /// [`MIN_REFRESH_INTERVAL`] ago.
+    fn recently_fetched(&self) -> bool {
+        let cache = match self.cache.read() {
+            Ok(cache) => cache,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let now = Instant::now();
+        cache.as_ref().is_some_and(|cached| {
+            cached.expires_at > now && now.duration_since(cached.fetched_at) < MIN_REFRESH_INTERVAL
+        })
+    }
+
+    async fn refresh(&self) -> TokenResult<()> {
+        let request = match http::Request::get(self.url).body(Vec::new()) {
+            Ok(request) => request,
+            Err(_) => return Err(TokenError::NetworkError),
+        };
+        let response = match self.client.execute(request).await {
+            Ok(response) if response.status().is_success() => response,
+            _ => return Err(TokenError::NetworkError),
+        };
+
+        let max_age = response
+            .headers()
+            .get(CACHE_CONTROL)
+            .and_then(|value| value.to_str().ok())
+            .and_then(max_age)
+            .unwrap_or(DEFAULT_MAX_AGE);
+        let keys: JwkSet = match serde_json::from_slice(response.body()) {
+            Ok(keys) => keys,
+            Err(err) => return Err(TokenError::InvalidJson(err)),
+        };
+        self.store(keys, max_age);
+
+        Ok(())
+    }
+
+    pub fn store(&self, keys: JwkSet, max_age: Duration) {
+        let now = Instant::now();
+        let cached = CachedKeys {
+            keys,
+            fetched_at: now,
+            expires_at: now + max_age,
+        };
+        match self.cache.write() {
+            Ok(mut cache) => *cache = Some(cached),
+            Err(poisoned) => *poisoned.into_inner() = Some(cached),
+        }
+    }
+}
+
+/// Reads `max-age` out of a `Cache-Control` header value.
+fn max_age(cache_control: &str) -> Option<Duration> {
+    cache_control.split(',').find_map(|directive| {
+        let seconds = directive.trim().strip_prefix("max-age=")?;
+        seconds.parse().ok().map(Duration::from_secs)
+    })
+}
+
+/// Key pair shared by the tests of the token verifiers.
+#[cfg(test)]
+pub(crate) mod testing {
+    use jsonwebtoken::jwk::JwkSet;
+    use jsonwebtoken::{Algorithm, EncodingKey, Header};
+    use serde_json::{json, Value};
+
+    const RSA_KEY: &str = include_str!("testdata/rsa.pem");
+    const RSA_N: &str = "oNuOw5foq5svfpKU2VU8AdZ6sgEPrFcFxilR4kSvm2c4gKvoI3B6BmsezgZnOx_9PfedoD1QJIkrlAbtppd1MmSSWmGCX9VpQX_-jPuLkX52SSnURVFxDa-0W6eOOAtPTf7Xu6l2FDgEMGGUtcn3a61wnApig-8b29B8VpwK2hyN75vWkN7X4XDxRE7nXMZEl5D7-2nggSFCcJqzmFJMHsQCL8LEmXZD4rmfCMMko6PRPsSMrB1_ndqKTL1s8jWLuHITfPGMjH8LvlWyaIdJrCYDDSDL9vb-zjCJaBYxHicEuEavUPz1khLrPGEToAUuqCJg1Rklmb7vc6JqY4X2aQ";
+
+    /// Signs `claims` as an RS256 JWT with the key ID `test`.
+    pub fn sign(claims: Value) -> String {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some("test".to_string());
+        let key = EncodingKey::from_rsa_pem(RSA_KEY.as_bytes()).unwrap();
+        jsonwebtoken::encode(&header, &claims, &key).unwrap()
+    }
+
+    /// The public half of the signing key, published as `test`.
+    pub fn keys() -> JwkSet {
+        serde_json::from_value(json!({
+            "keys": [{ "kty": "RSA", "alg": "RS256", "use": "sig", "kid": "test", "n": RSA_N, "e": "AQAB" }]
+        }))
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::errors::{RequestResult, TokenError};
+    use crate::hooks::HttpRequest;
+    use crate::jwks::testing::keys;
+    use crate::jwks::{max_age, Jwks};
+    use async_trait::async_trait;
+    use jsonwebtoken::{Algorithm, EncodingKey, Header, Validation};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Serves the test key set and counts how often it was fetched.
+    #[derive(Debug, Default)]
+    struct KeyEndpoint(AtomicUsize);
+
+    #[async_trait]
+    impl HttpClient for KeyEndpoint {
+        async fn execute(&self, _: HttpRequest) -> RequestResult<HttpResponse> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(http::Response::new(serde_json::to_vec(&keys()).unwrap()))
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_keys_dont_force_fetches() {
+        let endpoint = Arc::new(KeyEndpoint::default());
+        let jwks = Jwks::new("https://keys.example.com", endpoint.clone());
+
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("made-up".to_string());
+        let token =
+            jsonwebtoken::encode(&header, &json!({}), &EncodingKey::from_secret(b"x")).unwrap();
+        let validation = Validation::new(Algorithm::RS256);
+
+        for _ in 0..3 {
+            assert!(matches!(
+                jwks.decode::<Value>(&token, &validation).await,
+                Err(TokenError::UnknownKey(kid)) if kid == "made-up"
+            ));
+        }
+        assert_eq!(endpoint.0.load(Ordering::SeqCst), 1);
+
+        // Expired keys are fetched again.
+        jwks.store(keys(), Duration::ZERO);
+        assert!(jwks.decode::<Value>(&token, &validation).await.is_err());
+        assert_eq!(endpoint.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn cache_control() {
+        assert_eq!(
+            max_age("public, max-age=21600, must-revalidate"),
+            Some(Duration::from_secs(21600))
+        );
+        assert_eq!(max_age("no-cache"), None);
+    }
+}
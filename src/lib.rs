@@ -1,22 +1,129 @@
-use constants::{Method, Response, AUTH};
-use errors::{RequestError, RequestResult, UrlParseError, UrlParseResult};
-use params::Params;
-use reqwest::{Client, StatusCode};
-use serde::de::DeserializeOwned;
-use serde::Serialize;
-use serde_json::Value;
-use std::fmt::Debug;
+#[cfg(feature = "jwt")]
+pub use app_check::{AppCheckClaims, AppCheckVerifier};
+pub use app_distribution::{AppDistribution, AppRelease, TesterGroup};
+pub use auth::{Auth, AuthSession, IdpCredential, ProviderUserInfo, SessionTokens, UserData};
+pub use batch::{BatchResult, WriteBatch, DEFAULT_BATCH_PARALLELISM};
+pub use children::{diff_children, ChildEvent, ChildOrder};
+#[cfg(all(feature = "workers", target_arch = "wasm32"))]
+pub use clients::WorkersClient;
+pub use clients::{BodyStream, HttpClient, HttpResponse, StreamingResponse, Timeout};
+#[cfg(feature = "reqwest")]
+pub use clients::{ReqwestClient, ReqwestOptions};
+#[cfg(feature = "wasi")]
+pub use clients::{WasiClient, WasiOptions};
+pub use config::{AuthMode, FirebaseConfig, FirebaseOptions};
+pub use constants::{Method, PushResponse, Response};
+pub use credential::AuthCredential;
+use credential::SharedAuth;
+pub use credentials::{AccessToken, Credentials, CLOUD_PLATFORM_SCOPE, DATABASE_SCOPES};
+pub use endpoint::Endpoint;
+pub use errors::{ApiError, ApiResult, AuthError, AuthResult};
+pub use errors::{CredentialError, CredentialResult};
+pub use errors::{FirebaseError, FirebaseResult};
+pub use errors::{QueryError, QueryResult};
+pub use errors::{RealtimeError, RealtimeResult};
+pub use errors::{RequestError, RequestResult, UrlParseError, UrlParseResult};
+pub use errors::{TokenError, TokenResult};
+pub use events::{Event, EventStream};
+pub use export::Exported;
+pub use external_account::{CredentialSource, ExternalAccount, SubjectTokenFormat};
+use hooks::RequestHooks;
+pub use hooks::{HttpRequest, RequestHook};
+#[cfg(feature = "hosting")]
+pub use hosting::{Hosting, HostingRelease, HostingVersion};
+#[cfg(feature = "jwt")]
+pub use id_token::{IdTokenClaims, IdTokenVerifier};
+pub use listener::{Listener, ListenerHandle, ReconnectPolicy};
+pub use mirror::apply_event;
+pub use ml::{MlModel, MlModels, ModelState, TfliteModel};
+pub use multiplex::BufferStrategy;
+use multiplex::Hubs;
+use params::append_query_pair;
+pub use params::{OrderBy, OutputMode, Paramable, Params, QueryValue, WriteSizeLimit};
+pub use query::{Filter, Limited, Ordered, QueryBuilder, QuerySpec, Unlimited, Unordered};
+pub use queue::{ConcurrencyLimit, Priority};
+#[cfg(feature = "realtime")]
+pub use realtime::{OnDisconnect, Realtime, RealtimeEvents};
+pub use requestable::{Requestable, RequestableExt};
+pub use response::{FirebaseResponse, Maybe};
+pub use secret::Secret;
+pub use secrets::DatabaseSecrets;
+pub use server_time::ServerTimeOffset;
+#[cfg(feature = "jwt")]
+pub use service_account::ServiceAccount;
+pub use sinks::{EventHandler, EventStreamExt};
+pub use sse::{SseEvent, SseStream};
+use std::sync::Arc;
+use std::time::Duration;
+pub use throttle::{BandwidthLimits, Throttled};
+pub use token_cache::{clear_token_cache, EXPIRY_MARGIN};
+pub use token_provider::TokenProvider;
+pub use transaction::DEFAULT_TRANSACTION_RETRIES;
 use url::Url;
 use utils::check_uri;
 
+#[cfg(feature = "jwt")]
+mod app_check;
+mod app_distribution;
+mod auth;
+mod batch;
+mod bulk;
+mod children;
+mod clients;
+mod config;
 mod constants;
+mod credential;
+mod credentials;
+mod endpoint;
 mod errors;
+mod events;
+mod export;
+mod external_account;
+mod google_api;
+mod hooks;
+#[cfg(feature = "hosting")]
+mod hosting;
+#[cfg(feature = "jwt")]
+mod id_token;
+mod json;
+#[cfg(feature = "jwt")]
+mod jwks;
+mod listener;
+mod mirror;
+mod ml;
+mod multiplex;
+mod ordered;
+mod pagination;
 mod params;
+mod query;
+mod queue;
+#[cfg(feature = "realtime")]
+mod realtime;
+mod requestable;
+mod response;
+mod secret;
+mod secrets;
+mod server_time;
+#[cfg(feature = "jwt")]
+mod service_account;
+mod sinks;
+mod sse;
+mod throttle;
+mod token_cache;
+mod token_provider;
+mod transaction;
 mod utils;
 
-#[derive(Debug)]
+/// Handle to a database. Cloning is cheap: clones share the HTTP client, the credentials and the
+/// request hooks, and can be moved freely between tasks.
+#[derive(Debug, Clone)]
 pub struct Firebase {
     uri: Url,
+    auth: SharedAuth,
+    hooks: RequestHooks,
+    client: Arc<dyn HttpClient>,
+    timeout: Option<Duration>,
+    listeners: Hubs,
 }
 
 impl Firebase {
@@ -29,9 +136,14 @@ impl Firebase {
     where
         Self: Sized,
     {
-        match check_uri(&uri) {
+        match check_uri(uri) {
             Ok(uri) => Ok(Self {
                 uri,
+                auth: SharedAuth::default(),
+                hooks: RequestHooks::default(),
+                client: clients::default_client(),
+                timeout: None,
+                listeners: Hubs::default(),
             }),
             Err(err) => Err(err),
         }
@@ -46,250 +158,220 @@ impl Firebase {
     where
         Self: Sized,
     {
-        match check_uri(&uri) {
-            Ok(mut uri) => {
-                uri.set_query(Some(&format!("{}={}", AUTH, auth_key)));
-                Ok(Self {
-                    uri,
-                })
-            }
+        match check_uri(uri) {
+            Ok(uri) => Ok(Self {
+                uri,
+                auth: SharedAuth::new(Some(AuthCredential::DatabaseSecret(Secret::new(auth_key)))),
+                hooks: RequestHooks::default(),
+                client: clients::default_client(),
+                timeout: None,
+                listeners: Hubs::default(),
+            }),
             Err(err) => Err(err),
         }
     }
 
-    /// ```rust
-    /// use firebase_rs::Firebase;
+    /// Replaces the credentials of this instance and of every instance derived from it.
     ///
-    /// # async fn run() {
-    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_params().start_at(1).order_by("name").equal_to(5).finish();
-    /// let result = firebase.get::<String>().await;
-    /// # }
     /// ```
-    pub fn with_params(&self) -> Params {
-        let uri = self.uri.clone();
-        Params::new(uri)
+    /// use firebase_rs::{Firebase, Paramable};
+    ///
+    /// let firebase = Firebase::auth("https://myfirebase.firebaseio.com", "my_auth_key").unwrap();
+    /// let users = firebase.at("users").order_by("name");
+    /// firebase.set_auth("my_rotated_auth_key");
+    /// ```
+    pub fn set_auth(&self, auth_key: &str) {
+        self.set_credential(AuthCredential::DatabaseSecret(Secret::new(auth_key)));
     }
 
     /// ```
-    /// use firebase_rs::Firebase;
+    /// use firebase_rs::{AuthCredential, Firebase};
     ///
-    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID").at("f69111a8a5258c15286d3d0bd4688c55");
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// firebase.set_credential(AuthCredential::AccessToken("ya29.token".into()));
     /// ```
-    pub fn at(&mut self, path: &str) -> &mut Self {
-        let re_path: String = self
-            .uri
-            .path_segments()
-            .unwrap_or_else(|| panic!("cannot be base"))
-            .map(|seg| format!("{}/", seg.trim_end_matches(".json")))
-            .collect();
-
-        let new_path = re_path + path;
-
-        self.uri
-            .set_path(&format!("{}.json", new_path.trim_end_matches(".json")));
-
-        self
+    pub fn set_credential(&self, credential: AuthCredential) {
+        self.auth.set(Some(credential));
     }
 
+    /// Asks `provider` for the credential of every request made through this instance and every
+    /// instance derived from it. Setting a fixed credential afterwards removes the provider.
+    ///
     /// ```
-    /// use firebase_rs::Firebase;
+    /// use firebase_rs::{Credentials, Firebase};
     ///
-    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
-    /// let uri = firebase.get_uri();
+    /// # fn run() {
+    /// let credentials = Credentials::from_file("/etc/firebase/credentials.json").unwrap();
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// firebase.set_token_provider(credentials);
+    /// # }
     /// ```
-    pub fn get_uri(&self) -> String {
-        self.uri.to_string()
-    }
-
-    async fn request(&self, method: Method, data: Option<Value>) -> RequestResult<Response> {
-        let client = Client::new();
-
-        return match method {
-            Method::GET => {
-                let request = client.get(self.uri.to_string()).send().await;
-                match request {
-                    Ok(response) => {
-                        if response.status() == StatusCode::from_u16(200).unwrap() {
-                            return match response.text().await {
-                                Ok(data) => {
-                                    if data.as_str() == "null" {
-                                        return Err(RequestError::NotFoundOrNullBody);
-                                    }
-                                    return Ok(Response { data });
-                                }
-                                Err(_) => Err(RequestError::NotJSON),
-                            };
-                        } else {
-                            Err(RequestError::NetworkError)
-                        }
-                    }
-                    Err(_) => return Err(RequestError::NetworkError),
-                }
-            }
-            Method::POST => {
-                if !data.is_some() {
-                    return Err(RequestError::SerializeError);
-                }
-
-                let request = client.post(self.uri.to_string()).json(&data).send().await;
-                match request {
-                    Ok(response) => {
-                        let data = response.text().await.unwrap();
-                        Ok(Response { data })
-                    }
-                    Err(_) => Err(RequestError::NetworkError),
-                }
-            }
-            Method::PATCH => {
-                if !data.is_some() {
-                    return Err(RequestError::SerializeError);
-                }
-
-                let request = client.patch(self.uri.to_string()).json(&data).send().await;
-                match request {
-                    Ok(response) => {
-                        let data = response.text().await.unwrap();
-                        Ok(Response { data })
-                    }
-                    Err(_) => Err(RequestError::NetworkError),
-                }
-            }
-            Method::DELETE => {
-                let request = client.delete(self.uri.to_string()).send().await;
-                match request {
-                    Ok(_) => Ok(Response {
-                        data: String::default(),
-                    }),
-                    Err(_) => Err(RequestError::NetworkError),
-                }
-            }
-        };
+    pub fn set_token_provider<P>(&self, provider: P)
+    where
+        P: TokenProvider + 'static,
+    {
+        self.auth.set_provider(Arc::new(provider));
     }
 
-    async fn request_generic<T>(&self, method: Method) -> RequestResult<T>
+    /// ```
+    /// use firebase_rs::{AuthCredential, Firebase};
+    ///
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_token_provider(AuthCredential::DatabaseSecret("my_auth_key".into()));
+    /// ```
+    pub fn with_token_provider<P>(self, provider: P) -> Self
     where
-        T: Serialize + DeserializeOwned + Debug,
+        P: TokenProvider + 'static,
     {
-        let request = self.request(method, None).await;
-
-        match request {
-            Ok(response) => {
-                let data: T = serde_json::from_str(response.data.as_str()).unwrap();
-
-                Ok(data)
-            }
-            Err(err) => Err(err),
-        }
+        self.set_token_provider(provider);
+        self
     }
 
     /// ```
     /// use firebase_rs::Firebase;
-    /// use serde::{Serialize, Deserialize};
     ///
-    /// #[derive(Serialize, Deserialize, Debug)]
-    /// struct User {
-    ///    name: String
-    /// }
-    ///
-    /// # async fn run() {
-    /// let user = User { name: String::default() };
-    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
-    /// let users = firebase.set(&user).await;
-    /// # }
+    /// let firebase = Firebase::auth("https://myfirebase.firebaseio.com", "my_auth_key").unwrap();
+    /// firebase.clear_auth();
     /// ```
-    pub async fn set<T>(&self, data: &T) -> RequestResult<Response>
-    where
-        T: Serialize + DeserializeOwned + Debug,
-    {
-        let data = serde_json::to_value(&data).unwrap();
-        self.request(Method::POST, Some(data)).await
+    pub fn clear_auth(&self) {
+        self.auth.set(None);
     }
 
     /// ```
-    /// use std::collections::HashMap;
     /// use firebase_rs::Firebase;
-    /// use serde::{Serialize, Deserialize};
     ///
-    /// #[derive(Serialize, Deserialize, Debug)]
-    /// struct User {
-    ///    name: String
-    /// }
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_auth("my_auth_key");
+    /// ```
+    pub fn with_auth(self, auth_key: &str) -> Self {
+        self.set_auth(auth_key);
+        self
+    }
+
+    /// ```rust
+    /// use firebase_rs::{Firebase, Paramable, RequestableExt};
     ///
     /// # async fn run() {
-    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
-    /// let users = firebase.get::<HashMap<String, User>>().await;
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_params().start_at(1).order_by("name").equal_to(5);
+    /// let result = firebase.get::<String>().await;
     /// # }
     /// ```
-    pub async fn get_as_string(&self) -> RequestResult<Response> {
-        self.request(Method::GET, None).await
+    pub fn with_params(&self) -> Endpoint {
+        Endpoint::new(self)
     }
 
+    /// Registers a hook that runs on every finalized request right before it is sent.
+    ///
     /// ```
-    /// use std::collections::HashMap;
     /// use firebase_rs::Firebase;
-    /// use serde::{Serialize, Deserialize};
     ///
-    /// #[derive(Serialize, Deserialize, Debug)]
-    /// struct User {
-    ///     name: String
-    /// }
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_request_hook(|request| {
+    ///     let signature = format!("{} {} {}", request.method(), request.uri().path(), request.body().len());
+    ///     request.headers_mut().insert("x-gateway-signature", signature.parse().unwrap());
+    /// });
+    /// ```
+    pub fn with_request_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut HttpRequest) + Send + Sync + 'static,
+    {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// Sends updates as `POST` with `X-HTTP-Method-Override: PATCH`, which the database treats
+    /// as a `PATCH`, for proxies and hosts that block `PATCH`. Hooks registered earlier still
+    /// see the `PATCH`.
     ///
-    /// # async fn run() {
-    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID");
-    /// let user = firebase.get::<User>().await;
+    /// ```
+    /// use firebase_rs::Firebase;
     ///
-    ///  // OR
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_method_override();
+    /// ```
+    pub fn with_method_override(self) -> Self {
+        self.with_request_hook(|request| {
+            if request.method() == http::Method::PATCH {
+                *request.method_mut() = http::Method::POST;
+                request.headers_mut().insert(
+                    constants::METHOD_OVERRIDE,
+                    http::HeaderValue::from_static("PATCH"),
+                );
+            }
+        })
+    }
+
+    /// Sends requests through `client` instead of the default reqwest-based transport.
     ///
-    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
-    /// let user = firebase.get::<HashMap<String, User>>().await;
-    /// # }
     /// ```
-    pub async fn get<T>(&self) -> RequestResult<T>
+    /// use firebase_rs::{Firebase, ReqwestClient};
+    ///
+    /// let client = reqwest::Client::builder().local_address("10.0.0.2".parse().ok()).build().unwrap();
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_http_client(ReqwestClient::from(client));
+    /// ```
+    pub fn with_http_client<C>(mut self, client: C) -> Self
     where
-        T: Serialize + DeserializeOwned + Debug,
+        C: HttpClient + 'static,
     {
-        self.request_generic::<T>(Method::GET).await
+        self.client = Arc::new(client);
+        self
     }
 
+    /// Bounds every request, from connecting until the body has been read.
+    ///
     /// ```
+    /// use std::time::Duration;
     /// use firebase_rs::Firebase;
     ///
-    /// # async fn run() {
-    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID");
-    /// firebase.delete().await;
-    /// # }
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_timeout(Duration::from_secs(30));
     /// ```
-    pub async fn delete(&self) -> RequestResult<Response> {
-        self.request(Method::DELETE, None).await
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
     }
 
     /// ```
     /// use firebase_rs::Firebase;
-    /// use serde::{Serialize, Deserialize};
     ///
-    /// #[derive(Serialize, Deserialize, Debug)]
-    /// struct User {
-    ///     name: String
-    /// }
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID").at("f69111a8a5258c15286d3d0bd4688c55");
+    /// ```
+    pub fn at(&self, path: &str) -> Endpoint {
+        Endpoint::new(self).at(path)
+    }
+
+    /// ```
+    /// use firebase_rs::Firebase;
     ///
-    /// # async fn run() {
-    /// let user = User { name: String::default() };
-    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID");
-    /// let users = firebase.update(&user).await;
-    /// # }
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// let uri = firebase.get_uri();
     /// ```
-    pub async fn update<T>(&self, data: &T) -> RequestResult<Response>
-    where
-        T: DeserializeOwned + Serialize + Debug,
-    {
-        let value = serde_json::to_value(&data).unwrap();
-        self.request(Method::PATCH, Some(value)).await
+    pub fn get_uri(&self) -> String {
+        let mut uri = self.uri.clone();
+        if let Some(credential) = self.auth.get() {
+            let (key, value) = credential.query_param();
+            append_query_pair(&mut uri, key, value);
+        }
+        uri.to_string()
+    }
+
+    /// The database URL with the credential masked, safe to write to logs. Use
+    /// [`get_uri`](Firebase::get_uri) when the credential itself is needed.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// let firebase = Firebase::auth("https://myfirebase.firebaseio.com", "my_auth_key").unwrap();
+    /// assert_eq!(firebase.base_uri_redacted(), "https://myfirebase.firebaseio.com/?auth=***");
+    /// ```
+    pub fn base_uri_redacted(&self) -> String {
+        let mut uri = self.uri.clone();
+        if let Some(credential) = self.auth.get() {
+            let (key, value) = credential.redacted_query_param();
+            append_query_pair(&mut uri, key, value);
+        }
+        uri.to_string()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Firebase, UrlParseError};
+    use crate::{AuthCredential, Firebase, Paramable, UrlParseError};
 
     const URI: &str = "https://firebase_id.firebaseio.com";
     const URI_WITH_SLASH: &str = "https://firebase_id.firebaseio.com/";
@@ -304,18 +386,95 @@ mod tests {
     #[tokio::test]
     async fn non_https() {
         let firebase = Firebase::new(URI_NON_HTTPS).map_err(|e| e.to_string());
+        assert_eq!(firebase.err(), Some(UrlParseError::NotHttps.to_string()));
+    }
+
+    #[tokio::test]
+    async fn with_auth() {
+        let firebase = Firebase::auth(URI, "auth_key").unwrap();
+        assert_eq!(format!("{}/?auth=auth_key", URI), firebase.get_uri());
+    }
+
+    #[tokio::test]
+    async fn rotate_auth() {
+        let firebase = Firebase::auth(URI, "auth_key").unwrap();
+        let derived = firebase.with_params().shallow(true);
+
+        firebase.set_auth("rotated_key");
         assert_eq!(
-            firebase.err(),
-            Some(String::from(UrlParseError::NotHttps.to_string()))
+            format!("{}/.json?shallow=true&auth=rotated_key", URI),
+            derived.get_uri()
         );
+
+        firebase.clear_auth();
+        assert_eq!(format!("{}/.json?shallow=true", URI), derived.get_uri());
     }
 
     #[tokio::test]
-    async fn with_auth() {
+    async fn as_user() {
         let firebase = Firebase::auth(URI, "auth_key").unwrap();
+        let user = firebase.at("users").as_user("id_token");
+
+        firebase.set_auth("rotated_key");
+        assert_eq!(format!("{}/users.json?auth=id_token", URI), user.get_uri());
+        assert_eq!(format!("{}/?auth=rotated_key", URI), firebase.get_uri());
+    }
+
+    #[tokio::test]
+    async fn access_token() {
+        let firebase = Firebase::new(URI).unwrap();
+        firebase.set_credential(AuthCredential::AccessToken("ya29.token".into()));
         assert_eq!(
-            format!("{}/?auth=auth_key", URI.to_string()),
+            format!("{}/?access_token=ya29.token", URI),
             firebase.get_uri()
         );
     }
+
+    #[tokio::test]
+    async fn with_credentials() {
+        let admin = Firebase::auth(URI, "secret").unwrap();
+        let user = Firebase::auth(URI, "id_token").unwrap().at("users");
+
+        let as_admin = user.with_credentials(&admin);
+        admin.set_auth("rotated_secret");
+        assert_eq!(
+            format!("{}/users.json?auth=rotated_secret", URI),
+            as_admin.get_uri()
+        );
+        assert_eq!(format!("{}/users.json?auth=id_token", URI), user.get_uri());
+    }
+
+    #[tokio::test]
+    async fn clones_share_credentials() {
+        fn assert_send_sync<T: Send + Sync + Clone + 'static>() {}
+        assert_send_sync::<Firebase>();
+
+        let firebase = Firebase::auth(URI, "auth_key").unwrap();
+        let clone = firebase.clone();
+        let handle = tokio::spawn(async move { clone.set_auth("rotated_key") });
+        handle.await.unwrap();
+
+        assert_eq!(format!("{}/?auth=rotated_key", URI), firebase.get_uri());
+    }
+
+    #[tokio::test]
+    async fn debug_hides_auth() {
+        let firebase = Firebase::auth(URI, "auth_key").unwrap();
+        assert!(!format!("{:?}", firebase).contains("auth_key"));
+        assert!(!format!("{:?}", firebase.at("users")).contains("auth_key"));
+    }
+
+    #[test]
+    fn redacted_uris() {
+        let firebase = Firebase::auth(URI, "auth_key").unwrap();
+        assert_eq!(firebase.base_uri_redacted(), format!("{}/?auth=***", URI));
+        assert_eq!(
+            firebase.at("users").shallow(true).get_uri_redacted(),
+            format!("{}/users.json?shallow=true&auth=***", URI)
+        );
+        assert_eq!(
+            Firebase::new(URI).unwrap().base_uri_redacted(),
+            URI_WITH_SLASH
+        );
+    }
 }
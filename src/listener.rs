@@ -0,0 +1,836 @@
+use crate::clients;
+use crate::credential::AuthCredential;
+use crate::credentials::{Credentials, DATABASE_SCOPES};
+use crate::endpoint::Endpoint;
+use crate::errors::RequestError;
+use crate::events::{Event, EventStream};
+use crate::multiplex::{BufferStrategy, Buffering, HubKey};
+use crate::params::Paramable;
+use crate::sse::{SseEvent, SseStream};
+use crate::token_cache::EXPIRY_MARGIN;
+use futures::stream::{AbortHandle, Abortable};
+use futures::{Stream, StreamExt};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+use tokio::sync::oneshot;
+
+/// The server sends a `keep-alive` event about every 30 seconds, so three missed ones mean the
+/// connection is gone even if the socket doesn't say so.
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// How a [`Listener`] reopens its connection after it drops.
+///
+/// The delay before the `n`th consecutive attempt is `initial_delay * multiplier^(n - 1)`, capped
+/// at `max_delay`, minus a random share of up to `jitter` of it. The count resets once an event
+/// arrives on the new connection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    /// Between `0.0` (fixed delays) and `1.0` (anywhere from zero to the full delay).
+    pub jitter: f64,
+    /// Gives up after this many consecutive failures; `None` retries forever.
+    pub max_retries: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.5,
+            max_retries: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent);
+        let delay = delay.min(self.max_delay.as_secs_f64());
+
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        Duration::from_secs_f64(delay * (1.0 - jitter * random))
+    }
+
+    fn exhausted(&self, failures: u32) -> bool {
+        matches!(self.max_retries, Some(max) if failures > max)
+    }
+}
+
+/// Configures a long-lived subscription to a location, created with [`Endpoint::listener`].
+#[derive(Debug, Clone)]
+pub struct Listener {
+    endpoint: Endpoint,
+    reconnect: Option<ReconnectPolicy>,
+    heartbeat_timeout: Option<Duration>,
+    credentials: Option<Credentials>,
+    buffering: Buffering,
+}
+
+struct State {
+    listener: Listener,
+    connection: Option<SseStream>,
+    failures: u32,
+    done: bool,
+    /// Expiry of the access token fetched from the listener's credentials.
+    expires_at: Option<SystemTime>,
+}
+
+impl State {
+    /// Records a dropped connection, and hands back `err` once no retries are left.
+    fn failed(&mut self, err: RequestError) -> Option<RequestError> {
+        self.connection = None;
+        self.failures += 1;
+
+        match &self.listener.reconnect {
+            Some(policy) if !policy.exhausted(self.failures) => None,
+            _ => {
+                self.done = true;
+                Some(err)
+            }
+        }
+    }
+
+    /// Time left until the access token should be replaced, if the listener manages one.
+    fn token_lifetime(&self) -> Option<Duration> {
+        self.listener.credentials.as_ref()?;
+        let renew_at = self.expires_at? - EXPIRY_MARGIN;
+        Some(
+            renew_at
+                .duration_since(SystemTime::now())
+                .unwrap_or_default(),
+        )
+    }
+
+    fn token_expiring(&self) -> bool {
+        self.listener.credentials.is_some() && self.token_lifetime().unwrap_or_default().is_zero()
+    }
+
+    async fn refresh_token(&mut self) -> Result<(), RequestError> {
+        let credentials = match &self.listener.credentials {
+            Some(credentials) => credentials,
+            None => return Ok(()),
+        };
+
+        let endpoint = &self.listener.endpoint;
+        match credentials
+            .access_token_via(endpoint.client(), &DATABASE_SCOPES)
+            .await
+        {
+            Ok(token) => {
+                // Only this connection uses the token; clones of the endpoint keep their auth.
+                let credential = AuthCredential::AccessToken(token.token);
+                self.listener.endpoint = endpoint.with_credential(credential);
+                self.expires_at = Some(token.expires_at);
+                Ok(())
+            }
+            Err(err) => Err(RequestError::Credentials(err)),
+        }
+    }
+
+    async fn next(&mut self) -> Option<Result<SseEvent, RequestError>> {
+        loop {
+            if self.done {
+                return None;
+            }
+
+            let wait = match (self.listener.heartbeat_timeout, self.token_lifetime()) {
+                (Some(heartbeat), Some(lifetime)) => Some(heartbeat.min(lifetime)),
+                (heartbeat, lifetime) => heartbeat.or(lifetime),
+            };
+            let connection = match &mut self.connection {
+                Some(connection) => connection,
+                None => {
+                    if let Some(policy) = &self.listener.reconnect {
+                        if self.failures > 0 {
+                            clients::sleep(policy.delay(self.failures)).await;
+                        }
+                    }
+                    if self.token_expiring() {
+                        if let Err(err) = self.refresh_token().await {
+                            match self.failed(err) {
+                                Some(err) => return Some(Err(err)),
+                                None => continue,
+                            }
+                        }
+                    }
+                    match self.listener.endpoint.stream().await {
+                        Ok(connection) => self.connection = Some(connection),
+                        Err(err) => match self.failed(err) {
+                            Some(err) => return Some(Err(err)),
+                            None => continue,
+                        },
+                    }
+                    continue;
+                }
+            };
+
+            let next = match wait {
+                Some(wait) => match clients::timeout(wait, connection.next()).await {
+                    Some(next) => next,
+                    None if self.token_expiring() => {
+                        // Reconnect with a fresh token before the server revokes this one.
+                        self.connection = None;
+                        continue;
+                    }
+                    None => Some(Err(RequestError::NetworkError)),
+                },
+                None => connection.next().await,
+            };
+
+            match next {
+                Some(Ok(frame)) if frame.event == "cancel" => {
+                    // Access was denied; reconnecting would be refused the same way.
+                    self.done = true;
+                    return Some(Ok(frame));
+                }
+                Some(Ok(frame))
+                    if frame.event == "auth_revoked" && self.listener.credentials.is_some() =>
+                {
+                    if let Some(credentials) = &self.listener.credentials {
+                        credentials.forget(&DATABASE_SCOPES);
+                    }
+                    self.expires_at = None;
+                    self.connection = None;
+                }
+                Some(Ok(frame))
+                    if frame.event == "auth_revoked"
+                        && self.listener.endpoint.auth().has_provider() =>
+                {
+                    // Reconnecting asks the token provider for a new credential.
+                    self.connection = None;
+                }
+                Some(Ok(frame)) if frame.event == "auth_revoked" => {
+                    // Without a reconnect policy this ends the stream after the event.
+                    self.failed(RequestError::NetworkError);
+                    return Some(Ok(frame));
+                }
+                Some(Ok(frame)) => {
+                    self.failures = 0;
+                    return Some(Ok(frame));
+                }
+                Some(Err(err)) => {
+                    if let Some(err) = self.failed(err) {
+                        return Some(Err(err));
+                    }
+                }
+                None => {
+                    if self.failed(RequestError::NetworkError).is_some() {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Listener {
+    pub(crate) fn new(endpoint: Endpoint) -> Self {
+        Self {
+            endpoint,
+            reconnect: None,
+            heartbeat_timeout: Some(HEARTBEAT_TIMEOUT),
+            credentials: None,
+            buffering: Buffering::default(),
+        }
+    }
+
+    /// Reopens dropped connections instead of ending the stream. After reconnecting the
+    /// server sends the whole node again as a `Put` at `/`.
+    pub fn reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = Some(policy);
+        self
+    }
+
+    /// Treats the connection as dropped when nothing, not even a `keep-alive`, arrives for
+    /// `timeout`. Defaults to 90 seconds; `None` waits forever.
+    pub fn heartbeat_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.heartbeat_timeout = timeout;
+        self
+    }
+
+    /// Starts listening. Connection failures are reported through the stream.
+    ///
+    /// Listeners with the same location, query, credentials and settings share one connection;
+    /// a listener that joins later starts with a `Put` of the current state.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, ReconnectPolicy};
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run() {
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let mut events = users.listener().reconnect(ReconnectPolicy::default()).start();
+    /// while let Some(event) = events.next().await {
+    ///     println!("{:?}", event);
+    /// }
+    /// # }
+    /// ```
+    pub fn start(self) -> EventStream {
+        // Shared connections can only pass on a generic error, so report this one up front.
+        if let Err(err) = self.endpoint.params().validate() {
+            let err = RequestError::InvalidQuery(err);
+            return Box::pin(futures::stream::once(async { Err(err) }));
+        }
+
+        let settings = format!(
+            "{} {:?} {:?} {:?} {:?}",
+            self.endpoint.get_uri_redacted(),
+            self.reconnect,
+            self.heartbeat_timeout,
+            self.buffering,
+            self.credentials.as_ref().map(Credentials::principal)
+        );
+        let key = HubKey::new(settings, self.endpoint.auth().get());
+        let hubs = self.endpoint.listeners().clone();
+        let buffering = self.buffering;
+        hubs.subscribe(key, buffering, || self.connect())
+    }
+
+    /// Authenticates with access tokens for `credentials`, replacing them shortly before they
+    /// expire or when the server revokes them. The tokens are used by this listener only; the
+    /// originating [`Firebase`](crate::Firebase) and its clones keep their own credentials.
+    ///
+    /// ```
+    /// use firebase_rs::{Credentials, Firebase};
+    ///
+    /// # async fn run() {
+    /// let credentials = Credentials::from_file("/etc/firebase/credentials.json").unwrap();
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let events = users.listener().refresh_credentials(credentials).start();
+    /// # }
+    /// ```
+    pub fn refresh_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Buffers at most `capacity` events for a consumer that falls behind, then applies
+    /// `strategy`. Defaults to 256 events and [`BufferStrategy::Block`]. Dropping events leaves
+    /// a locally mirrored state out of date until the next full `Put`.
+    ///
+    /// ```
+    /// use firebase_rs::{BufferStrategy, Firebase};
+    ///
+    /// let prices = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("prices");
+    /// let events = prices.listener().buffer(16, BufferStrategy::DropOldest).start();
+    /// ```
+    pub fn buffer(mut self, capacity: usize, strategy: BufferStrategy) -> Self {
+        self.buffering = Buffering { capacity, strategy };
+        self
+    }
+
+    /// Like [`start`](Listener::start), with a handle to end the stream from elsewhere, e.g.
+    /// on shutdown.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run() {
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let (mut events, handle) = users.listener().start_with_handle();
+    /// let task = tokio::spawn(async move {
+    ///     while let Some(event) = events.next().await {
+    ///         println!("{:?}", event);
+    ///     }
+    /// });
+    ///
+    /// handle.stop();
+    /// handle.await_closed().await;
+    /// # }
+    /// ```
+    pub fn start_with_handle(self) -> (EventStream, ListenerHandle) {
+        let (abort, registration) = AbortHandle::new_pair();
+        let (closed, on_closed) = oneshot::channel();
+        let events = Closing {
+            events: Some(Abortable::new(self.start(), registration)),
+            _closed: Some(closed),
+        };
+
+        let handle = ListenerHandle {
+            abort,
+            closed: on_closed,
+        };
+        (Box::pin(events), handle)
+    }
+
+    /// Yields the frames as received instead of [`Event`]s, including event types this crate
+    /// doesn't know. Reconnection and the other settings still apply, but the connection isn't
+    /// shared with other listeners.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run() {
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let mut frames = users.listener().start_raw();
+    /// while let Some(Ok(frame)) = frames.next().await {
+    ///     println!("{}: {}", frame.event, frame.data);
+    /// }
+    /// # }
+    /// ```
+    pub fn start_raw(self) -> SseStream {
+        if let Err(err) = self.endpoint.params().validate() {
+            let err = RequestError::InvalidQuery(err);
+            return Box::pin(futures::stream::once(async { Err(err) }));
+        }
+        self.frames()
+    }
+
+    fn connect(self) -> EventStream {
+        let events = self.frames().filter_map(|frame| async move {
+            match frame {
+                Ok(frame) => Event::from_sse(&frame).transpose(),
+                Err(err) => Some(Err(err)),
+            }
+        });
+        Box::pin(events)
+    }
+
+    fn frames(self) -> SseStream {
+        let state = State {
+            listener: self,
+            connection: None,
+            failures: 0,
+            done: false,
+            expires_at: None,
+        };
+
+        Box::pin(futures::stream::unfold(state, |mut state| async move {
+            state.next().await.map(|event| (event, state))
+        }))
+    }
+}
+
+/// Queries narrow what a listener receives, e.g. only the newest entries of a long list.
+///
+/// ```
+/// use firebase_rs::{Firebase, Paramable};
+///
+/// let messages = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("messages");
+/// let tail = messages.listener().order_by("$key").limit_to_last(50).start();
+/// ```
+impl Paramable for Listener {
+    fn add_param<T>(mut self, key: &str, value: T) -> Self
+    where
+        T: ToString,
+    {
+        self.endpoint = self.endpoint.add_param(key, value);
+        self
+    }
+
+    fn remove_param(mut self, key: &str) -> Self {
+        self.endpoint = self.endpoint.remove_param(key);
+        self
+    }
+}
+
+/// Ends a stream from [`Listener::start_with_handle`].
+#[derive(Debug)]
+pub struct ListenerHandle {
+    abort: AbortHandle,
+    closed: oneshot::Receiver<()>,
+}
+
+impl ListenerHandle {
+    /// Ends the stream: its consumer sees `None` on the next poll and the connection is released.
+    pub fn stop(&self) {
+        self.abort.abort();
+    }
+
+    /// Waits until the stream has ended or was dropped.
+    pub async fn await_closed(self) {
+        let _ = self.closed.await;
+    }
+}
+
+/// Drops the underlying connection as soon as the stream ends, which is what
+/// [`ListenerHandle::await_closed`] waits for.
+struct Closing {
+    events: Option<Abortable<EventStream>>,
+    _closed: Option<oneshot::Sender<()>>,
+}
+
+impl Stream for Closing {
+    type Item = Result<Event, RequestError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let events = match self.events.as_mut() {
+            Some(events) => events,
+            None => return Poll::Ready(None),
+        };
+        match events.poll_next_unpin(cx) {
+            Poll::Ready(None) => {
+                self.events = None;
+                self._closed = None;
+                Poll::Ready(None)
+            }
+            other => other,
+        }
+    }
+}
+
+impl Endpoint {
+    pub fn listener(&self) -> Listener {
+        Listener::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{BodyStream, HttpClient, HttpResponse, StreamingResponse};
+    use crate::credential::AuthCredential;
+    use crate::credentials::{AccessToken, Credentials, DATABASE_SCOPES};
+    use crate::errors::RequestError;
+    use crate::errors::RequestResult;
+    use crate::events::Event;
+    use crate::hooks::HttpRequest;
+    use crate::listener::ReconnectPolicy;
+    use crate::params::Paramable;
+    use crate::requestable::Requestable;
+    use crate::secret::Secret;
+    use crate::token_cache::{self, CacheKey};
+    use crate::{Firebase, TokenProvider};
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, SystemTime};
+
+    #[derive(Debug, Default)]
+    struct Counting(AtomicUsize);
+
+    #[async_trait]
+    impl HttpClient for Counting {
+        async fn execute(&self, _request: HttpRequest) -> RequestResult<HttpResponse> {
+            let count = self.0.fetch_add(1, Ordering::SeqCst);
+            let body = format!(
+                "event: put\ndata: {{\"path\":\"/\",\"data\":{}}}\n\n",
+                count
+            );
+            Ok(http::Response::new(body.into_bytes()))
+        }
+    }
+
+    /// Sends one event per connection, then goes quiet without closing.
+    #[derive(Debug, Default)]
+    struct Silent(AtomicUsize);
+
+    #[async_trait]
+    impl HttpClient for Silent {
+        async fn execute(&self, _request: HttpRequest) -> RequestResult<HttpResponse> {
+            unreachable!()
+        }
+
+        async fn execute_streaming(
+            &self,
+            _request: HttpRequest,
+        ) -> RequestResult<StreamingResponse> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let event = b"event: put\ndata: {\"path\":\"/\",\"data\":1}\n\n".to_vec();
+            let body: BodyStream = Box::pin(
+                futures::stream::once(async { Ok(event) }).chain(futures::stream::pending()),
+            );
+            Ok(http::Response::new(body))
+        }
+    }
+
+    #[test]
+    fn backoff() {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.0,
+            max_retries: Some(3),
+        };
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(3), Duration::from_secs(4));
+        assert_eq!(policy.delay(10), Duration::from_secs(5));
+        assert!(!policy.exhausted(3));
+        assert!(policy.exhausted(4));
+
+        let jittered = ReconnectPolicy {
+            jitter: 1.0,
+            ..policy
+        };
+        assert!(jittered.delay(2) <= Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_close() {
+        let endpoint = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(Counting::default())
+            .at("users");
+
+        let once: Vec<_> = endpoint.listener().start().collect().await;
+        assert_eq!(once.len(), 1);
+
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_millis(1),
+            ..ReconnectPolicy::default()
+        };
+        let events: Vec<_> = endpoint
+            .listener()
+            .reconnect(policy)
+            .start()
+            .take(3)
+            .collect()
+            .await;
+        let values: Vec<_> = events
+            .into_iter()
+            .map(|event| match event.unwrap() {
+                Event::Put { data, .. } => data,
+                other => panic!("unexpected event: {:?}", other),
+            })
+            .collect();
+        assert_eq!(values, vec![json!(1), json!(2), json!(3)]);
+    }
+
+    #[derive(Debug, Default)]
+    struct Recording(std::sync::Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl HttpClient for Recording {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            self.0.lock().unwrap().push(request.uri().to_string());
+            let body = b"event: put\ndata: {\"path\":\"/\",\"data\":1}\n\n".to_vec();
+            Ok(http::Response::new(body))
+        }
+    }
+
+    #[tokio::test]
+    async fn listens_with_fresh_tokens() {
+        let credentials = Credentials::from_json(
+            r#"{
+            "type": "external_account",
+            "audience": "//iam.googleapis.com/projects/1/locations/global/workloadIdentityPools/listener/providers/test",
+            "subject_token_type": "urn:ietf:params:oauth:token-type:jwt",
+            "token_url": "https://sts.googleapis.com/v1/token",
+            "credential_source": { "file": "/nonexistent/token" }
+        }"#,
+        )
+        .unwrap();
+        let token = AccessToken {
+            token: Secret::new("listener_token"),
+            expires_at: SystemTime::now() + Duration::from_secs(3600),
+        };
+        token_cache::insert(
+            CacheKey::new(credentials.principal(), &DATABASE_SCOPES),
+            token,
+        );
+
+        let client = std::sync::Arc::new(Recording::default());
+        let firebase = Firebase::auth("https://myfirebase.firebaseio.com", "shared_secret")
+            .unwrap()
+            .with_http_client(client.clone());
+        let users = firebase.at("users");
+        let events: Vec<_> = users
+            .listener()
+            .refresh_credentials(credentials)
+            .start()
+            .collect()
+            .await;
+
+        assert_eq!(events.len(), 1);
+        assert!(client.0.lock().unwrap()[0].ends_with("access_token=listener_token"));
+        assert!(firebase.get_uri().ends_with("auth=shared_secret"));
+        assert!(users.get_uri().ends_with("auth=shared_secret"));
+    }
+
+    #[tokio::test]
+    async fn fresh_tokens_leave_the_token_provider_installed() {
+        let credentials = Credentials::from_json(
+            r#"{
+            "type": "external_account",
+            "audience": "//iam.googleapis.com/projects/1/locations/global/workloadIdentityPools/listener/providers/sibling",
+            "subject_token_type": "urn:ietf:params:oauth:token-type:jwt",
+            "token_url": "https://sts.googleapis.com/v1/token",
+            "credential_source": { "file": "/nonexistent/token" }
+        }"#,
+        )
+        .unwrap();
+        let token = AccessToken {
+            token: Secret::new("listener_token"),
+            expires_at: SystemTime::now() + Duration::from_secs(3600),
+        };
+        token_cache::insert(
+            CacheKey::new(credentials.principal(), &DATABASE_SCOPES),
+            token,
+        );
+
+        let client = std::sync::Arc::new(Recording::default());
+        let firebase = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone())
+            .with_token_provider(AuthCredential::DatabaseSecret("provider_secret".into()));
+        let events: Vec<_> = firebase
+            .at("users")
+            .listener()
+            .refresh_credentials(credentials)
+            .start()
+            .collect()
+            .await;
+        assert_eq!(events.len(), 1);
+
+        firebase.at("posts").get_raw().await.unwrap();
+        let requests = client.0.lock().unwrap();
+        assert!(requests[0].ends_with("access_token=listener_token"));
+        assert!(requests[1].ends_with("posts.json?auth=provider_secret"));
+    }
+
+    /// Revokes the credential of the first connection and sends one event on the next.
+    #[derive(Debug, Default)]
+    struct Revoking(AtomicUsize);
+
+    #[async_trait]
+    impl HttpClient for Revoking {
+        async fn execute(&self, _request: HttpRequest) -> RequestResult<HttpResponse> {
+            let body = match self.0.fetch_add(1, Ordering::SeqCst) {
+                0 => "event: auth_revoked\ndata: \"credential is no longer valid\"\n\n",
+                _ => "event: put\ndata: {\"path\":\"/\",\"data\":1}\n\n",
+            };
+            Ok(http::Response::new(body.as_bytes().to_vec()))
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct Rotating(std::sync::Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl TokenProvider for Rotating {
+        async fn token(&self) -> RequestResult<AuthCredential> {
+            let count = self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(AuthCredential::IdToken(format!("token_{}", count).into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn revoked_credentials_are_renewed_by_the_token_provider() {
+        let provider = Rotating::default();
+        let calls = provider.0.clone();
+        let events: Vec<_> = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(Revoking::default())
+            .with_token_provider(provider)
+            .at("users")
+            .listener()
+            .start()
+            .collect()
+            .await;
+
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], Ok(Event::Put { .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn stalled_connections_are_replaced() {
+        let client = std::sync::Arc::new(Silent::default());
+        let endpoint = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone())
+            .at("users");
+
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_millis(1),
+            ..ReconnectPolicy::default()
+        };
+        let events: Vec<_> = endpoint
+            .listener()
+            .reconnect(policy)
+            .heartbeat_timeout(Some(Duration::from_millis(20)))
+            .start()
+            .take(2)
+            .collect()
+            .await;
+        assert!(events.iter().all(|event| event.is_ok()));
+        assert_eq!(client.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn connections_are_shared_per_credential() {
+        let client = std::sync::Arc::new(Silent::default());
+        let endpoint = Firebase::auth("https://myfirebase.firebaseio.com", "shared_secret")
+            .unwrap()
+            .with_http_client(client.clone())
+            .at("users");
+
+        let mut listeners = [
+            endpoint.listener().start(),
+            endpoint.listener().start(),
+            endpoint.as_user("USER_ID_TOKEN").listener().start(),
+        ];
+        for events in &mut listeners {
+            assert!(events.next().await.unwrap().is_ok());
+        }
+        assert_eq!(client.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn stop_ends_the_stream() {
+        let endpoint = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(Silent::default())
+            .at("users");
+
+        let (mut events, handle) = endpoint
+            .listener()
+            .heartbeat_timeout(None)
+            .start_with_handle();
+        assert!(events.next().await.unwrap().is_ok());
+
+        handle.stop();
+        assert!(events.next().await.is_none());
+        handle.await_closed().await;
+    }
+
+    #[tokio::test]
+    async fn queries_reach_the_stream() {
+        let client = std::sync::Arc::new(Recording::default());
+        let messages = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone())
+            .at("messages");
+
+        let events: Vec<_> = messages
+            .listener()
+            .order_by("$key")
+            .limit_to_last(2)
+            .start()
+            .collect()
+            .await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            client.0.lock().unwrap()[0],
+            "https://myfirebase.firebaseio.com/messages.json?limitToLast=2&orderBy=%22%24key%22"
+        );
+
+        let invalid: Vec<_> = messages.listener().limit_to_last(2).start().collect().await;
+        assert!(matches!(invalid[..], [Err(RequestError::InvalidQuery(_))]));
+    }
+
+    #[tokio::test]
+    async fn raw_frames() {
+        let endpoint = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(Counting::default())
+            .at("users");
+
+        let frames: Vec<_> = endpoint.listener().start_raw().collect().await;
+        let frame = frames[0].as_ref().unwrap();
+        assert_eq!(frame.event, "put");
+        assert_eq!(frame.data, r#"{"path":"/","data":0}"#);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::errors::RequestResult;
+use crate::events::Event;
+use crate::listener::Listener;
+use futures::{Future, StreamExt};
+use serde_json::{Map, Value};
+use tokio::sync::watch;
+
+/// Applies a change notification to a local copy of the listened node.
+///
+/// ```
+/// use firebase_rs::{apply_event, Event};
+/// use serde_json::json;
+///
+/// let mut node = json!({ "a": 1, "b": { "c": 2 } });
+/// apply_event(&mut node, &Event::Patch { path: "/b".to_string(), data: json!({ "d": 3 }) });
+/// apply_event(&mut node, &Event::Put { path: "/a".to_string(), data: json!(null) });
+/// assert_eq!(node, json!({ "b": { "c": 2, "d": 3 } }));
+/// ```
+pub fn apply_event(node: &mut Value, event: &Event) {
+    match event {
+        Event::Put { path, data } => set(node, &segments(path), data.clone()),
+        Event::Patch { path, data } => {
+            let base = segments(path);
+            if let Value::Object(children) = data {
+                for (key, value) in children {
+                    let mut path = base.clone();
+                    path.extend(segments(key));
+                    set(node, &path, value.clone());
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn segments(path: &str) -> Vec<&str> {
+    path.split('/').filter(|s| !s.is_empty()).collect()
+}
+
+/// Writes `value` at `path`; `null` removes the key, and parents left empty disappear with it
+/// the way they do on the server.
+fn set(node: &mut Value, path: &[&str], value: Value) {
+    let (key, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            *node = value;
+            return;
+        }
+    };
+
+    if !node.is_object() {
+        if value.is_null() {
+            return;
+        }
+        *node = Value::Object(Map::new());
+    }
+    if let Value::Object(children) = node {
+        let child = children.entry(key.to_string()).or_insert(Value::Null);
+        set(child, rest, value);
+        if child.is_null() {
+            children.remove(*key);
+        }
+        if children.is_empty() {
+            *node = Value::Null;
+        }
+    }
+}
+
+impl Listener {
+    /// Keeps the latest state of the node in a `watch` channel. The returned future drives the
+    /// connection; spawn it on your runtime. It finishes when the stream ends or every receiver
+    /// is dropped.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// # async fn run() {
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let (mut snapshot, sync) = users.listener().mirror();
+    /// tokio::spawn(sync);
+    ///
+    /// while snapshot.changed().await.is_ok() {
+    ///     println!("{}", *snapshot.borrow());
+    /// }
+    /// # }
+    /// ```
+    pub fn mirror(
+        self,
+    ) -> (
+        watch::Receiver<Value>,
+        impl Future<Output = RequestResult<()>> + Send + 'static,
+    ) {
+        let (sender, receiver) = watch::channel(Value::Null);
+        let mut events = self.start();
+
+        let sync = async move {
+            while let Some(event) = events.next().await {
+                let event = event?;
+                sender.send_modify(|node| apply_event(node, &event));
+                if sender.is_closed() {
+                    break;
+                }
+            }
+            Ok(())
+        };
+
+        (receiver, sync)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::Event;
+    use crate::mirror::apply_event;
+    use serde_json::json;
+
+    fn put(path: &str, data: serde_json::Value) -> Event {
+        Event::Put {
+            path: path.to_string(),
+            data,
+        }
+    }
+
+    #[test]
+    fn merge_semantics() {
+        let mut node = json!(null);
+        apply_event(&mut node, &put("/", json!({ "a": { "b": 1 } })));
+        apply_event(&mut node, &put("/x/y", json!(true)));
+        assert_eq!(node, json!({ "a": { "b": 1 }, "x": { "y": true } }));
+
+        apply_event(
+            &mut node,
+            &Event::Patch {
+                path: "/".to_string(),
+                data: json!({ "a/c": 2, "x": null }),
+            },
+        );
+        assert_eq!(node, json!({ "a": { "b": 1, "c": 2 } }));
+
+        apply_event(&mut node, &put("/a/b", json!(null)));
+        apply_event(&mut node, &put("/a/c", json!(null)));
+        assert_eq!(node, json!(null));
+
+        apply_event(&mut node, &put("/", json!(5)));
+        apply_event(&mut node, &put("/k", json!(null)));
+        assert_eq!(node, json!(5));
+    }
+}
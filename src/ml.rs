@@ -0,0 +1,232 @@
+use crate::errors::{ApiError, ApiResult};
+use crate::google_api::{ApiClient, Operation};
+use crate::Firebase;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use url::Url;
+
+const ML_API: &str = "https://firebaseml.googleapis.com/v1beta2";
+const STORAGE_UPLOAD_API: &str = "https://storage.googleapis.com/upload/storage/v1";
+
+/// Manages custom models hosted by Firebase ML. Requires an OAuth access token with the
+/// `cloud-platform` scope.
+#[derive(Debug, Clone)]
+pub struct MlModels {
+    api: ApiClient,
+    project: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MlModel {
+    /// `projects/PROJECT_ID/models/MODEL_ID`
+    pub name: String,
+    pub display_name: String,
+    pub tags: Vec<String>,
+    pub state: ModelState,
+    pub tflite_model: Option<TfliteModel>,
+    pub etag: String,
+    pub create_time: String,
+    pub update_time: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelState {
+    pub published: bool,
+    pub validation_error: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TfliteModel {
+    pub gcs_tflite_uri: String,
+    pub size_bytes: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelList {
+    #[serde(default)]
+    models: Vec<MlModel>,
+    next_page_token: Option<String>,
+}
+
+impl MlModel {
+    pub fn model_id(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or_default()
+    }
+}
+
+impl Firebase {
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// let models = Firebase::new("https://myfirebase.firebaseio.com").unwrap().ml("my-project");
+    /// ```
+    pub fn ml(&self, project_id: &str) -> MlModels {
+        MlModels {
+            api: ApiClient::new(self),
+            project: format!("projects/{}", project_id),
+        }
+    }
+}
+
+impl MlModels {
+    /// Stores a `.tflite` file in a Cloud Storage bucket and returns its `gs://` URI for
+    /// [`create_model`](MlModels::create_model) or [`update_model`](MlModels::update_model).
+    ///
+    /// ```
+    /// use firebase_rs::{AuthCredential, Firebase};
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// firebase.set_credential(AuthCredential::AccessToken("ya29.token".into()));
+    /// let models = firebase.ml("my-project");
+    ///
+    /// let tflite = std::fs::read("model.tflite").unwrap();
+    /// let uri = models.upload_tflite("my-project.appspot.com", "model.tflite", tflite).await.unwrap();
+    /// let model = models.create_model("flowers", &["vision"], Some(&uri)).await.unwrap();
+    /// models.publish(model.model_id()).await.unwrap();
+    /// # }
+    /// ```
+    pub async fn upload_tflite(
+        &self,
+        bucket: &str,
+        file_name: &str,
+        tflite: Vec<u8>,
+    ) -> ApiResult<String> {
+        let object = format!("Firebase/ML/Models/{}", file_name);
+        let encoded: String = url::form_urlencoded::byte_serialize(object.as_bytes()).collect();
+        let url = format!(
+            "{}/b/{}/o?uploadType=media&name={}",
+            STORAGE_UPLOAD_API, bucket, encoded
+        );
+        self.api
+            .send(http::Method::POST, &url, "application/octet-stream", tflite)
+            .await?;
+
+        Ok(format!("gs://{}/{}", bucket, object))
+    }
+
+    pub async fn create_model(
+        &self,
+        display_name: &str,
+        tags: &[&str],
+        gcs_tflite_uri: Option<&str>,
+    ) -> ApiResult<MlModel> {
+        let mut body = json!({ "displayName": display_name, "tags": tags });
+        if let Some(uri) = gcs_tflite_uri {
+            body["tfliteModel"] = json!({ "gcsTfliteUri": uri });
+        }
+
+        let url = format!("{}/{}/models", ML_API, self.project);
+        let operation: Operation = self.api.json(http::Method::POST, &url, Some(&body)).await?;
+        self.finish(operation).await
+    }
+
+    /// Saves the display name, tags and model file of `model`.
+    pub async fn update_model(&self, model: &MlModel) -> ApiResult<MlModel> {
+        let mut body = json!({ "displayName": model.display_name, "tags": model.tags });
+        let mut mask = String::from("displayName,tags");
+        if let Some(tflite) = &model.tflite_model {
+            body["tfliteModel"] = json!({ "gcsTfliteUri": tflite.gcs_tflite_uri });
+            mask.push_str(",tfliteModel.gcsTfliteUri");
+        }
+
+        self.patch(model.model_id(), &mask, &body).await
+    }
+
+    pub async fn publish(&self, model_id: &str) -> ApiResult<MlModel> {
+        let body = json!({ "state": { "published": true } });
+        self.patch(model_id, "state.published", &body).await
+    }
+
+    pub async fn unpublish(&self, model_id: &str) -> ApiResult<MlModel> {
+        let body = json!({ "state": { "published": false } });
+        self.patch(model_id, "state.published", &body).await
+    }
+
+    pub async fn get_model(&self, model_id: &str) -> ApiResult<MlModel> {
+        let url = format!("{}/{}/models/{}", ML_API, self.project, model_id);
+        self.api.json(http::Method::GET, &url, None).await
+    }
+
+    /// Lists every model, optionally narrowed by a filter such as `tags: vision`.
+    pub async fn list_models(&self, filter: Option<&str>) -> ApiResult<Vec<MlModel>> {
+        let mut models = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut url = match Url::parse(&format!("{}/{}/models", ML_API, self.project)) {
+                Ok(url) => url,
+                Err(_) => return Err(ApiError::NetworkError),
+            };
+            if let Some(filter) = filter {
+                url.query_pairs_mut().append_pair("filter", filter);
+            }
+            if let Some(token) = &page_token {
+                url.query_pairs_mut().append_pair("pageToken", token);
+            }
+
+            let page: ModelList = self.api.json(http::Method::GET, url.as_str(), None).await?;
+            models.extend(page.models);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => return Ok(models),
+            }
+        }
+    }
+
+    pub async fn delete_model(&self, model_id: &str) -> ApiResult<()> {
+        let url = format!("{}/{}/models/{}", ML_API, self.project, model_id);
+        let _: Value = self.api.json(http::Method::DELETE, &url, None).await?;
+        Ok(())
+    }
+
+    async fn patch(&self, model_id: &str, mask: &str, body: &Value) -> ApiResult<MlModel> {
+        let url = format!(
+            "{}/{}/models/{}?updateMask={}",
+            ML_API, self.project, model_id, mask
+        );
+        let operation: Operation = self.api.json(http::Method::PATCH, &url, Some(body)).await?;
+        self.finish(operation).await
+    }
+
+    async fn finish(&self, operation: Operation) -> ApiResult<MlModel> {
+        let response = self.api.wait(ML_API, operation).await?;
+        match serde_json::from_value(response) {
+            Ok(model) => Ok(model),
+            Err(err) => Err(ApiError::InvalidJson(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::google_api::Operation;
+    use crate::ml::MlModel;
+    use serde_json::json;
+
+    #[test]
+    fn model_from_operation() {
+        let operation: Operation = serde_json::from_value(json!({
+            "name": "projects/p/operations/o",
+            "done": true,
+            "response": {
+                "@type": "type.googleapis.com/google.firebase.ml.v1beta2.Model",
+                "name": "projects/p/models/12345",
+                "displayName": "flowers",
+                "tags": ["vision"],
+                "state": { "published": true },
+                "tfliteModel": { "gcsTfliteUri": "gs://b/Firebase/ML/Models/m.tflite", "sizeBytes": "1024" }
+            }
+        }))
+        .unwrap();
+
+        let model: MlModel = serde_json::from_value(operation.result().unwrap().unwrap()).unwrap();
+        assert_eq!(model.model_id(), "12345");
+        assert_eq!(model.display_name, "flowers");
+        assert!(model.state.published);
+        assert_eq!(model.tflite_model.unwrap().size_bytes, "1024");
+    }
+}
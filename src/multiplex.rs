@@ -0,0 +1,450 @@
+use crate::credential::AuthCredential;
+use crate::errors::RequestError;
+use crate::events::{Event, EventStream};
+use crate::mirror::apply_event;
+use futures::future::{self, BoxFuture, Either};
+use futures::{FutureExt, StreamExt};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use tokio::sync::Notify;
+
+/// What a listener does when its consumer falls behind by a full buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BufferStrategy {
+    /// Discards the oldest buffered event to make room.
+    DropOldest,
+    /// Discards the incoming event.
+    DropNewest,
+    /// Stops reading from the connection until there is room, which also holds up other
+    /// listeners sharing it.
+    #[default]
+    Block,
+}
+
+/// Bound on the events buffered for each listener.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Buffering {
+    pub capacity: usize,
+    pub strategy: BufferStrategy,
+}
+
+impl Default for Buffering {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            strategy: BufferStrategy::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Event(Event),
+    Failed(Arc<RequestError>),
+    End,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Events waiting for one subscriber.
+#[derive(Default)]
+struct Queue {
+    items: Mutex<VecDeque<Item>>,
+    /// Wakes the subscriber.
+    filled: Notify,
+    /// Wakes a blocked connection.
+    drained: Notify,
+    /// Set once the subscriber is dropped, after which nothing is queued for it.
+    closed: AtomicBool,
+}
+
+impl Queue {
+    async fn push(&self, item: Item, buffering: Buffering) {
+        loop {
+            // Registered before checking, so a pop in between isn't missed.
+            let drained = self.drained.notified();
+            if self.closed.load(Ordering::SeqCst) {
+                return;
+            }
+            let blocked = {
+                let mut items = lock(&self.items);
+                let full = items.len() >= buffering.capacity.max(1);
+                let blocked = match (full, &item, buffering.strategy) {
+                    (false, _, _) => false,
+                    (true, _, BufferStrategy::Block) => true,
+                    // The end of the stream is never dropped.
+                    (true, Item::End, _) | (true, _, BufferStrategy::DropOldest) => {
+                        items.pop_front();
+                        false
+                    }
+                    (true, _, BufferStrategy::DropNewest) => return,
+                };
+                if !blocked {
+                    items.push_back(item.clone());
+                }
+                blocked
+            };
+
+            if !blocked {
+                self.filled.notify_one();
+                return;
+            }
+            drained.await;
+        }
+    }
+
+    async fn pop(&self) -> Item {
+        loop {
+            let filled = self.filled.notified();
+            if let Some(item) = lock(&self.items).pop_front() {
+                self.drained.notify_one();
+                return item;
+            }
+            filled.await;
+        }
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    /// `None` until the first `Put` or `Patch`.
+    node: Option<Value>,
+    subscribers: Vec<Weak<Queue>>,
+    ended: bool,
+}
+
+/// Reads the upstream connection into every subscriber's queue until it ends.
+type Pump = future::Shared<BoxFuture<'static, ()>>;
+
+/// One upstream connection shared by every listener on the same location and query.
+///
+/// No task is spawned for the connection: whichever subscriber is waiting for events drives it,
+/// so listeners work on any executor. It is dropped, and the connection closed, with the last
+/// subscriber.
+struct Hub {
+    shared: Arc<Mutex<Shared>>,
+    pump: Pump,
+}
+
+impl Hub {
+    fn new(mut upstream: EventStream, buffering: Buffering) -> Self {
+        let shared = Arc::<Mutex<Shared>>::default();
+        let state = shared.clone();
+        let pump = async move {
+            loop {
+                let item = match upstream.next().await {
+                    Some(Ok(event)) => Item::Event(event),
+                    Some(Err(err)) => Item::Failed(Arc::new(err)),
+                    None => Item::End,
+                };
+
+                let subscribers: Vec<_> = {
+                    let mut shared = lock(&state);
+                    match &item {
+                        Item::Event(event @ (Event::Put { .. } | Event::Patch { .. })) => {
+                            apply_event(shared.node.get_or_insert(Value::Null), event);
+                        }
+                        Item::End => shared.ended = true,
+                        _ => {}
+                    }
+                    shared.subscribers.retain(|queue| queue.strong_count() > 0);
+                    shared
+                        .subscribers
+                        .iter()
+                        .filter_map(Weak::upgrade)
+                        .collect()
+                };
+                for queue in subscribers {
+                    queue.push(item.clone(), buffering).await;
+                }
+                if let Item::End = item {
+                    return;
+                }
+            }
+        };
+
+        Self {
+            shared,
+            pump: pump.boxed().shared(),
+        }
+    }
+
+    /// Registers a subscriber, starting with the state so far if it joins late.
+    fn subscribe(&self) -> Arc<Queue> {
+        let queue = Arc::new(Queue::default());
+        let mut shared = lock(&self.shared);
+        if let Some(data) = shared.node.clone() {
+            let put = Event::Put {
+                path: "/".to_string(),
+                data,
+            };
+            lock(&queue.items).push_back(Item::Event(put));
+        }
+        shared.subscribers.push(Arc::downgrade(&queue));
+        queue
+    }
+}
+
+struct Subscriber {
+    hub: Arc<Hub>,
+    queue: Arc<Queue>,
+}
+
+impl Subscriber {
+    async fn next(&mut self) -> Option<Result<Event, RequestError>> {
+        let pop = self.queue.pop();
+        futures::pin_mut!(pop);
+        let item = match future::select(pop, self.hub.pump.clone()).await {
+            Either::Left((item, _)) => item,
+            // The connection has ended, after queueing its last items.
+            Either::Right(((), pop)) => pop.await,
+        };
+        match item {
+            Item::Event(event) => Some(Ok(event)),
+            // The last subscriber to see the failure gets the original.
+            Item::Failed(err) => Some(Err(
+                Arc::try_unwrap(err).unwrap_or_else(|err| err.duplicate())
+            )),
+            Item::End => None,
+        }
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        // The connection may be blocked on this queue, waiting for a pop that won't come.
+        self.queue.closed.store(true, Ordering::SeqCst);
+        self.queue.drained.notify_one();
+    }
+}
+
+/// Identifies a shared connection by the settings it was opened with and its credential. The
+/// credential is compared as is rather than written into `settings`, which stays free of
+/// secrets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct HubKey {
+    settings: String,
+    credential: Option<AuthCredential>,
+}
+
+impl HubKey {
+    pub fn new(settings: String, credential: Option<AuthCredential>) -> Self {
+        Self {
+            settings,
+            credential,
+        }
+    }
+}
+
+/// Open connections of a [`Firebase`](crate::Firebase) instance and its clones.
+#[derive(Clone, Default)]
+pub(crate) struct Hubs {
+    hubs: Arc<Mutex<HashMap<HubKey, Weak<Hub>>>>,
+}
+
+impl Debug for Hubs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Hubs({})", lock(&self.hubs).len())
+    }
+}
+
+impl Hubs {
+    /// Joins the connection registered under `key`, opening it with `connect` if there is none.
+    pub fn subscribe<F>(&self, key: HubKey, buffering: Buffering, connect: F) -> EventStream
+    where
+        F: FnOnce() -> EventStream,
+    {
+        let hub = {
+            let mut hubs = lock(&self.hubs);
+            hubs.retain(|_, hub| hub.strong_count() > 0);
+
+            let live = hubs
+                .get(&key)
+                .and_then(Weak::upgrade)
+                .filter(|hub| !lock(&hub.shared).ended);
+            match live {
+                Some(hub) => hub,
+                None => {
+                    let hub = Arc::new(Hub::new(connect(), buffering));
+                    hubs.insert(key, Arc::downgrade(&hub));
+                    hub
+                }
+            }
+        };
+
+        let queue = hub.subscribe();
+        let subscriber = Subscriber { hub, queue };
+        Box::pin(futures::stream::unfold(
+            subscriber,
+            |mut subscriber| async move {
+                let event = subscriber.next().await?;
+                Some((event, subscriber))
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::RequestError;
+    use crate::events::Event;
+    use crate::multiplex::{BufferStrategy, Buffering, HubKey, Hubs, Item, Queue};
+    use futures::StreamExt;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn users() -> HubKey {
+        HubKey::new("users".to_string(), None)
+    }
+
+    fn put(value: i32) -> Event {
+        Event::Put {
+            path: "/".to_string(),
+            data: json!(value),
+        }
+    }
+
+    #[tokio::test]
+    async fn shared_connection() {
+        let hubs = Hubs::default();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let (sender, events) = futures::channel::mpsc::unbounded();
+
+        let connect = || {
+            connections.fetch_add(1, Ordering::SeqCst);
+            events.map(Ok).boxed()
+        };
+        let buffering = Buffering::default();
+        let mut first = hubs.subscribe(users(), buffering, connect);
+        let mut second = hubs.subscribe(users(), buffering, || unreachable!());
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        sender.unbounded_send(put(1)).unwrap();
+        assert_eq!(first.next().await.unwrap().unwrap(), put(1));
+        assert_eq!(second.next().await.unwrap().unwrap(), put(1));
+
+        let mut late = hubs.subscribe(users(), buffering, || unreachable!());
+        assert_eq!(late.next().await.unwrap().unwrap(), put(1));
+
+        drop(sender);
+        assert!(first.next().await.is_none());
+        assert!(late.next().await.is_none());
+    }
+
+    #[test]
+    fn shared_without_a_runtime() {
+        let hubs = Hubs::default();
+        let (sender, events) = futures::channel::mpsc::unbounded();
+        let buffering = Buffering::default();
+        let mut first = hubs.subscribe(users(), buffering, || events.map(Ok).boxed());
+        let mut second = hubs.subscribe(users(), buffering, || unreachable!());
+
+        sender.unbounded_send(put(1)).unwrap();
+        drop(sender);
+        futures::executor::block_on(async {
+            assert_eq!(first.next().await.unwrap().unwrap(), put(1));
+            assert!(first.next().await.is_none());
+            assert_eq!(second.next().await.unwrap().unwrap(), put(1));
+            assert!(second.next().await.is_none());
+        });
+    }
+
+    #[tokio::test]
+    async fn dropped_subscribers_release_the_connection() {
+        let hubs = Hubs::default();
+        let (sender, events) = futures::channel::mpsc::unbounded();
+        let buffering = Buffering {
+            capacity: 1,
+            strategy: BufferStrategy::Block,
+        };
+        let first = hubs.subscribe(users(), buffering, || events.map(Ok).boxed());
+        let mut second = hubs.subscribe(users(), buffering, || unreachable!());
+
+        sender.unbounded_send(put(1)).unwrap();
+        sender.unbounded_send(put(2)).unwrap();
+        // `first` never reads, so the connection blocks on its full queue with `put(2)`.
+        assert_eq!(second.next().await.unwrap().unwrap(), put(1));
+
+        drop(first);
+        let next = tokio::time::timeout(Duration::from_secs(5), second.next()).await;
+        assert_eq!(next.unwrap().unwrap().unwrap(), put(2));
+    }
+
+    #[tokio::test]
+    async fn shared_failures() {
+        let hubs = Hubs::default();
+        let (sender, events) = futures::channel::mpsc::unbounded();
+        let buffering = Buffering::default();
+        let mut first = hubs.subscribe(users(), buffering, || events.boxed());
+        let mut second = hubs.subscribe(users(), buffering, || unreachable!());
+
+        let denied = RequestError::PermissionDenied("Permission denied".to_string());
+        sender.unbounded_send(Err(denied)).unwrap();
+        for listener in [&mut first, &mut second] {
+            assert!(matches!(
+                listener.next().await,
+                Some(Err(RequestError::PermissionDenied(message))) if message == "Permission denied"
+            ));
+        }
+    }
+
+    async fn drain(queue: &Queue) -> Vec<i32> {
+        let mut values = Vec::new();
+        while let Item::Event(Event::Put { data, .. }) = queue.pop().await {
+            values.push(data.as_i64().unwrap() as i32);
+        }
+        values
+    }
+
+    #[tokio::test]
+    async fn overflow() {
+        for (strategy, expected) in [
+            (BufferStrategy::DropOldest, vec![3, 4]),
+            (BufferStrategy::DropNewest, vec![1, 2]),
+        ] {
+            let buffering = Buffering {
+                capacity: 2,
+                strategy,
+            };
+            let queue = Queue::default();
+            for value in 1..=4 {
+                queue.push(Item::Event(put(value)), buffering).await;
+            }
+            let buffered: Vec<_> = queue.items.lock().unwrap().drain(..).collect();
+            assert_eq!(
+                format!("{:?}", buffered),
+                format!(
+                    "{:?}",
+                    expected
+                        .into_iter()
+                        .map(|value| Item::Event(put(value)))
+                        .collect::<Vec<_>>()
+                )
+            );
+        }
+
+        let buffering = Buffering {
+            capacity: 1,
+            strategy: BufferStrategy::Block,
+        };
+        let queue = Queue::default();
+        let producer = async {
+            for value in 1..=3 {
+                queue.push(Item::Event(put(value)), buffering).await;
+            }
+            queue.push(Item::End, buffering).await;
+        };
+        let (_, values) = futures::join!(producer, drain(&queue));
+        assert_eq!(values, vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::endpoint::Endpoint;
+use crate::errors::RequestResult;
+use crate::json::from_json;
+use crate::requestable::Requestable;
+use serde::de::{DeserializeOwned, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+
+/// Children of a node in the order they appear in the JSON body.
+///
+/// Nodes whose keys are all small integers come back as arrays, with `null` for missing
+/// indices; those holes are skipped. `null` itself is an empty list.
+pub(crate) struct Children<T>(pub Vec<(String, T)>);
+
+impl<'de, T> Deserialize<'de> for Children<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ChildrenVisitor(PhantomData))
+    }
+}
+
+struct ChildrenVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for ChildrenVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Children<T>;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "an object, an array or null")
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(Children(Vec::new()))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut children = Vec::new();
+        while let Some(child) = map.next_entry()? {
+            children.push(child);
+        }
+        Ok(Children(children))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut children = Vec::new();
+        let mut index = 0;
+        while let Some(child) = seq.next_element::<Option<T>>()? {
+            if let Some(child) = child {
+                children.push((index.to_string(), child));
+            }
+            index += 1;
+        }
+        Ok(Children(children))
+    }
+}
+
+pub(crate) fn parse_children<T: DeserializeOwned>(body: &str) -> RequestResult<Vec<(String, T)>> {
+    Ok(from_json::<Children<T>>(body.as_bytes())?.0)
+}
+
+impl Endpoint {
+    /// Fetches the children of this location as `(key, value)` pairs in the order the server
+    /// wrote them, which a `HashMap` would lose. A missing node is an empty list.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, OrderBy, Paramable};
+    ///
+    /// # async fn run() {
+    /// let leaderboard = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("scores").order_by(OrderBy::Value).limit_to_last(10);
+    /// let top = leaderboard.get_ordered::<u32>().await;
+    /// # }
+    /// ```
+    pub async fn get_ordered<T>(&self) -> RequestResult<Vec<(String, T)>>
+    where
+        T: DeserializeOwned,
+    {
+        parse_children(&self.get_raw().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::errors::{RequestError, RequestResult};
+    use crate::hooks::HttpRequest;
+    use crate::ordered::parse_children;
+    use crate::Firebase;
+    use async_trait::async_trait;
+
+    #[derive(Debug)]
+    struct Fixed(&'static str);
+
+    #[async_trait]
+    impl HttpClient for Fixed {
+        async fn execute(&self, _request: HttpRequest) -> RequestResult<HttpResponse> {
+            Ok(http::Response::new(self.0.as_bytes().to_vec()))
+        }
+    }
+
+    #[tokio::test]
+    async fn server_order() {
+        let scores = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(Fixed(r#"{"zoe":12,"adam":40,"mia":7}"#))
+            .at("scores");
+        assert_eq!(
+            scores.get_ordered::<u32>().await.unwrap(),
+            vec![
+                ("zoe".to_string(), 12),
+                ("adam".to_string(), 40),
+                ("mia".to_string(), 7)
+            ]
+        );
+
+        assert_eq!(
+            parse_children::<u32>("[null,5,null,3]").unwrap(),
+            vec![("1".to_string(), 5), ("3".to_string(), 3)]
+        );
+        assert_eq!(parse_children::<u32>("null").unwrap(), vec![]);
+        assert!(matches!(
+            parse_children::<u32>("42"),
+            Err(RequestError::Decode { .. })
+        ));
+    }
+}
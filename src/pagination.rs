@@ -0,0 +1,223 @@
+use crate::children::compare_keys;
+use crate::constants::SHALLOW;
+use crate::endpoint::Endpoint;
+use crate::errors::RequestResult;
+use crate::ordered::parse_children;
+use crate::params::{OrderBy, Paramable};
+use crate::query::QUERY_KEYS;
+use crate::requestable::Requestable;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use serde::de::{DeserializeOwned, IgnoredAny};
+
+impl Endpoint {
+    /// Walks the children of this location in key order, `page_size` at a time, using the last
+    /// key of each page as the `startAfter` cursor of the next. Query parameters and `shallow`
+    /// already set on the endpoint are dropped; the stream ends after the first short page or error.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    /// use futures::StreamExt;
+    /// use serde_json::Value;
+    ///
+    /// # async fn run() {
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let mut pages = users.paginate::<Value>(1000);
+    /// while let Some(Ok(page)) = pages.next().await {
+    ///     for (key, user) in page {
+    ///         println!("{}: {}", key, user);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn paginate<T>(&self, page_size: u32) -> BoxStream<'static, RequestResult<Vec<(String, T)>>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let base = QUERY_KEYS
+            .into_iter()
+            .fold(self.clone(), |endpoint, key| endpoint.remove_param(key))
+            .remove_param(SHALLOW)
+            .order_by(OrderBy::Key)
+            .limit_to_first(page_size);
+
+        stream::unfold(Some(None), move |cursor: Option<Option<String>>| {
+            let base = base.clone();
+            async move {
+                let endpoint = match cursor? {
+                    Some(key) => base.start_after(key),
+                    None => base,
+                };
+                let page = match fetch_page::<T>(&endpoint).await {
+                    Ok(page) => page,
+                    Err(err) => return Some((Err(err), None)),
+                };
+                if page.is_empty() {
+                    return None;
+                }
+
+                let next = if page.len() < page_size as usize {
+                    None
+                } else {
+                    page.last().map(|(key, _)| Some(key.clone()))
+                };
+                Some((Ok(page), next))
+            }
+        })
+        .boxed()
+    }
+}
+
+impl Endpoint {
+    /// Lists the keys of this location lazily, `page_size` at a time, for nodes with too many
+    /// children for [`keys`](Endpoint::keys). The server refuses `shallow=true` together with
+    /// `orderBy`, so each page is a regular [`paginate`](Endpoint::paginate) read whose values
+    /// are skipped while parsing rather than kept.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run() {
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let mut batches = users.paginate_keys(5000);
+    /// while let Some(Ok(keys)) = batches.next().await {
+    ///     println!("{} more users", keys.len());
+    /// }
+    /// # }
+    /// ```
+    pub fn paginate_keys(&self, page_size: u32) -> BoxStream<'static, RequestResult<Vec<String>>> {
+        self.paginate::<IgnoredAny>(page_size)
+            .map(|page| page.map(|page| page.into_iter().map(|(key, _)| key).collect()))
+            .boxed()
+    }
+}
+
+async fn fetch_page<T: DeserializeOwned>(endpoint: &Endpoint) -> RequestResult<Vec<(String, T)>> {
+    let mut page = parse_children::<T>(&endpoint.get_raw().await?)?;
+    page.sort_by(|a, b| compare_keys(&a.0, &b.0));
+    Ok(page)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::errors::RequestResult;
+    use crate::hooks::HttpRequest;
+    use crate::params::Paramable;
+    use crate::Firebase;
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use serde_json::{Map, Value};
+    use std::sync::Mutex;
+
+    /// Serves `count` numbered children, honouring `startAfter` and `limitToFirst` on `$key`.
+    #[derive(Debug)]
+    struct Numbered {
+        count: u32,
+        queries: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl HttpClient for Numbered {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            let url = url::Url::parse(&request.uri().to_string()).unwrap();
+            let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+            let param = |key: &str| params.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+            assert_eq!(param("orderBy").unwrap(), "\"$key\"");
+            self.queries
+                .lock()
+                .unwrap()
+                .push(url.query().unwrap().to_string());
+
+            let after: Option<u32> =
+                param("startAfter").map(|key| key.trim_matches('"').parse().unwrap());
+            let limit: usize = param("limitToFirst").unwrap().parse().unwrap();
+            let children: Map<String, Value> = (0..self.count)
+                .filter(|n| after.is_none_or(|after| *n > after))
+                .take(limit)
+                .map(|n| (n.to_string(), Value::from(n * 10)))
+                .collect();
+            let body = if children.is_empty() {
+                "null".to_string()
+            } else {
+                Value::Object(children).to_string()
+            };
+            Ok(http::Response::new(body.into_bytes()))
+        }
+    }
+
+    #[tokio::test]
+    async fn key_pages() {
+        let client = std::sync::Arc::new(Numbered {
+            count: 25,
+            queries: Mutex::default(),
+        });
+        let scores = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone())
+            .at("scores")
+            .order_by("points")
+            .limit_to_last(3);
+
+        let pages: Vec<Vec<(String, u32)>> = scores
+            .paginate::<u32>(10)
+            .map(|page| page.unwrap())
+            .collect()
+            .await;
+        assert_eq!(
+            pages.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![10, 10, 5]
+        );
+        // Numeric keys come back in numeric rather than lexicographic order.
+        assert_eq!(pages[0][2], ("2".to_string(), 20));
+        assert_eq!(pages[1][0], ("10".to_string(), 100));
+        assert_eq!(pages[2][4], ("24".to_string(), 240));
+        assert_eq!(
+            client.queries.lock().unwrap()[1],
+            "limitToFirst=10&orderBy=%22%24key%22&startAfter=%229%22"
+        );
+
+        // A full last page costs one more request, which comes back empty.
+        let exact = std::sync::Arc::new(Numbered {
+            count: 20,
+            queries: Mutex::default(),
+        });
+        let pages = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(exact.clone())
+            .at("scores")
+            .paginate::<u32>(10)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(pages.len(), 2);
+        assert_eq!(exact.queries.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn key_batches() {
+        let client = std::sync::Arc::new(Numbered {
+            count: 7,
+            queries: Mutex::default(),
+        });
+        let batches: Vec<Vec<String>> = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone())
+            .at("scores")
+            .shallow(true)
+            .paginate_keys(3)
+            .map(|batch| batch.unwrap())
+            .collect()
+            .await;
+        assert_eq!(
+            batches,
+            vec![vec!["0", "1", "2"], vec!["3", "4", "5"], vec!["6"]]
+        );
+        assert!(client
+            .queries
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|query| !query.contains("shallow")));
+    }
+}
@@ -1,82 +1,603 @@
 use crate::constants::{
-    END_AT, EQUAL_TO, EXPORT, FORMAT, LIMIT_TO_FIRST, LIMIT_TO_LAST, ORDER_BY, SHALLOW, START_AT,
+    DOWNLOAD, END_AT, END_BEFORE, EQUAL_TO, EXPORT, FORMAT, LIMIT_TO_FIRST, LIMIT_TO_LAST,
+    ORDER_BY, PRETTY, PRINT, SHALLOW, SILENT, START_AFTER, START_AT, TIMEOUT, WRITE_SIZE_LIMIT,
 };
-use crate::Firebase;
-use itertools::Itertools;
-use std::collections::HashMap;
+use crate::errors::{QueryError, QueryResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
 use url::Url;
 
-#[derive(Debug)]
+/// Largest limit a query can carry. Firebase documents no maximum, so this is the largest one
+/// the official SDKs can send, which take limits as signed 32-bit integers.
+const MAX_LIMIT: u32 = i32::MAX as u32;
+
+/// Longest `timeout` the server accepts.
+const MAX_SERVER_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Serializes as a map of parameter names to their encoded values, e.g.
+/// `{"orderBy": "\"age\"", "limitToFirst": "10"}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Params {
-    pub uri: Url,
-    pub params: HashMap<String, String>,
+    params: BTreeMap<String, String>,
 }
 
 impl Params {
-    pub fn new(uri: Url) -> Self {
-        Self {
-            uri,
-            params: Default::default(),
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert<T>(&mut self, key: &str, value: T)
+    where
+        T: ToString,
+    {
+        self.params.insert(key.to_string(), value.to_string());
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.params.remove(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.params.get(key).map(String::as_str)
+    }
+
+    /// Adds every parameter of `other`, replacing values for keys present in both.
+    pub fn extend(&mut self, other: &Params) {
+        self.params.extend(other.params.clone());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Catches queries the server would reject, without a round trip.
+    ///
+    /// ```
+    /// use firebase_rs::{Paramable, Params, QueryError};
+    ///
+    /// let params = Params::new().order_by("$key").limit_to_first(0);
+    /// assert_eq!(params.validate(), Err(QueryError::ZeroLimit("limitToFirst")));
+    /// ```
+    pub fn validate(&self) -> QueryResult<()> {
+        for key in [LIMIT_TO_FIRST, LIMIT_TO_LAST] {
+            if self.get(key) == Some("0") {
+                return Err(QueryError::ZeroLimit(key));
+            }
+            if let Some(Ok(limit)) = self.get(key).map(str::parse::<u64>) {
+                if limit > u64::from(MAX_LIMIT) {
+                    return Err(QueryError::LimitTooLarge {
+                        key,
+                        max: MAX_LIMIT,
+                    });
+                }
+            }
+        }
+        if self.get(LIMIT_TO_FIRST).is_some() && self.get(LIMIT_TO_LAST).is_some() {
+            return Err(QueryError::ConflictingLimits);
+        }
+
+        let filters = [
+            LIMIT_TO_FIRST,
+            LIMIT_TO_LAST,
+            START_AT,
+            START_AFTER,
+            END_AT,
+            END_BEFORE,
+            EQUAL_TO,
+        ];
+        if self.get(ORDER_BY).is_none() && filters.iter().any(|key| self.get(key).is_some()) {
+            return Err(QueryError::MissingOrderBy);
+        }
+
+        if let Some(timeout) = self.get(TIMEOUT) {
+            match parse_server_duration(timeout) {
+                Some(duration) if !duration.is_zero() && duration <= MAX_SERVER_TIMEOUT => {}
+                _ => return Err(QueryError::InvalidTimeout(timeout.to_string())),
+            }
         }
+
+        if let (Some(start), Some(end)) = (self.get(START_AT), self.get(END_AT)) {
+            if let (Ok(low), Ok(high)) = (start.parse::<f64>(), end.parse::<f64>()) {
+                if low > high {
+                    return Err(QueryError::EmptyRange {
+                        start: start.to_string(),
+                        end: end.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
     }
 
-    pub fn set_params(&mut self) -> () {
-        for (k, v) in self.params.iter().sorted() {
-            self.uri.query_pairs_mut().append_pair(k, v);
+    pub(crate) fn set_params(&self, uri: &mut Url) {
+        for (k, v) in self.params.iter() {
+            append_query_pair(uri, k, v);
         }
     }
+}
+
+/// Percent-encodes a query component. Spaces become `%20` rather than the `+` of form
+/// encoding, which the server could take for a literal plus inside a JSON string.
+fn encode_query_component(value: &str) -> String {
+    // `byte_serialize` escapes literal pluses, so any `+` left stands for a space.
+    url::form_urlencoded::byte_serialize(value.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20")
+}
 
-    pub fn add_param<T>(&mut self, key: &str, value: T) -> &mut Self
+/// Appends `key=value` to the query of `uri`. Every query parameter sent to the database,
+/// credentials included, goes through here.
+pub(crate) fn append_query_pair(uri: &mut Url, key: &str, value: &str) {
+    let pair = format!(
+        "{}={}",
+        encode_query_component(key),
+        encode_query_component(value)
+    );
+    let query = match uri.query() {
+        Some(query) if !query.is_empty() => format!("{}&{}", query, pair),
+        _ => pair,
+    };
+    uri.set_query(Some(&query));
+}
+
+impl Paramable for Params {
+    fn add_param<T>(mut self, key: &str, value: T) -> Self
     where
         T: ToString,
     {
-        self.params.insert(key.to_string(), value.to_string());
+        self.insert(key, value);
+        self
+    }
 
+    fn remove_param(mut self, key: &str) -> Self {
+        self.remove(key);
         self
     }
+}
+
+/// A value for `startAt`, `endAt` or `equalTo`, rendered as the JSON literal the server
+/// expects: strings are quoted and escaped, numbers and booleans are bare. JSON has no NaN or
+/// infinity, so such floats render as `null`.
+///
+/// ```
+/// use firebase_rs::QueryValue;
+///
+/// assert_eq!(QueryValue::from("Ada").to_string(), "\"Ada\"");
+/// assert_eq!(QueryValue::from(-2.5).to_string(), "-2.5");
+/// assert_eq!(QueryValue::Null.to_string(), "null");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryValue {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    Null,
+}
+
+impl Display for QueryValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryValue::Int(value) => write!(f, "{}", value),
+            QueryValue::Float(value) => write!(f, "{}", plain_float(*value)),
+            QueryValue::Str(value) => write!(f, "{}", Value::from(value.as_str())),
+            QueryValue::Bool(value) => write!(f, "{}", value),
+            QueryValue::Null => write!(f, "null"),
+        }
+    }
+}
+
+/// Formats `value` in plain decimal notation, e.g. `1000000000000000000000.0` rather than
+/// `1e21`, keeping a `.0` so it still reads as a float.
+fn plain_float(value: f64) -> String {
+    if !value.is_finite() {
+        return "null".to_string();
+    }
+    let plain = value.to_string();
+    if plain.contains('.') {
+        plain
+    } else {
+        format!("{}.0", plain)
+    }
+}
+
+macro_rules! query_value_from_int {
+    ($($int:ty),*) => {
+        $(impl From<$int> for QueryValue {
+            fn from(value: $int) -> Self {
+                QueryValue::Int(value as i64)
+            }
+        })*
+    };
+}
+
+query_value_from_int!(i8, i16, i32, i64, isize, u8, u16, u32);
+
+macro_rules! query_value_from_wide_int {
+    ($($int:ty),*) => {
+        $(impl From<$int> for QueryValue {
+            /// Values beyond `i64` fall back to the nearest float.
+            fn from(value: $int) -> Self {
+                match i64::try_from(value) {
+                    Ok(value) => QueryValue::Int(value),
+                    Err(_) => QueryValue::Float(value as f64),
+                }
+            }
+        })*
+    };
+}
+
+query_value_from_wide_int!(u64, usize);
+
+impl From<f32> for QueryValue {
+    /// Goes through the shortest decimal form of `value` so `0.1_f32` stays `0.1` rather than
+    /// becoming `0.10000000149011612`.
+    fn from(value: f32) -> Self {
+        match value.to_string().parse() {
+            Ok(value) => QueryValue::Float(value),
+            Err(_) => QueryValue::Float(value as f64),
+        }
+    }
+}
+
+impl From<f64> for QueryValue {
+    fn from(value: f64) -> Self {
+        QueryValue::Float(value)
+    }
+}
+
+impl From<bool> for QueryValue {
+    fn from(value: bool) -> Self {
+        QueryValue::Bool(value)
+    }
+}
+
+impl From<&str> for QueryValue {
+    fn from(value: &str) -> Self {
+        QueryValue::Str(value.to_string())
+    }
+}
+
+impl From<String> for QueryValue {
+    fn from(value: String) -> Self {
+        QueryValue::Str(value)
+    }
+}
+
+impl From<()> for QueryValue {
+    fn from(_: ()) -> Self {
+        QueryValue::Null
+    }
+}
+
+impl<T> From<Option<T>> for QueryValue
+where
+    T: Into<QueryValue>,
+{
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => QueryValue::Null,
+        }
+    }
+}
+
+/// What a query sorts by, rendered with the quotes `orderBy` requires.
+///
+/// ```
+/// use firebase_rs::OrderBy;
+///
+/// assert_eq!(OrderBy::Key.to_string(), "\"$key\"");
+/// assert_eq!(OrderBy::child("scores/total").to_string(), "\"scores/total\"");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderBy {
+    Key,
+    Value,
+    Priority,
+    /// A child path, e.g. `age` or `scores/total`.
+    Child(String),
+}
+
+impl OrderBy {
+    pub fn child(path: &str) -> Self {
+        OrderBy::Child(path.to_string())
+    }
+}
 
-    pub fn order_by(&mut self, key: &str) -> &mut Params {
-        self.add_param(ORDER_BY, key)
+impl Display for OrderBy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderBy::Key => write!(f, "\"$key\""),
+            OrderBy::Value => write!(f, "\"$value\""),
+            OrderBy::Priority => write!(f, "\"$priority\""),
+            OrderBy::Child(path) => write!(f, "{}", Value::from(path.as_str())),
+        }
     }
+}
+
+/// Reads `$key`, `$value` and `$priority` as such and anything else as a child path. Quotes
+/// around the string, as older code had to add by hand, are dropped.
+impl From<&str> for OrderBy {
+    fn from(value: &str) -> Self {
+        let unquoted = match serde_json::from_str::<String>(value) {
+            Ok(unquoted) => unquoted,
+            Err(_) => value.to_string(),
+        };
+        match unquoted.as_str() {
+            "$key" => OrderBy::Key,
+            "$value" => OrderBy::Value,
+            "$priority" => OrderBy::Priority,
+            _ => OrderBy::Child(unquoted),
+        }
+    }
+}
+
+impl From<String> for OrderBy {
+    fn from(value: String) -> Self {
+        OrderBy::from(value.as_str())
+    }
+}
+
+/// Renders a range bound with the key that breaks ties between equal values.
+fn with_key(value: QueryValue, key: &str) -> String {
+    format!("{},{}", value, QueryValue::from(key))
+}
+
+/// Largest write the server accepts before aborting it (`writeSizeLimit`). Bigger writes take
+/// longer to apply and block other operations meanwhile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteSizeLimit {
+    /// Up to 10 KB, applied within about a second.
+    Tiny,
+    /// Up to 1 MB, applied within about 10 seconds.
+    Small,
+    /// Up to 10 MB, applied within about 30 seconds.
+    Medium,
+    /// Up to 100 MB, applied within about 2 minutes.
+    Large,
+    Unlimited,
+}
 
-    pub fn limit_to_first(&mut self, count: u32) -> &mut Params {
+impl Display for WriteSizeLimit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let limit = match self {
+            WriteSizeLimit::Tiny => "tiny",
+            WriteSizeLimit::Small => "small",
+            WriteSizeLimit::Medium => "medium",
+            WriteSizeLimit::Large => "large",
+            WriteSizeLimit::Unlimited => "unlimited",
+        };
+        write!(f, "{}", limit)
+    }
+}
+
+/// Renders `duration` in the largest unit of the `timeout` parameter that represents it
+/// exactly, e.g. `15s` or `1500ms`.
+// `u128::is_multiple_of` needs Rust 1.87; `%` keeps older toolchains building.
+#[allow(unknown_lints, clippy::manual_is_multiple_of)]
+fn server_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis > 0 && millis % 60_000 == 0 {
+        format!("{}min", millis / 60_000)
+    } else if millis % 1_000 == 0 {
+        format!("{}s", millis / 1_000)
+    } else {
+        format!("{}ms", millis)
+    }
+}
+
+/// The inverse of [`server_duration`].
+fn parse_server_duration(value: &str) -> Option<Duration> {
+    let split = value.find(|c: char| !c.is_ascii_digit())?;
+    let amount = value[..split].parse::<u64>().ok()?;
+    match &value[split..] {
+        "ms" => Some(Duration::from_millis(amount)),
+        "s" => Some(Duration::from_secs(amount)),
+        "min" => Some(Duration::from_secs(amount.checked_mul(60)?)),
+        _ => None,
+    }
+}
+
+/// How the server shapes the response body. Only one mode applies to a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Includes priorities and other metadata (`format=export`).
+    Export,
+    /// Human-readable indented JSON (`print=pretty`).
+    Pretty,
+    /// Suppresses the response body of writes (`print=silent`).
+    Silent,
+}
+
+impl OutputMode {
+    fn param(self) -> (&'static str, &'static str) {
+        match self {
+            OutputMode::Export => (FORMAT, EXPORT),
+            OutputMode::Pretty => (PRINT, PRETTY),
+            OutputMode::Silent => (PRINT, SILENT),
+        }
+    }
+}
+
+/// Query builder methods. They take the receiver by value so chains don't copy at every step;
+/// clone an endpoint to branch off it.
+pub trait Paramable: Sized {
+    fn add_param<T>(self, key: &str, value: T) -> Self
+    where
+        T: ToString;
+
+    fn remove_param(self, key: &str) -> Self;
+
+    /// ```
+    /// use firebase_rs::{Firebase, OrderBy, Paramable};
+    ///
+    /// let by_age = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").order_by(OrderBy::child("age"));
+    /// let by_key = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").order_by("$key");
+    /// ```
+    fn order_by(self, order: impl Into<OrderBy>) -> Self {
+        self.add_param(ORDER_BY, order.into())
+    }
+
+    fn limit_to_first(self, count: u32) -> Self {
         self.add_param(LIMIT_TO_FIRST, count)
     }
 
-    pub fn limit_to_last(&mut self, count: u32) -> &mut Params {
+    fn limit_to_last(self, count: u32) -> Self {
         self.add_param(LIMIT_TO_LAST, count)
     }
 
-    pub fn start_at(&mut self, index: u32) -> &mut Params {
-        self.add_param(START_AT, index)
+    /// ```
+    /// use firebase_rs::{Firebase, Paramable};
+    ///
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").order_by("\"name\"").start_at("A").end_at("B");
+    /// ```
+    fn start_at(self, value: impl Into<QueryValue>) -> Self {
+        self.add_param(START_AT, value.into())
+    }
+
+    fn end_at(self, value: impl Into<QueryValue>) -> Self {
+        self.add_param(END_AT, value.into())
+    }
+
+    /// Like [`start_at`](Paramable::start_at) but excludes children equal to `value`.
+    fn start_after(self, value: impl Into<QueryValue>) -> Self {
+        self.add_param(START_AFTER, value.into())
+    }
+
+    /// Like [`end_at`](Paramable::end_at) but excludes children equal to `value`.
+    fn end_before(self, value: impl Into<QueryValue>) -> Self {
+        self.add_param(END_BEFORE, value.into())
     }
 
-    pub fn end_at(&mut self, index: u32) -> &mut Params {
-        self.add_param(END_AT, index)
+    /// Starts at `value`, and among children with exactly that value at the one with key `key`
+    /// (`startAt=value,"key"`), so pages over non-unique values don't repeat or skip children.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, OrderBy, Paramable};
+    ///
+    /// let next_page = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("scores").order_by(OrderBy::child("points")).start_at_with_key(120, "user_17").limit_to_first(20);
+    /// ```
+    fn start_at_with_key(self, value: impl Into<QueryValue>, key: &str) -> Self {
+        self.add_param(START_AT, with_key(value.into(), key))
     }
 
-    pub fn equal_to(&mut self, value: u32) -> &mut Params {
-        self.add_param(EQUAL_TO, value)
+    fn end_at_with_key(self, value: impl Into<QueryValue>, key: &str) -> Self {
+        self.add_param(END_AT, with_key(value.into(), key))
     }
 
-    pub fn shallow(&mut self, flag: bool) -> &mut Params {
+    /// Matches children equal to `value`, which may also be a boolean, or `None` for children
+    /// where the ordered value is missing.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, OrderBy, Paramable};
+    ///
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let banned = users.clone().order_by(OrderBy::child("banned")).equal_to(true);
+    /// let unverified = users.order_by(OrderBy::child("verified_at")).equal_to(None::<i64>);
+    /// ```
+    fn equal_to(self, value: impl Into<QueryValue>) -> Self {
+        self.add_param(EQUAL_TO, value.into())
+    }
+
+    fn shallow(self, flag: bool) -> Self {
         self.add_param(SHALLOW, flag)
     }
 
-    pub fn format(&mut self) -> &mut Params {
-        self.add_param(FORMAT, EXPORT)
+    /// Makes the server give up on a read after `duration` (at least 1 millisecond, at most 15
+    /// minutes) instead of letting it run to completion. Unlike a client timeout this also stops
+    /// the work on the server. Durations out of range fail validation before the request is
+    /// sent.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, Paramable};
+    /// use std::time::Duration;
+    ///
+    /// let logs = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("logs").server_timeout(Duration::from_secs(15));
+    /// ```
+    fn server_timeout(self, duration: Duration) -> Self {
+        self.add_param(TIMEOUT, server_duration(duration))
+    }
+
+    /// ```
+    /// use firebase_rs::{Firebase, Paramable, WriteSizeLimit};
+    ///
+    /// let archive = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("archive").write_size_limit(WriteSizeLimit::Large);
+    /// ```
+    fn write_size_limit(self, limit: WriteSizeLimit) -> Self {
+        self.add_param(WRITE_SIZE_LIMIT, limit)
+    }
+
+    /// Makes the server send the data as an attachment named `filename`
+    /// (`content-disposition`), so a browser opening the URL saves it as a file.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, Paramable};
+    ///
+    /// let backup = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").download("users.json");
+    /// ```
+    fn download(self, filename: &str) -> Self {
+        self.add_param(DOWNLOAD, filename)
     }
 
-    pub fn finish(&mut self) -> Firebase {
-        self.set_params();
-        Firebase::new(self.uri.as_str()).unwrap()
+    /// Asks the server to answer writes with `204 No Content` instead of echoing the written
+    /// data (`print=silent`), which saves bandwidth for bulk writers.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, Paramable, RequestableExt};
+    /// use serde_json::json;
+    ///
+    /// # async fn run() {
+    /// let events = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("events").silent();
+    /// events.push(&json!({ "kind": "login" })).await;
+    /// # }
+    /// ```
+    fn silent(self) -> Self {
+        self.output(OutputMode::Silent)
+    }
+
+    /// Indented JSON responses (`print=pretty`), handy when the raw body is logged or saved.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, Paramable, Requestable};
+    ///
+    /// # async fn run() {
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").pretty();
+    /// let readable = users.get_raw().await;
+    /// # }
+    /// ```
+    fn pretty(self) -> Self {
+        self.output(OutputMode::Pretty)
+    }
+
+    /// Replaces any previously selected output mode.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, OutputMode, Paramable};
+    ///
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").output(OutputMode::Export);
+    /// ```
+    fn output(self, mode: OutputMode) -> Self {
+        let (key, value) = mode.param();
+        self.remove_param(FORMAT)
+            .remove_param(PRINT)
+            .add_param(key, value)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::params::Params;
+    use crate::errors::QueryError;
+    use crate::params::{
+        append_query_pair, OrderBy, OutputMode, Paramable, Params, QueryValue, WriteSizeLimit,
+    };
     use std::collections::HashMap;
+    use std::time::Duration;
     use url::Url;
 
     #[test]
@@ -85,16 +606,232 @@ mod tests {
         params.insert("param_1", "value_1");
         params.insert("param_2", "value_2");
 
-        let mut param = Params::new(Url::parse("https://github.com/emreyalvac").unwrap());
+        let mut param = Params::new();
+        let mut uri = Url::parse("https://github.com/emreyalvac").unwrap();
 
         for (k, v) in params {
-            param.add_param(&k, v);
+            param.insert(k, v);
         }
-        param.set_params();
+        param.set_params(&mut uri);
 
         assert_eq!(
-            param.uri.as_str(),
+            uri.as_str(),
             "https://github.com/emreyalvac?param_1=value_1&param_2=value_2"
         )
     }
+
+    #[test]
+    fn output_modes_replace_each_other() {
+        let params = Params::new()
+            .output(OutputMode::Export)
+            .output(OutputMode::Silent);
+        assert_eq!(params.get("format"), None);
+        assert_eq!(params.get("print"), Some("silent"));
+
+        let params = params.output(OutputMode::Export);
+        assert_eq!(params.get("format"), Some("export"));
+        assert_eq!(params.get("print"), None);
+
+        let params = params.pretty();
+        assert_eq!(params.get("format"), None);
+        assert_eq!(params.get("print"), Some("pretty"));
+        assert_eq!(params.silent().get("print"), Some("silent"));
+    }
+
+    #[test]
+    fn validation() {
+        let ordered = Params::new().order_by("score");
+        assert_eq!(ordered.clone().limit_to_last(10).validate(), Ok(()));
+        assert_eq!(
+            ordered.clone().limit_to_first(i32::MAX as u32).validate(),
+            Ok(())
+        );
+        assert_eq!(
+            ordered.clone().limit_to_first(u32::MAX).validate(),
+            Err(QueryError::LimitTooLarge {
+                key: "limitToFirst",
+                max: i32::MAX as u32
+            })
+        );
+        assert_eq!(
+            ordered
+                .clone()
+                .limit_to_first(1)
+                .limit_to_last(1)
+                .validate(),
+            Err(QueryError::ConflictingLimits)
+        );
+        assert_eq!(
+            ordered.clone().start_at(5).end_at(2).validate(),
+            Err(QueryError::EmptyRange {
+                start: "5".to_string(),
+                end: "2".to_string()
+            })
+        );
+        assert_eq!(ordered.start_at(2).end_at(2).validate(), Ok(()));
+        assert_eq!(
+            Params::new().equal_to(1).validate(),
+            Err(QueryError::MissingOrderBy)
+        );
+        assert_eq!(Params::new().shallow(true).validate(), Ok(()));
+    }
+
+    #[test]
+    fn typed_values() {
+        let params = Params::new()
+            .order_by("\"name\"")
+            .start_at("Ada")
+            .end_at(2.5)
+            .equal_to(Option::<bool>::None);
+        assert_eq!(params.get("startAt"), Some("\"Ada\""));
+        assert_eq!(params.get("endAt"), Some("2.5"));
+        assert_eq!(params.get("equalTo"), Some("null"));
+
+        assert_eq!(QueryValue::from(-3).to_string(), "-3");
+        assert_eq!(QueryValue::from(true).to_string(), "true");
+        assert_eq!(QueryValue::from(1.0).to_string(), "1.0");
+    }
+
+    #[test]
+    fn order_by_is_quoted() {
+        assert_eq!(
+            Params::new().order_by("name").get("orderBy"),
+            Some("\"name\"")
+        );
+        assert_eq!(
+            Params::new().order_by("\"$key\"").get("orderBy"),
+            Some("\"$key\"")
+        );
+        assert_eq!(OrderBy::from("$priority"), OrderBy::Priority);
+        assert_eq!(OrderBy::Value.to_string(), "\"$value\"");
+        assert_eq!(OrderBy::child("a\"b").to_string(), "\"a\\\"b\"");
+    }
+
+    #[test]
+    fn bounds_with_keys() {
+        let params = Params::new()
+            .order_by(OrderBy::child("points"))
+            .start_at_with_key(120, "user_17")
+            .end_at_with_key("z", "last");
+        assert_eq!(params.get("startAt"), Some("120,\"user_17\""));
+        assert_eq!(params.get("endAt"), Some("\"z\",\"last\""));
+        assert_eq!(params.validate(), Ok(()));
+    }
+
+    #[test]
+    fn server_limits() {
+        let params = Params::new()
+            .server_timeout(Duration::from_secs(15))
+            .write_size_limit(WriteSizeLimit::Tiny);
+        assert_eq!(params.get("timeout"), Some("15s"));
+        assert_eq!(params.get("writeSizeLimit"), Some("tiny"));
+
+        let timeout = |duration| Params::new().server_timeout(duration);
+        assert_eq!(
+            timeout(Duration::from_secs(120)).get("timeout"),
+            Some("2min")
+        );
+        assert_eq!(
+            timeout(Duration::from_millis(1500)).get("timeout"),
+            Some("1500ms")
+        );
+        assert_eq!(timeout(Duration::ZERO).get("timeout"), Some("0s"));
+    }
+
+    #[test]
+    fn server_timeout_range() {
+        let timeout = |duration| Params::new().server_timeout(duration).validate();
+        assert_eq!(timeout(Duration::from_millis(1)), Ok(()));
+        assert_eq!(timeout(Duration::from_secs(15 * 60)), Ok(()));
+        assert_eq!(
+            timeout(Duration::ZERO),
+            Err(QueryError::InvalidTimeout("0s".to_string()))
+        );
+        assert_eq!(
+            timeout(Duration::from_micros(500)),
+            Err(QueryError::InvalidTimeout("0s".to_string()))
+        );
+        assert_eq!(
+            timeout(Duration::from_secs(15 * 60 + 1)),
+            Err(QueryError::InvalidTimeout("901s".to_string()))
+        );
+
+        let raw = Params::new().add_param("timeout", "soon");
+        assert_eq!(
+            raw.validate(),
+            Err(QueryError::InvalidTimeout("soon".to_string()))
+        );
+    }
+
+    #[test]
+    fn value_encoding() {
+        let cases: [(QueryValue, &str, &str); 7] = [
+            (
+                "Söme \"name\"".into(),
+                "\"Söme \\\"name\\\"\"",
+                "%22S%C3%B6me%20%5C%22name%5C%22%22",
+            ),
+            ("a+b&c=d".into(), "\"a+b&c=d\"", "%22a%2Bb%26c%3Dd%22"),
+            ((-42).into(), "-42", "-42"),
+            ((-0.5).into(), "-0.5", "-0.5"),
+            (
+                1e21.into(),
+                "1000000000000000000000.0",
+                "1000000000000000000000.0",
+            ),
+            (f64::NAN.into(), "null", "null"),
+            (QueryValue::Null, "null", "null"),
+        ];
+
+        for (value, literal, encoded) in cases {
+            assert_eq!(value.to_string(), literal);
+            let mut uri = Url::parse("https://db.firebaseio.com/users.json").unwrap();
+            Params::new()
+                .order_by("name")
+                .equal_to(value)
+                .set_params(&mut uri);
+            assert_eq!(
+                uri.query(),
+                Some(format!("equalTo={}&orderBy=%22name%22", encoded).as_str())
+            );
+        }
+
+        let mut uri = Url::parse("https://db.firebaseio.com/.json?shallow=true").unwrap();
+        append_query_pair(&mut uri, "auth", "a b/c");
+        assert_eq!(uri.query(), Some("shallow=true&auth=a%20b%2Fc"));
+    }
+
+    #[test]
+    fn equal_to_bool_and_null() {
+        let by_flag = Params::new().order_by("active");
+        assert_eq!(
+            by_flag.clone().equal_to(false).get("equalTo"),
+            Some("false")
+        );
+        assert_eq!(by_flag.clone().equal_to(()).get("equalTo"), Some("null"));
+        assert_eq!(by_flag.equal_to(Some(true)).get("equalTo"), Some("true"));
+    }
+
+    #[test]
+    fn negative_and_float_ranges() {
+        let params = Params::new()
+            .order_by("offset")
+            .start_at(-86_400_000_i64)
+            .end_at(-0.25);
+        assert_eq!(params.get("startAt"), Some("-86400000"));
+        assert_eq!(params.get("endAt"), Some("-0.25"));
+        assert_eq!(params.validate(), Ok(()));
+
+        assert_eq!(QueryValue::from(1e-7).to_string(), "0.0000001");
+        assert_eq!(
+            QueryValue::from(-3e20).to_string(),
+            "-300000000000000000000.0"
+        );
+        assert_eq!(QueryValue::from(0.1_f32).to_string(), "0.1");
+        assert_eq!(
+            QueryValue::from(u64::MAX),
+            QueryValue::Float(u64::MAX as f64)
+        );
+        assert_eq!(QueryValue::from(7_usize), QueryValue::Int(7));
+    }
 }
@@ -0,0 +1,394 @@
+use crate::constants::{
+    END_AT, END_BEFORE, EQUAL_TO, LIMIT_TO_FIRST, LIMIT_TO_LAST, ORDER_BY, START_AFTER, START_AT,
+};
+use crate::endpoint::Endpoint;
+use crate::errors::QueryResult;
+use crate::params::{OrderBy, Paramable, Params, QueryValue};
+use crate::Firebase;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+pub(crate) const QUERY_KEYS: [&str; 8] = [
+    ORDER_BY,
+    LIMIT_TO_FIRST,
+    LIMIT_TO_LAST,
+    START_AT,
+    START_AFTER,
+    END_AT,
+    END_BEFORE,
+    EQUAL_TO,
+];
+
+/// [`QueryBuilder`] state before `orderBy` is chosen; filters and limits are unavailable.
+#[derive(Debug, Clone, Copy)]
+pub struct Unordered;
+
+/// [`QueryBuilder`] state once `orderBy` is chosen.
+#[derive(Debug, Clone, Copy)]
+pub struct Ordered;
+
+/// [`QueryBuilder`] state without a limit.
+#[derive(Debug, Clone, Copy)]
+pub struct Unlimited;
+
+/// [`QueryBuilder`] state after `limitToFirst` or `limitToLast`; a second limit is unavailable.
+#[derive(Debug, Clone, Copy)]
+pub struct Limited;
+
+/// Query parameters whose type tracks what has been set, so that filtering without `orderBy`
+/// or combining `limitToFirst` with `limitToLast` doesn't compile.
+///
+/// ```
+/// use firebase_rs::{Firebase, OrderBy, QueryBuilder};
+///
+/// let query = QueryBuilder::new().order_by(OrderBy::child("age")).start_at(18).limit_to_first(10);
+/// let adults = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").query(query);
+/// ```
+///
+/// ```compile_fail
+/// use firebase_rs::QueryBuilder;
+///
+/// let query = QueryBuilder::new().start_at(18);
+/// ```
+///
+/// ```compile_fail
+/// use firebase_rs::{OrderBy, QueryBuilder};
+///
+/// let query = QueryBuilder::new().order_by(OrderBy::Key).limit_to_first(1).limit_to_last(1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct QueryBuilder<O = Unordered, L = Unlimited> {
+    params: Params,
+    state: PhantomData<(O, L)>,
+}
+
+impl QueryBuilder {
+    pub fn new() -> Self {
+        Self {
+            params: Params::new(),
+            state: PhantomData,
+        }
+    }
+}
+
+impl Default for QueryBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O, L> QueryBuilder<O, L> {
+    fn with<O2, L2>(mut self, key: &str, value: impl ToString) -> QueryBuilder<O2, L2> {
+        self.params.insert(key, value);
+        QueryBuilder {
+            params: self.params,
+            state: PhantomData,
+        }
+    }
+
+    pub fn build(self) -> Params {
+        self.params
+    }
+}
+
+impl QueryBuilder<Unordered, Unlimited> {
+    pub fn order_by(self, order: impl Into<OrderBy>) -> QueryBuilder<Ordered, Unlimited> {
+        self.with(ORDER_BY, order.into())
+    }
+}
+
+impl<L> QueryBuilder<Ordered, L> {
+    pub fn start_at(self, value: impl Into<QueryValue>) -> Self {
+        self.with(START_AT, value.into())
+    }
+
+    pub fn end_at(self, value: impl Into<QueryValue>) -> Self {
+        self.with(END_AT, value.into())
+    }
+
+    pub fn start_after(self, value: impl Into<QueryValue>) -> Self {
+        self.with(START_AFTER, value.into())
+    }
+
+    pub fn end_before(self, value: impl Into<QueryValue>) -> Self {
+        self.with(END_BEFORE, value.into())
+    }
+
+    pub fn equal_to(self, value: impl Into<QueryValue>) -> Self {
+        self.with(EQUAL_TO, value.into())
+    }
+}
+
+impl QueryBuilder<Ordered, Unlimited> {
+    pub fn limit_to_first(self, count: u32) -> QueryBuilder<Ordered, Limited> {
+        self.with(LIMIT_TO_FIRST, count)
+    }
+
+    pub fn limit_to_last(self, count: u32) -> QueryBuilder<Ordered, Limited> {
+        self.with(LIMIT_TO_LAST, count)
+    }
+}
+
+impl<O, L> From<QueryBuilder<O, L>> for Params {
+    fn from(query: QueryBuilder<O, L>) -> Self {
+        query.build()
+    }
+}
+
+/// Which end of the ordered children a [`Filter`] keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Limit {
+    First(u32),
+    Last(u32),
+}
+
+/// An ordering plus the range and limit applied to it, compiled to `orderBy`, `startAt`,
+/// `endAt`, `equalTo` and `limitToFirst`/`limitToLast`. Setting a range replaces an exact
+/// match and vice versa, and a second limit replaces the first, so the only mistakes left for
+/// [`validate`](Filter::validate) are empty ranges and zero limits.
+///
+/// ```
+/// use firebase_rs::{Filter, Firebase};
+///
+/// let adults = Filter::child("age").between(18, 30).limit_first(10);
+/// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").query(adults);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    order: OrderBy,
+    start: Option<QueryValue>,
+    end: Option<QueryValue>,
+    equal: Option<QueryValue>,
+    limit: Option<Limit>,
+}
+
+impl Filter {
+    pub fn new(order: impl Into<OrderBy>) -> Self {
+        Self {
+            order: order.into(),
+            start: None,
+            end: None,
+            equal: None,
+            limit: None,
+        }
+    }
+
+    pub fn child(path: &str) -> Self {
+        Self::new(OrderBy::child(path))
+    }
+
+    pub fn key() -> Self {
+        Self::new(OrderBy::Key)
+    }
+
+    pub fn value() -> Self {
+        Self::new(OrderBy::Value)
+    }
+
+    pub fn priority() -> Self {
+        Self::new(OrderBy::Priority)
+    }
+
+    /// Keeps children from `low` to `high`, both inclusive.
+    pub fn between(self, low: impl Into<QueryValue>, high: impl Into<QueryValue>) -> Self {
+        self.at_least(low).at_most(high)
+    }
+
+    pub fn at_least(mut self, low: impl Into<QueryValue>) -> Self {
+        self.start = Some(low.into());
+        self.equal = None;
+        self
+    }
+
+    pub fn at_most(mut self, high: impl Into<QueryValue>) -> Self {
+        self.end = Some(high.into());
+        self.equal = None;
+        self
+    }
+
+    pub fn equal_to(mut self, value: impl Into<QueryValue>) -> Self {
+        self.equal = Some(value.into());
+        self.start = None;
+        self.end = None;
+        self
+    }
+
+    pub fn limit_first(mut self, count: u32) -> Self {
+        self.limit = Some(Limit::First(count));
+        self
+    }
+
+    pub fn limit_last(mut self, count: u32) -> Self {
+        self.limit = Some(Limit::Last(count));
+        self
+    }
+
+    pub fn validate(&self) -> QueryResult<()> {
+        Params::from(self.clone()).validate()
+    }
+}
+
+impl From<Filter> for Params {
+    fn from(filter: Filter) -> Self {
+        let mut params = Params::new().order_by(filter.order);
+        if let Some(start) = filter.start {
+            params = params.start_at(start);
+        }
+        if let Some(end) = filter.end {
+            params = params.end_at(end);
+        }
+        if let Some(equal) = filter.equal {
+            params = params.equal_to(equal);
+        }
+        match filter.limit {
+            Some(Limit::First(count)) => params.limit_to_first(count),
+            Some(Limit::Last(count)) => params.limit_to_last(count),
+            None => params,
+        }
+    }
+}
+
+impl Endpoint {
+    /// Replaces the query parameters of this endpoint with those of `query`, a [`QueryBuilder`]
+    /// or [`Filter`]; other parameters, such as `shallow` or the output mode, are kept.
+    pub fn query(self, query: impl Into<Params>) -> Self {
+        let params = query.into();
+        QUERY_KEYS
+            .into_iter()
+            .fold(self, |endpoint, key| match params.get(key) {
+                Some(value) => endpoint.add_param(key, value),
+                None => endpoint.remove_param(key),
+            })
+    }
+}
+
+/// A database path together with its query parameters, for canned queries kept in
+/// configuration and replayed with [`Firebase::query`].
+///
+/// ```
+/// use firebase_rs::{Firebase, QuerySpec};
+///
+/// let spec: QuerySpec = serde_json::from_str(r#"{
+///     "path": "users",
+///     "params": { "orderBy": "\"age\"", "startAt": "18", "limitToFirst": "10" }
+/// }"#).unwrap();
+/// let adults = Firebase::new("https://myfirebase.firebaseio.com").unwrap().query(&spec);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuerySpec {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Params::is_empty")]
+    pub params: Params,
+}
+
+impl QuerySpec {
+    pub fn new(path: &str, params: impl Into<Params>) -> Self {
+        Self {
+            path: path.to_string(),
+            params: params.into(),
+        }
+    }
+}
+
+impl Endpoint {
+    /// Captures the path and parameters of this endpoint, leaving out credentials.
+    pub fn to_spec(&self) -> QuerySpec {
+        QuerySpec {
+            path: self.path(),
+            params: self.params().clone(),
+        }
+    }
+}
+
+impl Firebase {
+    /// The endpoint captured by `spec`, the inverse of [`Endpoint::to_spec`].
+    pub fn query(&self, spec: &QuerySpec) -> Endpoint {
+        self.at(&spec.path).extend_params(&spec.params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::QueryError;
+    use crate::params::{OrderBy, Paramable, Params};
+    use crate::query::{Filter, QueryBuilder, QuerySpec};
+    use crate::Firebase;
+
+    #[test]
+    fn builds_valid_queries() {
+        let query = QueryBuilder::new()
+            .order_by(OrderBy::child("age"))
+            .start_at(18)
+            .limit_to_last(10)
+            .end_at(30);
+        let params = query.clone().build();
+        assert_eq!(params.validate(), Ok(()));
+        assert_eq!(params.get("limitToLast"), Some("10"));
+
+        let users = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .at("users")
+            .order_by("name")
+            .limit_to_first(5)
+            .shallow(true)
+            .query(query);
+        assert_eq!(
+            users.get_uri(),
+            "https://myfirebase.firebaseio.com/users.json?endAt=30&limitToLast=10&orderBy=%22age%22&shallow=true&startAt=18"
+        );
+    }
+
+    #[test]
+    fn filters() {
+        let params = Params::from(Filter::child("age").between(18, 30).limit_first(10));
+        assert_eq!(
+            params,
+            Params::new()
+                .order_by(OrderBy::child("age"))
+                .start_at(18)
+                .end_at(30)
+                .limit_to_first(10)
+        );
+
+        let params = Params::from(
+            Filter::key()
+                .between("a", "m")
+                .equal_to("kim")
+                .limit_first(1)
+                .limit_last(2),
+        );
+        assert_eq!(params.get("startAt"), None);
+        assert_eq!(params.get("equalTo"), Some("\"kim\""));
+        assert_eq!(params.get("limitToFirst"), None);
+        assert_eq!(params.get("limitToLast"), Some("2"));
+        assert_eq!(params.validate(), Ok(()));
+
+        assert_eq!(
+            Filter::value().between(5, 1).validate(),
+            Err(QueryError::EmptyRange {
+                start: "5".to_string(),
+                end: "1".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn spec_round_trip() {
+        let firebase = Firebase::auth("https://myfirebase.firebaseio.com", "secret").unwrap();
+        let adults = firebase
+            .at("users")
+            .query(Filter::child("age").at_least(18))
+            .shallow(false);
+
+        let json = serde_json::to_string(&adults.to_spec()).unwrap();
+        assert_eq!(
+            json,
+            r#"{"path":"users","params":{"orderBy":"\"age\"","shallow":"false","startAt":"18"}}"#
+        );
+        let spec: QuerySpec = serde_json::from_str(&json).unwrap();
+        assert_eq!(firebase.query(&spec).get_uri(), adults.get_uri());
+
+        let spec: QuerySpec = serde_json::from_str(r#"{"path":"users/42"}"#).unwrap();
+        assert_eq!(spec, QuerySpec::new("users/42", Params::new()));
+    }
+}
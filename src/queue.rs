@@ -0,0 +1,267 @@
+use crate::clients::{BodyStream, HttpClient, HttpResponse, StreamingResponse};
+use crate::errors::{RequestError, RequestResult};
+use crate::hooks::HttpRequest;
+use crate::Firebase;
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::oneshot;
+
+/// Scheduling class of a request, attached with [`Endpoint::with_priority`](crate::Endpoint::with_priority).
+/// Under a concurrency limit, waiting requests are admitted highest priority first and in
+/// arrival order within a priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Batch,
+    #[default]
+    Normal,
+    Interactive,
+}
+
+struct Waiter {
+    priority: Priority,
+    sequence: Reverse<u64>,
+    sender: oneshot::Sender<Permit>,
+}
+
+impl Waiter {
+    fn key(&self) -> (Priority, Reverse<u64>) {
+        (self.priority, self.sequence)
+    }
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    available: usize,
+    sequence: u64,
+    waiters: BinaryHeap<Waiter>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Slot in the limiter, handed to the next waiter when dropped.
+struct Permit {
+    shared: Option<Arc<Shared>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let shared = match self.shared.take() {
+            Some(shared) => shared,
+            None => return,
+        };
+
+        let mut state = shared.lock();
+        while let Some(waiter) = state.waiters.pop() {
+            let permit = Permit {
+                shared: Some(shared.clone()),
+            };
+            match waiter.sender.send(permit) {
+                Ok(()) => return,
+                // The waiter gave up; disarm the permit and try the next one.
+                Err(mut permit) => drop(permit.shared.take()),
+            }
+        }
+        state.available += 1;
+    }
+}
+
+/// Wraps a transport and runs at most a fixed number of requests at once.
+pub struct ConcurrencyLimit<C> {
+    inner: C,
+    shared: Arc<Shared>,
+}
+
+impl<C> ConcurrencyLimit<C> {
+    /// Allows `max_concurrent` requests at once, at least 1.
+    pub fn new(inner: C, max_concurrent: usize) -> Self {
+        let state = State {
+            available: max_concurrent.max(1),
+            ..State::default()
+        };
+        Self {
+            inner,
+            shared: Arc::new(Shared {
+                state: Mutex::new(state),
+            }),
+        }
+    }
+
+    async fn acquire(&self, priority: Priority) -> RequestResult<Permit> {
+        let receiver = {
+            let mut state = self.shared.lock();
+            if state.available > 0 && state.waiters.is_empty() {
+                state.available -= 1;
+                return Ok(Permit {
+                    shared: Some(self.shared.clone()),
+                });
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            state.sequence += 1;
+            let sequence = Reverse(state.sequence);
+            state.waiters.push(Waiter {
+                priority,
+                sequence,
+                sender,
+            });
+            receiver
+        };
+
+        match receiver.await {
+            Ok(permit) => Ok(permit),
+            Err(_) => Err(RequestError::NetworkError),
+        }
+    }
+}
+
+impl<C> Debug for ConcurrencyLimit<C>
+where
+    C: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = self.shared.lock();
+        f.debug_struct("ConcurrencyLimit")
+            .field("inner", &self.inner)
+            .field("available", &state.available)
+            .field("waiting", &state.waiters.len())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<C> HttpClient for ConcurrencyLimit<C>
+where
+    C: HttpClient,
+{
+    async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+        let priority = request
+            .extensions()
+            .get::<Priority>()
+            .copied()
+            .unwrap_or_default();
+        let _permit = self.acquire(priority).await?;
+
+        self.inner.execute(request).await
+    }
+
+    async fn execute_streaming(&self, request: HttpRequest) -> RequestResult<StreamingResponse> {
+        let priority = request
+            .extensions()
+            .get::<Priority>()
+            .copied()
+            .unwrap_or_default();
+        let permit = self.acquire(priority).await?;
+
+        // The slot stays taken until the body has been consumed or dropped.
+        let (parts, body) = self.inner.execute_streaming(request).await?.into_parts();
+        let body: BodyStream = Box::pin(body.map(move |chunk| {
+            let _ = &permit;
+            chunk
+        }));
+        Ok(http::Response::from_parts(parts, body))
+    }
+}
+
+impl Firebase {
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_concurrency_limit(8);
+    /// ```
+    pub fn with_concurrency_limit(mut self, max_concurrent: usize) -> Self {
+        self.client = Arc::new(ConcurrencyLimit::new(self.client, max_concurrent));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::queue::{ConcurrencyLimit, Priority};
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn higher_priority_goes_first() {
+        let limit = Arc::new(ConcurrencyLimit::new((), 1));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let held = limit.acquire(Priority::Normal).await.unwrap();
+
+        let mut tasks = Vec::new();
+        for priority in [Priority::Batch, Priority::Batch, Priority::Interactive] {
+            let limit = limit.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                let _permit = limit.acquire(priority).await.unwrap();
+                order.lock().unwrap().push(priority);
+            }));
+            tokio::task::yield_now().await;
+        }
+
+        drop(held);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec![Priority::Interactive, Priority::Batch, Priority::Batch]
+        );
+    }
+
+    #[tokio::test]
+    async fn abandoned_waiters_release_their_slot() {
+        let limit = ConcurrencyLimit::new((), 1);
+        let held = limit.acquire(Priority::Normal).await.unwrap();
+
+        let mut waiting = Box::pin(limit.acquire(Priority::Interactive));
+        assert!(futures::poll!(waiting.as_mut()).is_pending());
+        drop(waiting);
+
+        drop(held);
+        let _permit = limit.acquire(Priority::Batch).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn zero_allows_one_request() {
+        let limit = ConcurrencyLimit::new((), 0);
+        let held = limit.acquire(Priority::Normal).await.unwrap();
+
+        let mut waiting = Box::pin(limit.acquire(Priority::Normal));
+        assert!(futures::poll!(waiting.as_mut()).is_pending());
+        drop(held);
+        let _permit = waiting.await.unwrap();
+    }
+}
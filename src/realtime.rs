@@ -0,0 +1,711 @@
+use crate::credential::AuthCredential;
+use crate::errors::{RealtimeError, RealtimeResult};
+use crate::events::Event;
+use crate::server_time::ServerTimeOffset;
+use crate::Firebase;
+use futures::stream::BoxStream;
+use futures::{SinkExt, Stream, StreamExt};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio_tungstenite::tungstenite::Message;
+use url::Url;
+
+const PROTOCOL_VERSION: &str = "5";
+/// The server drops connections that stay silent for a minute.
+const KEEPALIVE: Duration = Duration::from_secs(45);
+const MAX_REDIRECTS: usize = 3;
+
+type Pending = HashMap<u64, oneshot::Sender<RealtimeResult<Value>>>;
+
+/// State shared with the reader task. It deliberately doesn't hold the outgoing channel, so the
+/// connection closes once every [`Realtime`] handle and listener is gone.
+struct State {
+    pending: Mutex<Pending>,
+    listeners: Mutex<Vec<Subscription>>,
+    connected: watch::Sender<bool>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            pending: Mutex::default(),
+            listeners: Mutex::default(),
+            connected: watch::channel(false).0,
+        }
+    }
+}
+
+struct Subscription {
+    id: u64,
+    path: String,
+    sender: mpsc::UnboundedSender<Event>,
+}
+
+struct Connection {
+    outgoing: mpsc::UnboundedSender<Message>,
+    next_id: AtomicU64,
+    state: Arc<State>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Connection speaking the realtime wire protocol the client SDKs use, for what REST can't do.
+/// Clones share the connection; it closes when the last clone and listener are dropped.
+#[derive(Clone)]
+pub struct Realtime {
+    connection: Arc<Connection>,
+    server_time: ServerTimeOffset,
+}
+
+impl std::fmt::Debug for Realtime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Realtime").finish_non_exhaustive()
+    }
+}
+
+/// Strips the slashes the protocol doesn't use around paths.
+fn normalize(path: &str) -> String {
+    path.trim_matches('/').to_string()
+}
+
+/// Where `changed` sits below `listened`, in the `/`-rooted form of [`Event`] paths.
+fn relative(listened: &str, changed: &str) -> Option<String> {
+    if listened.is_empty() {
+        return Some(format!("/{}", changed));
+    }
+    if changed == listened {
+        return Some("/".to_string());
+    }
+    changed
+        .strip_prefix(listened)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .map(|rest| format!("/{}", rest))
+}
+
+fn socket_url(base: &Url, host: Option<&str>) -> RealtimeResult<Url> {
+    let secure = base.scheme() != "http";
+    let host = match (host, base.host_str()) {
+        (Some(host), _) => host.to_string(),
+        (None, Some(host)) => match base.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        },
+        (None, None) => return Err(RealtimeError::Handshake("database URL has no host".into())),
+    };
+    let namespace = base
+        .query_pairs()
+        .find(|(key, _)| key == "ns")
+        .map(|(_, ns)| ns.into_owned())
+        .unwrap_or_else(|| {
+            let database = base.host_str().unwrap_or_default();
+            database.split('.').next().unwrap_or_default().to_string()
+        });
+
+    let scheme = if secure { "wss" } else { "ws" };
+    let url = format!(
+        "{}://{}/.ws?v={}&ns={}",
+        scheme, host, PROTOCOL_VERSION, namespace
+    );
+    match Url::parse(&url) {
+        Ok(url) => Ok(url),
+        Err(err) => Err(RealtimeError::Handshake(err.to_string())),
+    }
+}
+
+/// Reassembles messages the server splits into several frames, announced by a frame count.
+#[derive(Default)]
+struct Frames {
+    remaining: usize,
+    buffer: String,
+}
+
+impl Frames {
+    fn push(&mut self, text: &str) -> Option<String> {
+        if self.remaining == 0 {
+            if text.len() <= 6 {
+                if let Ok(count) = text.parse::<usize>() {
+                    self.remaining = count;
+                    self.buffer.clear();
+                    return None;
+                }
+            }
+            return Some(text.to_string());
+        }
+
+        self.buffer.push_str(text);
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            Some(std::mem::take(&mut self.buffer))
+        } else {
+            None
+        }
+    }
+}
+
+impl State {
+    fn handle(&self, message: &Value) {
+        if message["t"] != "d" {
+            return;
+        }
+        let data = &message["d"];
+
+        if let Some(id) = data["r"].as_u64() {
+            if let Some(reply) = lock(&self.pending).remove(&id) {
+                let body = &data["b"];
+                let result = match body["s"].as_str() {
+                    Some("ok") => Ok(body["d"].clone()),
+                    status => Err(RealtimeError::Rejected {
+                        status: status.unwrap_or("unknown").to_string(),
+                        message: match &body["d"] {
+                            Value::String(message) => message.clone(),
+                            other => other.to_string(),
+                        },
+                    }),
+                };
+                let _ = reply.send(result);
+            }
+            return;
+        }
+
+        let body = &data["b"];
+        let path = normalize(body["p"].as_str().unwrap_or_default());
+        let mut listeners = lock(&self.listeners);
+        match data["a"].as_str() {
+            Some(action @ ("d" | "m")) => {
+                for listener in listeners.iter() {
+                    if let Some(path) = relative(&listener.path, &path) {
+                        let data = body["d"].clone();
+                        let event = match action {
+                            "d" => Event::Put { path, data },
+                            _ => Event::Patch { path, data },
+                        };
+                        let _ = listener.sender.send(event);
+                    }
+                }
+            }
+            Some("c") => listeners.retain(|listener| {
+                if listener.path != path {
+                    return true;
+                }
+                let _ = listener.sender.send(Event::Cancel);
+                false
+            }),
+            Some("ac") => {
+                for listener in listeners.drain(..) {
+                    let _ = listener.sender.send(Event::AuthRevoked);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn close(&self) {
+        self.connected.send_replace(false);
+        lock(&self.pending).clear();
+        lock(&self.listeners).clear();
+    }
+}
+
+impl Connection {
+    fn send(
+        &self,
+        action: &str,
+        body: Value,
+    ) -> RealtimeResult<oneshot::Receiver<RealtimeResult<Value>>> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (reply, response) = oneshot::channel();
+        lock(&self.state.pending).insert(id, reply);
+
+        let message = json!({ "t": "d", "d": { "r": id, "a": action, "b": body } });
+        match self.outgoing.send(Message::Text(message.to_string())) {
+            Ok(()) => Ok(response),
+            Err(_) => {
+                lock(&self.state.pending).remove(&id);
+                Err(RealtimeError::Closed)
+            }
+        }
+    }
+
+    async fn request(&self, action: &str, body: Value) -> RealtimeResult<Value> {
+        match self.send(action, body)?.await {
+            Ok(result) => result,
+            Err(_) => Err(RealtimeError::Closed),
+        }
+    }
+}
+
+/// Events of one location, from [`Realtime::listen`]. Dropping it stops the listen on the server.
+pub struct RealtimeEvents {
+    events: mpsc::UnboundedReceiver<Event>,
+    connection: Arc<Connection>,
+    id: u64,
+    path: String,
+}
+
+impl Stream for RealtimeEvents {
+    type Item = Event;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+impl Drop for RealtimeEvents {
+    fn drop(&mut self) {
+        let mut listeners = lock(&self.connection.state.listeners);
+        listeners.retain(|listener| listener.id != self.id);
+        if !listeners.iter().any(|listener| listener.path == self.path) {
+            let _ = self
+                .connection
+                .send("n", json!({ "p": format!("/{}", self.path) }));
+        }
+    }
+}
+
+impl Realtime {
+    async fn connect(base: &Url, credential: Option<AuthCredential>) -> RealtimeResult<Self> {
+        let mut host = None;
+        let mut redirects = 0;
+        let (socket, server_time) = loop {
+            let url = socket_url(base, host.as_deref())?;
+            let sent = SystemTime::now();
+            let mut socket = match tokio_tungstenite::connect_async(url.as_str()).await {
+                Ok((socket, _)) => socket,
+                Err(err) => return Err(RealtimeError::WebSocket(err.to_string())),
+            };
+
+            let handshake = match socket.next().await {
+                Some(Ok(Message::Text(text))) => text,
+                Some(Ok(other)) => return Err(RealtimeError::Handshake(other.to_string())),
+                Some(Err(err)) => return Err(RealtimeError::WebSocket(err.to_string())),
+                None => return Err(RealtimeError::Closed),
+            };
+            let handshake: Value = match serde_json::from_str(&handshake) {
+                Ok(handshake) => handshake,
+                Err(_) => return Err(RealtimeError::Handshake(handshake)),
+            };
+
+            let control = &handshake["d"];
+            match (handshake["t"].as_str(), control["t"].as_str()) {
+                (Some("c"), Some("h")) => {
+                    let server_millis = match control["d"]["ts"].as_i64() {
+                        Some(server_millis) => server_millis,
+                        None => return Err(RealtimeError::Handshake(handshake.to_string())),
+                    };
+                    let offset =
+                        ServerTimeOffset::from_probe(sent, SystemTime::now(), server_millis);
+                    break (socket, offset);
+                }
+                (Some("c"), Some("r")) if redirects < MAX_REDIRECTS => {
+                    redirects += 1;
+                    host = control["d"].as_str().map(str::to_string);
+                }
+                _ => return Err(RealtimeError::Handshake(handshake.to_string())),
+            }
+        };
+
+        let (mut sink, mut source) = socket.split();
+        let (outgoing, mut queue) = mpsc::unbounded_channel::<Message>();
+        let state = Arc::new(State::default());
+
+        tokio::spawn(async move {
+            loop {
+                let message = match tokio::time::timeout(KEEPALIVE, queue.recv()).await {
+                    Ok(Some(message)) => message,
+                    Ok(None) => break,
+                    Err(_) => Message::Text("0".to_string()),
+                };
+                if sink.send(message).await.is_err() {
+                    return;
+                }
+            }
+            let _ = sink.close().await;
+        });
+
+        let reader = state.clone();
+        tokio::spawn(async move {
+            let mut frames = Frames::default();
+            while let Some(Ok(message)) = source.next().await {
+                let text = match message {
+                    Message::Text(text) => text,
+                    Message::Close(_) => break,
+                    _ => continue,
+                };
+                if let Some(text) = frames.push(&text) {
+                    if let Ok(message) = serde_json::from_str::<Value>(&text) {
+                        reader.handle(&message);
+                    }
+                }
+            }
+            reader.close();
+        });
+
+        let realtime = Realtime {
+            connection: Arc::new(Connection {
+                outgoing,
+                next_id: AtomicU64::new(1),
+                state,
+            }),
+            server_time,
+        };
+        if let Some(credential) = credential {
+            let (action, token) = match &credential {
+                AuthCredential::AccessToken(token) => ("gauth", token),
+                AuthCredential::DatabaseSecret(token) | AuthCredential::IdToken(token) => {
+                    ("auth", token)
+                }
+            };
+            realtime
+                .connection
+                .request(action, json!({ "cred": token.expose() }))
+                .await?;
+        }
+        realtime.connection.state.connected.send_replace(true);
+
+        Ok(realtime)
+    }
+
+    /// Counterpart of the SDKs' `.info/connected`: yields the current state, then every change.
+    /// Connections aren't re-established, so `false` is the last item.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// let realtime = firebase.realtime().await.unwrap();
+    /// let mut states = realtime.connection_state();
+    /// while let Some(connected) = states.next().await {
+    ///     println!("online: {}", connected);
+    /// }
+    /// # }
+    /// ```
+    pub fn connection_state(&self) -> BoxStream<'static, bool> {
+        let receiver = self.connection.state.connected.subscribe();
+        Box::pin(futures::stream::unfold(
+            (Some(receiver), true),
+            |(receiver, first)| async move {
+                let mut receiver = receiver?;
+                if !first && receiver.changed().await.is_err() {
+                    return None;
+                }
+                let connected = *receiver.borrow_and_update();
+                // A closed connection stays closed.
+                let next = if connected { Some(receiver) } else { None };
+                Some((connected, (next, false)))
+            },
+        ))
+    }
+
+    /// Counterpart of the SDKs' `.info/serverTimeOffset`, measured during the handshake. The
+    /// offset can point either way, so it comes as a [`Duration`] from
+    /// [`ServerTimeOffset::offset`] together with [`ServerTimeOffset::is_ahead`].
+    pub fn server_time_offset(&self) -> ServerTimeOffset {
+        self.server_time
+    }
+
+    /// Subscribes to `path`; the first event is a `Put` at `/` with the current value.
+    pub async fn listen(&self, path: &str) -> RealtimeResult<RealtimeEvents> {
+        let path = normalize(path);
+        let id = self.connection.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, events) = mpsc::unbounded_channel();
+        lock(&self.connection.state.listeners).push(Subscription {
+            id,
+            path: path.clone(),
+            sender,
+        });
+
+        let events = RealtimeEvents {
+            events,
+            connection: self.connection.clone(),
+            id,
+            path: path.clone(),
+        };
+        self.connection
+            .request("q", json!({ "p": format!("/{}", path), "h": "" }))
+            .await?;
+
+        Ok(events)
+    }
+
+    /// Replaces the value at `path`.
+    pub async fn put<T>(&self, path: &str, data: &T) -> RealtimeResult<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.write("p", path, data).await
+    }
+
+    /// Updates the given children of `path`, leaving the others untouched.
+    pub async fn merge<T>(&self, path: &str, data: &T) -> RealtimeResult<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.write("m", path, data).await
+    }
+
+    /// Queues writes the server performs when this connection drops, e.g. to mark a user
+    /// offline.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    /// use serde_json::json;
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::auth("https://myfirebase.firebaseio.com", "my_auth_key").unwrap();
+    /// let realtime = firebase.realtime().await.unwrap();
+    ///
+    /// realtime.on_disconnect("status/USER_ID").set(&json!("offline")).await.unwrap();
+    /// realtime.put("status/USER_ID", &json!("online")).await.unwrap();
+    /// # }
+    /// ```
+    pub fn on_disconnect(&self, path: &str) -> OnDisconnect<'_> {
+        OnDisconnect {
+            realtime: self,
+            path: path.to_string(),
+        }
+    }
+
+    async fn write<T>(&self, action: &str, path: &str, data: &T) -> RealtimeResult<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let data = match serde_json::to_value(data) {
+            Ok(data) => data,
+            Err(_) => return Err(RealtimeError::SerializeError),
+        };
+        let body = json!({ "p": format!("/{}", normalize(path)), "d": data });
+        self.connection.request(action, body).await.map(|_| ())
+    }
+}
+
+/// Writes held back until the connection drops, from [`Realtime::on_disconnect`]. Each call
+/// replaces what was queued before for the same location.
+#[derive(Debug)]
+pub struct OnDisconnect<'a> {
+    realtime: &'a Realtime,
+    path: String,
+}
+
+impl OnDisconnect<'_> {
+    pub async fn set<T>(&self, data: &T) -> RealtimeResult<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.realtime.write("o", &self.path, data).await
+    }
+
+    pub async fn update<T>(&self, data: &T) -> RealtimeResult<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.realtime.write("om", &self.path, data).await
+    }
+
+    pub async fn remove(&self) -> RealtimeResult<()> {
+        self.set(&Value::Null).await
+    }
+
+    /// Drops every queued write for this location and its children.
+    pub async fn cancel(&self) -> RealtimeResult<()> {
+        let body = json!({ "p": format!("/{}", normalize(&self.path)) });
+        self.realtime
+            .connection
+            .request("oc", body)
+            .await
+            .map(|_| ())
+    }
+}
+
+impl Firebase {
+    /// Opens a realtime connection authenticated with the current credentials, asking an
+    /// installed [`TokenProvider`](crate::TokenProvider) for a fresh one first, like REST
+    /// requests do. Must be called from within a Tokio runtime.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    /// use futures::StreamExt;
+    /// use serde_json::json;
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::auth("https://myfirebase.firebaseio.com", "my_auth_key").unwrap();
+    /// let realtime = firebase.realtime().await.unwrap();
+    ///
+    /// let mut events = realtime.listen("rooms/lobby").await.unwrap();
+    /// realtime.merge("rooms/lobby", &json!({ "topic": "Rust" })).await.unwrap();
+    /// while let Some(event) = events.next().await {
+    ///     println!("{:?}", event);
+    /// }
+    /// # }
+    /// ```
+    pub async fn realtime(&self) -> RealtimeResult<Realtime> {
+        if let Err(err) = self.auth.refresh(self.client.as_ref()).await {
+            return Err(RealtimeError::Credential(err));
+        }
+        Realtime::connect(&self.uri, self.auth.get()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::credential::AuthCredential;
+    use crate::errors::{RealtimeError, RequestError, RequestResult};
+    use crate::events::Event;
+    use crate::realtime::{
+        relative, socket_url, Connection, Frames, Realtime, State, Subscription,
+    };
+    use crate::server_time::ServerTimeOffset;
+    use crate::{Firebase, TokenProvider};
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+    use std::sync::atomic::AtomicU64;
+    use std::sync::Arc;
+    use std::time::UNIX_EPOCH;
+    use tokio::sync::mpsc;
+    use tokio_tungstenite::tungstenite::Message;
+    use url::Url;
+
+    #[test]
+    fn socket_urls() {
+        let url = Url::parse("https://myfirebase.firebaseio.com").unwrap();
+        assert_eq!(
+            socket_url(&url, None).unwrap().as_str(),
+            "wss://myfirebase.firebaseio.com/.ws?v=5&ns=myfirebase"
+        );
+        assert_eq!(
+            socket_url(&url, Some("s-usc1a-nss-2.firebaseio.com"))
+                .unwrap()
+                .as_str(),
+            "wss://s-usc1a-nss-2.firebaseio.com/.ws?v=5&ns=myfirebase"
+        );
+
+        let emulator = Url::parse("http://127.0.0.1:9000/?ns=demo").unwrap();
+        assert_eq!(
+            socket_url(&emulator, None).unwrap().as_str(),
+            "ws://127.0.0.1:9000/.ws?v=5&ns=demo"
+        );
+    }
+
+    #[test]
+    fn split_frames() {
+        let mut frames = Frames::default();
+        assert_eq!(frames.push("2"), None);
+        assert_eq!(frames.push("{\"t\":"), None);
+        assert_eq!(frames.push("\"d\"}"), Some("{\"t\":\"d\"}".to_string()));
+        assert_eq!(frames.push("{}"), Some("{}".to_string()));
+    }
+
+    #[test]
+    fn dispatch() {
+        let state = State::default();
+        let (sender, mut events) = mpsc::unbounded_channel();
+        state.listeners.lock().unwrap().push(Subscription {
+            id: 1,
+            path: "rooms".to_string(),
+            sender,
+        });
+
+        state.handle(&json!({ "t": "d", "d": { "a": "m", "b": { "p": "rooms/lobby", "d": { "topic": "Rust" } } } }));
+        state.handle(&json!({ "t": "d", "d": { "a": "d", "b": { "p": "users", "d": 1 } } }));
+        state.handle(&json!({ "t": "d", "d": { "a": "c", "b": { "p": "/rooms" } } }));
+
+        assert_eq!(
+            events.try_recv().unwrap(),
+            Event::Patch {
+                path: "/lobby".to_string(),
+                data: json!({ "topic": "Rust" })
+            }
+        );
+        assert_eq!(events.try_recv().unwrap(), Event::Cancel);
+        assert!(events.try_recv().is_err());
+        assert_eq!(relative("", "a/b"), Some("/a/b".to_string()));
+        assert_eq!(relative("a/b", "a/bc"), None);
+    }
+
+    #[tokio::test]
+    async fn on_disconnect_messages() {
+        let (outgoing, mut sent) = mpsc::unbounded_channel();
+        let state = Arc::new(State::default());
+        let realtime = Realtime {
+            connection: Arc::new(Connection {
+                outgoing,
+                next_id: AtomicU64::new(1),
+                state: state.clone(),
+            }),
+            server_time: ServerTimeOffset::from_probe(UNIX_EPOCH, UNIX_EPOCH, 0),
+        };
+
+        let on_disconnect = realtime.on_disconnect("status/u1");
+        let offline = json!({ "online": false });
+        let reply = async {
+            state.handle(&json!({ "t": "d", "d": { "r": 1, "b": { "s": "ok", "d": "" } } }));
+        };
+        let (update, _) = futures::join!(on_disconnect.update(&offline), reply);
+        update.unwrap();
+
+        let message = match sent.recv().await.unwrap() {
+            Message::Text(text) => serde_json::from_str::<Value>(&text).unwrap(),
+            other => panic!("unexpected message: {:?}", other),
+        };
+        assert_eq!(
+            message,
+            json!({ "t": "d", "d": { "r": 1, "a": "om", "b": { "p": "/status/u1", "d": { "online": false } } } })
+        );
+    }
+
+    #[tokio::test]
+    async fn connection_state() {
+        let (outgoing, _sent) = mpsc::unbounded_channel();
+        let state = Arc::new(State::default());
+        let realtime = Realtime {
+            connection: Arc::new(Connection {
+                outgoing,
+                next_id: AtomicU64::new(1),
+                state: state.clone(),
+            }),
+            server_time: ServerTimeOffset::from_probe(UNIX_EPOCH, UNIX_EPOCH, 0),
+        };
+
+        state.connected.send_replace(true);
+        let mut states = realtime.connection_state();
+        assert_eq!(states.next().await, Some(true));
+        state.close();
+        assert_eq!(states.next().await, Some(false));
+        assert_eq!(states.next().await, None);
+    }
+
+    #[derive(Debug)]
+    struct Unavailable;
+
+    #[async_trait]
+    impl TokenProvider for Unavailable {
+        async fn token(&self) -> RequestResult<AuthCredential> {
+            Err(RequestError::NetworkError)
+        }
+    }
+
+    #[tokio::test]
+    async fn connects_only_after_the_provider_answers() {
+        let firebase = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_token_provider(Unavailable);
+        match firebase.realtime().await {
+            Err(RealtimeError::Credential(RequestError::NetworkError)) => {}
+            other => panic!("expected the provider's error, got {:?}", other.err()),
+        }
+    }
+}
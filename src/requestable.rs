@@ -0,0 +1,385 @@
+use crate::constants::{Method, PushResponse, Response};
+use crate::errors::{RequestError, RequestResult};
+use crate::json::from_json;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+#[async_trait]
+pub trait Requestable: Send + Sync {
+    async fn request(&self, method: Method, data: Option<Value>) -> RequestResult<Response>;
+
+    /// ```
+    /// use firebase_rs::{Firebase, Requestable};
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let users = firebase.get_as_string().await;
+    /// # }
+    /// ```
+    async fn get_as_string(&self) -> RequestResult<Response> {
+        self.request(Method::GET, None).await
+    }
+
+    /// Returns the response body exactly as sent by the server, including a literal `null` for
+    /// missing nodes.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, Requestable};
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let json = firebase.get_raw().await;
+    /// # }
+    /// ```
+    async fn get_raw(&self) -> RequestResult<String> {
+        match self.get_as_string().await {
+            Ok(response) => Ok(response.data),
+            Err(RequestError::NotFoundOrNullBody) => Ok(String::from("null")),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// ```
+    /// use firebase_rs::{Firebase, Requestable};
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID");
+    /// firebase.delete().await;
+    /// # }
+    /// ```
+    async fn delete(&self) -> RequestResult<Response> {
+        self.request(Method::DELETE, None).await
+    }
+}
+
+/// Typed helpers on top of [`Requestable`], available for every implementation including
+/// `dyn Requestable`.
+#[async_trait]
+pub trait RequestableExt: Requestable {
+    async fn request_generic<T>(&self, method: Method) -> RequestResult<T>
+    where
+        T: Serialize + DeserializeOwned + Debug,
+    {
+        let response = self.request(method, None).await?;
+
+        from_json(response.data.as_bytes())
+    }
+
+    /// ```
+    /// use firebase_rs::{Firebase, RequestableExt};
+    /// use serde::{Serialize, Deserialize};
+    ///
+    /// #[derive(Serialize, Deserialize, Debug)]
+    /// struct User {
+    ///    name: String
+    /// }
+    ///
+    /// # async fn run() {
+    /// let user = User { name: String::default() };
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let users = firebase.push(&user).await;
+    /// # }
+    /// ```
+    #[deprecated(
+        note = "`set` sends a POST, which adds a child under a generated key; use `push` for that or `put` to overwrite this location"
+    )]
+    async fn set<T>(&self, data: &T) -> RequestResult<Response>
+    where
+        T: Serialize + DeserializeOwned + Debug + Sync,
+    {
+        match serde_json::to_value(data) {
+            Ok(data) => self.request(Method::POST, Some(data)).await,
+            Err(err) => Err(RequestError::Serialize(err)),
+        }
+    }
+
+    /// Adds `data` as a new child under a server-generated, chronologically ordered key (POST)
+    /// and returns that key.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, RequestableExt};
+    /// use serde_json::json;
+    ///
+    /// # async fn run() {
+    /// let messages = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("messages");
+    /// if let Ok(created) = messages.push(&json!({ "text": "hello" })).await {
+    ///     println!("stored as {}", created.key);
+    /// }
+    /// # }
+    /// ```
+    async fn push<T>(&self, data: &T) -> RequestResult<PushResponse>
+    where
+        T: Serialize + Sync + ?Sized,
+    {
+        let data = match serde_json::to_value(data) {
+            Ok(data) => data,
+            Err(err) => return Err(RequestError::Serialize(err)),
+        };
+        let response = self.request(Method::POST, Some(data)).await?;
+        from_json(response.data.as_bytes())
+    }
+
+    /// Overwrites this location with `data`, replacing any children (PUT). This is what the
+    /// official SDKs call `set`.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, RequestableExt};
+    /// use serde_json::json;
+    ///
+    /// # async fn run() {
+    /// let user = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID");
+    /// user.put(&json!({ "name": "Ada" })).await;
+    /// # }
+    /// ```
+    async fn put<T>(&self, data: &T) -> RequestResult<Response>
+    where
+        T: Serialize + Sync + ?Sized,
+    {
+        match serde_json::to_value(data) {
+            Ok(data) => self.request(Method::PUT, Some(data)).await,
+            Err(err) => Err(RequestError::Serialize(err)),
+        }
+    }
+
+    /// A missing node comes back as `null`, which fails with
+    /// [`RequestError::NotFoundOrNullBody`]; use [`get_opt`](RequestableExt::get_opt) when the
+    /// node may not exist.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use firebase_rs::{Firebase, RequestableExt};
+    /// use serde::{Serialize, Deserialize};
+    ///
+    /// #[derive(Serialize, Deserialize, Debug)]
+    /// struct User {
+    ///     name: String
+    /// }
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID");
+    /// let user = firebase.get::<User>().await;
+    ///
+    ///  // OR
+    ///
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let user = firebase.get::<HashMap<String, User>>().await;
+    /// # }
+    /// ```
+    async fn get<T>(&self) -> RequestResult<T>
+    where
+        T: Serialize + DeserializeOwned + Debug,
+    {
+        self.request_generic::<T>(Method::GET).await
+    }
+
+    /// Like [`get`](RequestableExt::get) but maps a missing node to `None`.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, RequestableExt};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize, Debug)]
+    /// struct User {
+    ///     name: String
+    /// }
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID");
+    /// match firebase.get_opt::<User>().await {
+    ///     Ok(Some(user)) => println!("{}", user.name),
+    ///     Ok(None) => println!("no such user"),
+    ///     Err(err) => println!("{}", err),
+    /// }
+    /// # }
+    /// ```
+    async fn get_opt<T>(&self) -> RequestResult<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        from_json(self.get_raw().await?.as_bytes())
+    }
+
+    /// ```
+    /// use firebase_rs::{Firebase, RequestableExt};
+    /// use serde::{Serialize, Deserialize};
+    ///
+    /// #[derive(Serialize, Deserialize, Debug)]
+    /// struct User {
+    ///     name: String
+    /// }
+    ///
+    /// # async fn run() {
+    /// let user = User { name: String::default() };
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID");
+    /// let users = firebase.update(&user).await;
+    /// # }
+    /// ```
+    async fn update<T>(&self, data: &T) -> RequestResult<Response>
+    where
+        T: DeserializeOwned + Serialize + Debug + Sync,
+    {
+        match serde_json::to_value(data) {
+            Ok(value) => self.request(Method::PATCH, Some(value)).await,
+            Err(err) => Err(RequestError::Serialize(err)),
+        }
+    }
+
+    /// Writes several locations below this one in a single PATCH, so they are all applied or
+    /// none are. Keys are paths relative to this location; a `null` value deletes the path.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, RequestableExt};
+    /// use serde_json::{json, Value};
+    /// use std::collections::HashMap;
+    ///
+    /// # async fn run() {
+    /// let mut updates: HashMap<String, Value> = HashMap::new();
+    /// updates.insert("posts/POST_ID/title".to_string(), json!("Hello"));
+    /// updates.insert("users/USER_ID/posts/POST_ID".to_string(), json!(true));
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_params();
+    /// firebase.update_multi(&updates).await;
+    /// # }
+    /// ```
+    async fn update_multi<T>(&self, updates: &HashMap<String, T>) -> RequestResult<Response>
+    where
+        T: Serialize + Sync,
+    {
+        let mut body = Map::new();
+        for (path, value) in updates {
+            let value = match serde_json::to_value(value) {
+                Ok(value) => value,
+                Err(err) => return Err(RequestError::Serialize(err)),
+            };
+            body.insert(path.trim_matches('/').to_string(), value);
+        }
+        self.request(Method::PATCH, Some(Value::Object(body))).await
+    }
+}
+
+impl<R> RequestableExt for R where R: Requestable + ?Sized {}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::{Method, Response};
+    use crate::errors::{RequestError, RequestResult};
+    use crate::requestable::{Requestable, RequestableExt};
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    struct Mock;
+
+    struct Pushed;
+
+    #[async_trait]
+    impl Requestable for Pushed {
+        async fn request(&self, method: Method, _data: Option<Value>) -> RequestResult<Response> {
+            assert_eq!(method, Method::POST);
+            Ok(Response {
+                data: r#"{"name":"-Nabc123"}"#.to_string(),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Requestable for Mock {
+        async fn request(&self, method: Method, data: Option<Value>) -> RequestResult<Response> {
+            Ok(Response {
+                data: json!({ "method": format!("{:?}", method), "data": data }).to_string(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn trait_objects() {
+        let requestable: Arc<dyn Requestable> = Arc::new(Mock);
+
+        let value = requestable.get::<Value>().await.unwrap();
+        assert_eq!(value, json!({ "method": "GET", "data": null }));
+
+        let response = requestable.put(&json!(1)).await.unwrap();
+        assert_eq!(
+            response.data,
+            json!({ "method": "PUT", "data": 1 }).to_string()
+        );
+        let updates = HashMap::from([
+            ("/posts/1/title".to_string(), json!("Hi")),
+            ("users/7/posts/1".to_string(), json!(true)),
+        ]);
+        let response = requestable.update_multi(&updates).await.unwrap();
+        assert_eq!(
+            response.data,
+            json!({
+                "method": "PATCH",
+                "data": { "posts/1/title": "Hi", "users/7/posts/1": true }
+            })
+            .to_string()
+        );
+
+        let pushed = Pushed.push(&json!(2)).await.unwrap();
+        assert_eq!(pushed.key, "-Nabc123");
+
+        let response = requestable.update(&json!({ "name": "x" })).await.unwrap();
+        assert_eq!(
+            response.data,
+            json!({ "method": "PATCH", "data": { "name": "x" } }).to_string()
+        );
+    }
+
+    struct Missing;
+
+    #[async_trait]
+    impl Requestable for Missing {
+        async fn request(&self, _method: Method, _data: Option<Value>) -> RequestResult<Response> {
+            Err(RequestError::NotFoundOrNullBody)
+        }
+    }
+
+    #[tokio::test]
+    async fn optional_reads() {
+        assert_eq!(Missing.get_opt::<String>().await.unwrap(), None);
+        assert!(matches!(
+            Missing.get::<Value>().await,
+            Err(RequestError::NotFoundOrNullBody)
+        ));
+        assert_eq!(
+            Mock.get_opt::<Value>().await.unwrap(),
+            Some(json!({ "method": "GET", "data": null }))
+        );
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct User {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    impl serde::Serialize for User {
+        fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("unsupported"))
+        }
+    }
+
+    #[tokio::test]
+    async fn failures_are_errors() {
+        assert!(matches!(
+            Mock.get::<HashMap<String, String>>().await,
+            Err(RequestError::Decode { .. })
+        ));
+        let user = User {
+            name: "Ada".to_string(),
+        };
+        assert!(matches!(
+            Mock.update(&user).await,
+            Err(RequestError::Serialize(_))
+        ));
+        #[allow(deprecated)]
+        let set = Mock.set(&user).await;
+        assert!(matches!(set, Err(RequestError::Serialize(_))));
+    }
+}
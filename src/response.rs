@@ -0,0 +1,340 @@
+use crate::clients::HttpResponse;
+use crate::constants::Method;
+use crate::endpoint::{check_status, Endpoint};
+use crate::errors::{RequestError, RequestResult};
+use crate::json::from_json;
+use crate::transaction::ETAG_REQUEST_HEADER;
+use http::header::{HeaderName, HeaderValue, CONTENT_LENGTH, ETAG, IF_NONE_MATCH};
+use http::{HeaderMap, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// A decoded response body together with the status and headers it came with.
+#[derive(Debug, Clone)]
+pub struct FirebaseResponse<T> {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: T,
+}
+
+impl<T> FirebaseResponse<T> {
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The version of the data, present on responses to [`Endpoint::get_full`].
+    pub fn etag(&self) -> Option<&str> {
+        self.headers.get(ETAG).and_then(|etag| etag.to_str().ok())
+    }
+
+    /// Size of the body in bytes, when the server sent a `content-length`.
+    pub fn content_length(&self) -> Option<u64> {
+        self.headers
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse().ok())
+    }
+
+    pub fn body(&self) -> &T {
+        &self.body
+    }
+
+    pub fn into_body(self) -> T {
+        self.body
+    }
+}
+
+/// Outcome of [`Endpoint::get_if_changed`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Maybe<T> {
+    /// The data differs from the given version; `etag` identifies the new one.
+    Modified {
+        value: T,
+        etag: String,
+    },
+    NotModified,
+}
+
+impl Endpoint {
+    /// Like [`get`](crate::RequestableExt::get) but keeps the status and headers, and asks for
+    /// the ETag of the data. A missing node decodes from `null`, so read it as `Option<T>`.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    /// use serde_json::Value;
+    ///
+    /// # async fn run() {
+    /// let user = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID");
+    /// if let Ok(response) = user.get_full::<Option<Value>>().await {
+    ///     println!("{:?} at version {:?}", response.body(), response.etag());
+    /// }
+    /// # }
+    /// ```
+    pub async fn get_full<T>(&self) -> RequestResult<FirebaseResponse<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self
+            .send_with_header(
+                Method::GET,
+                None,
+                HeaderName::from_static(ETAG_REQUEST_HEADER),
+                HeaderValue::from_static("true"),
+            )
+            .await?;
+        decode(response)
+    }
+
+    /// Sends a `HEAD` request, returning the status and headers a read of this location would
+    /// carry, including its ETag, without transferring the data.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// # async fn run() {
+    /// let logs = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("logs");
+    /// if let Ok(head) = logs.head().await {
+    ///     println!("version {:?}, {:?} bytes", head.etag(), head.content_length());
+    /// }
+    /// # }
+    /// ```
+    pub async fn head(&self) -> RequestResult<FirebaseResponse<()>> {
+        self.auth().refresh(self.client()).await?;
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static(ETAG_REQUEST_HEADER),
+            HeaderValue::from_static("true"),
+        );
+        let request = self.build_request_with(http::Method::HEAD, None, headers)?;
+
+        let (parts, _) = check_status(self.client().execute(request).await?)?.into_parts();
+        Ok(FirebaseResponse {
+            status: parts.status,
+            headers: parts.headers,
+            body: (),
+        })
+    }
+
+    /// Reads this location only if it changed since the version `etag` from an earlier
+    /// [`get_full`](Endpoint::get_full) or [`get_if_changed`](Endpoint::get_if_changed). The
+    /// ETag goes out as `if-none-match`, letting the server answer `304` without a body; when it
+    /// sends the data anyway, an identical ETag still yields [`Maybe::NotModified`].
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, Maybe};
+    /// use serde_json::Value;
+    ///
+    /// # async fn run() {
+    /// let config = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("config");
+    /// let mut etag = String::new();
+    /// if let Ok(Maybe::Modified { value, etag: latest }) = config.get_if_changed::<Value>(&etag).await {
+    ///     println!("new config: {}", value);
+    ///     etag = latest;
+    /// }
+    /// # }
+    /// ```
+    pub async fn get_if_changed<T>(&self, etag: &str) -> RequestResult<Maybe<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.auth().refresh(self.client()).await?;
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static(ETAG_REQUEST_HEADER),
+            HeaderValue::from_static("true"),
+        );
+        if let Ok(etag) = HeaderValue::from_str(etag) {
+            headers.insert(IF_NONE_MATCH, etag);
+        }
+        let request = self.build_request_with(http::Method::GET, None, headers)?;
+        let response = self.client().execute(request).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Maybe::NotModified);
+        }
+
+        let response = decode::<T>(response)?;
+        match response.etag() {
+            Some(latest) if latest == etag => Ok(Maybe::NotModified),
+            Some(latest) => Ok(Maybe::Modified {
+                etag: latest.to_string(),
+                value: response.into_body(),
+            }),
+            None => Err(RequestError::NetworkError),
+        }
+    }
+
+    /// Sends `data` with `method` and decodes the response into a [`FirebaseResponse`]. An empty
+    /// body, as returned with `print=silent`, decodes from `null`.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, Method};
+    /// use serde_json::{json, Value};
+    ///
+    /// # async fn run() {
+    /// let user = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users").at("USER_ID");
+    /// let response = user.request_full::<Value>(Method::PATCH, Some(json!({ "name": "Ada" }))).await;
+    /// # }
+    /// ```
+    pub async fn request_full<T>(
+        &self,
+        method: Method,
+        data: Option<Value>,
+    ) -> RequestResult<FirebaseResponse<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let body = match data.map(|data| serde_json::to_vec(&data)) {
+            Some(Ok(body)) => Some(body),
+            Some(Err(err)) => return Err(RequestError::Serialize(err)),
+            None => None,
+        };
+        decode(self.send(method, body).await?)
+    }
+}
+
+fn decode<T: DeserializeOwned>(response: HttpResponse) -> RequestResult<FirebaseResponse<T>> {
+    let (parts, body) = check_status(response)?.into_parts();
+
+    let body: &[u8] = if body.is_empty() { b"null" } else { &body };
+    Ok(FirebaseResponse {
+        status: parts.status,
+        headers: parts.headers,
+        body: from_json(body)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::constants::Method;
+    use crate::errors::RequestResult;
+    use crate::hooks::HttpRequest;
+    use crate::response::Maybe;
+    use crate::Firebase;
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+
+    #[derive(Debug)]
+    struct Versioned;
+
+    #[async_trait]
+    impl HttpClient for Versioned {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            if request.method() == http::Method::GET {
+                assert_eq!(request.headers()["x-firebase-etag"], "true");
+                let mut response = http::Response::new(b"{\"name\":\"Ada\"}".to_vec());
+                response
+                    .headers_mut()
+                    .insert(http::header::ETAG, "v1".parse().unwrap());
+                return Ok(response);
+            }
+
+            let mut response = http::Response::new(Vec::new());
+            *response.status_mut() = http::StatusCode::NO_CONTENT;
+            Ok(response)
+        }
+    }
+
+    #[tokio::test]
+    async fn full_responses() {
+        let user = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(Versioned)
+            .at("users/1");
+
+        let response = user.get_full::<Value>().await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(response.etag(), Some("v1"));
+        assert_eq!(response.into_body(), json!({ "name": "Ada" }));
+
+        let response = user
+            .request_full::<Option<Value>>(Method::PUT, Some(json!(1)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), http::StatusCode::NO_CONTENT);
+        assert_eq!(response.etag(), None);
+        assert_eq!(*response.body(), None);
+    }
+
+    /// Serves version `v2`, answering `304` only when asked to.
+    #[derive(Debug)]
+    struct Cached {
+        honours_if_none_match: bool,
+    }
+
+    #[async_trait]
+    impl HttpClient for Cached {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            let mut response = http::Response::new(b"2".to_vec());
+            if self.honours_if_none_match && request.headers()["if-none-match"] == "v2" {
+                *response.status_mut() = http::StatusCode::NOT_MODIFIED;
+                response.body_mut().clear();
+            }
+            response
+                .headers_mut()
+                .insert(http::header::ETAG, "v2".parse().unwrap());
+            Ok(response)
+        }
+    }
+
+    #[tokio::test]
+    async fn conditional_reads() {
+        for honours_if_none_match in [true, false] {
+            let counter = Firebase::new("https://myfirebase.firebaseio.com")
+                .unwrap()
+                .with_http_client(Cached {
+                    honours_if_none_match,
+                })
+                .with_request_hook(|request| {
+                    assert!(request.headers().contains_key("if-none-match"));
+                })
+                .at("counter");
+
+            assert_eq!(
+                counter.get_if_changed::<u32>("v1").await.unwrap(),
+                Maybe::Modified {
+                    value: 2,
+                    etag: "v2".to_string()
+                }
+            );
+            assert_eq!(
+                counter.get_if_changed::<u32>("v2").await.unwrap(),
+                Maybe::NotModified
+            );
+        }
+    }
+
+    #[derive(Debug)]
+    struct Headers;
+
+    #[async_trait]
+    impl HttpClient for Headers {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            assert_eq!(request.method(), http::Method::HEAD);
+            assert_eq!(request.headers()["x-firebase-etag"], "true");
+            let mut response = http::Response::new(Vec::new());
+            let headers = response.headers_mut();
+            headers.insert(http::header::ETAG, "v3".parse().unwrap());
+            headers.insert(http::header::CONTENT_LENGTH, "52431".parse().unwrap());
+            Ok(response)
+        }
+    }
+
+    #[tokio::test]
+    async fn head_requests() {
+        let head = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(Headers)
+            .with_request_hook(|request| assert_eq!(request.method(), http::Method::HEAD))
+            .at("logs")
+            .head()
+            .await
+            .unwrap();
+        assert_eq!(head.etag(), Some("v3"));
+        assert_eq!(head.content_length(), Some(52431));
+    }
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroizing;
+
+#[cfg(feature = "zeroize")]
+type Inner = Zeroizing<String>;
+#[cfg(not(feature = "zeroize"))]
+type Inner = String;
+
+/// A credential that is never printed. With the `zeroize` feature (on by default) it is also
+/// wiped from memory on drop; database secrets, ID and refresh tokens, access tokens and
+/// service account keys are all held as `Secret`s.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(Inner);
+
+impl Secret {
+    /// ```
+    /// use firebase_rs::Secret;
+    ///
+    /// let secret = Secret::new("my_auth_key");
+    /// assert_eq!(format!("{:?}", secret), "Secret(***)");
+    /// ```
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(Inner::from(value.into()))
+    }
+
+    pub fn expose(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Secret(***)")
+    }
+}
+
+impl Hash for Secret {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.expose().hash(state);
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.expose())
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
@@ -0,0 +1,238 @@
+use crate::constants::{Method, SECRETS, SETTINGS};
+use crate::credential::AuthCredential;
+use crate::endpoint::Endpoint;
+use crate::errors::RequestResult;
+use crate::json::from_json;
+use crate::requestable::{Requestable, RequestableExt};
+use crate::secret::Secret;
+use crate::Firebase;
+use serde_json::Value;
+
+/// Legacy database secrets of an instance, managed through its `.settings/secrets` endpoint.
+///
+/// The managing instance must authenticate with an OAuth access token of an owner of the project.
+#[derive(Debug)]
+pub struct DatabaseSecrets {
+    settings: Endpoint,
+}
+
+impl Firebase {
+    /// ```
+    /// use firebase_rs::{AuthCredential, Firebase};
+    ///
+    /// let admin = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// admin.set_credential(AuthCredential::AccessToken("ya29.token".into()));
+    /// let secrets = admin.database_secrets();
+    /// ```
+    pub fn database_secrets(&self) -> DatabaseSecrets {
+        DatabaseSecrets {
+            settings: self.at(SETTINGS).at(SECRETS),
+        }
+    }
+}
+
+impl DatabaseSecrets {
+    /// ```
+    /// use firebase_rs::{AuthCredential, Firebase};
+    ///
+    /// # async fn run() {
+    /// let admin = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// admin.set_credential(AuthCredential::AccessToken("ADMIN_ACCESS_TOKEN".into()));
+    /// let secrets = admin.database_secrets().list().await;
+    /// # }
+    /// ```
+    pub async fn list(&self) -> RequestResult<Vec<Secret>> {
+        self.settings.get::<Vec<Secret>>().await
+    }
+
+    /// ```
+    /// use firebase_rs::{AuthCredential, Firebase};
+    ///
+    /// # async fn run() {
+    /// let admin = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// admin.set_credential(AuthCredential::AccessToken("ADMIN_ACCESS_TOKEN".into()));
+    /// let secret = admin.database_secrets().add().await;
+    /// # }
+    /// ```
+    pub async fn add(&self) -> RequestResult<Secret> {
+        let response = self
+            .settings
+            .request(Method::POST, Some(Value::Object(Default::default())))
+            .await?;
+
+        from_json(response.data.as_bytes())
+    }
+
+    /// ```
+    /// use firebase_rs::{AuthCredential, Firebase};
+    ///
+    /// # async fn run() {
+    /// let admin = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// admin.set_credential(AuthCredential::AccessToken("ADMIN_ACCESS_TOKEN".into()));
+    /// admin.database_secrets().revoke("OLD_DATABASE_SECRET").await;
+    /// # }
+    /// ```
+    pub async fn revoke(&self, secret: &str) -> RequestResult<()> {
+        self.settings.clone().at(secret).delete().await?;
+
+        Ok(())
+    }
+
+    /// Creates a new secret, switches `firebase` (and everything derived from it) over to it and
+    /// revokes the secret it used before.
+    ///
+    /// ```
+    /// use firebase_rs::{AuthCredential, Firebase};
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::auth("https://myfirebase.firebaseio.com", "OLD_DATABASE_SECRET").unwrap();
+    /// let admin = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// admin.set_credential(AuthCredential::AccessToken("ADMIN_ACCESS_TOKEN".into()));
+    /// let new_secret = admin.database_secrets().rotate(&firebase).await;
+    /// # }
+    /// ```
+    pub async fn rotate(&self, firebase: &Firebase) -> RequestResult<Secret> {
+        let previous = firebase.auth.get();
+        let secret = self.add().await?;
+        firebase.set_credential(AuthCredential::DatabaseSecret(secret.clone()));
+
+        if let Some(AuthCredential::DatabaseSecret(previous)) = previous {
+            self.revoke(previous.expose()).await?;
+        }
+
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::credential::AuthCredential;
+    use crate::errors::{RequestError, RequestResult};
+    use crate::hooks::HttpRequest;
+    use crate::secret::Secret;
+    use crate::Firebase;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn secrets_endpoint_is_at_root() {
+        let firebase = Firebase::auth("https://firebase_id.firebaseio.com", "token").unwrap();
+
+        assert_eq!(
+            firebase.database_secrets().settings.get_uri(),
+            "https://firebase_id.firebaseio.com/.settings/secrets.json?auth=token"
+        );
+    }
+
+    /// The secrets endpoint of one instance. New secrets are numbered; `full` refuses to add any.
+    #[derive(Debug, Default)]
+    struct Settings {
+        secrets: Mutex<Vec<String>>,
+        full: bool,
+    }
+
+    #[async_trait]
+    impl HttpClient for Settings {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            assert!(request
+                .uri()
+                .query()
+                .unwrap()
+                .contains("access_token=ADMIN"));
+            let mut secrets = self.secrets.lock().unwrap();
+            let path = request.uri().path();
+            let (status, body) = match (request.method().clone(), path) {
+                (http::Method::GET, "/.settings/secrets.json") => (200, json!(*secrets)),
+                (http::Method::POST, "/.settings/secrets.json") if self.full => {
+                    (400, json!({ "error": "Too many secrets" }))
+                }
+                (http::Method::POST, "/.settings/secrets.json") => {
+                    let secret = format!("secret{}", secrets.len() + 1);
+                    secrets.push(secret.clone());
+                    (200, json!(secret))
+                }
+                (http::Method::DELETE, path) => {
+                    let secret = path
+                        .trim_start_matches("/.settings/secrets/")
+                        .trim_end_matches(".json");
+                    secrets.retain(|kept| kept != secret);
+                    (200, json!(null))
+                }
+                _ => panic!("unexpected {} {}", request.method(), path),
+            };
+            let response = http::Response::builder()
+                .status(status)
+                .body(body.to_string().into_bytes())
+                .unwrap();
+            Ok(response)
+        }
+    }
+
+    fn admin(settings: &Arc<Settings>) -> Firebase {
+        let admin = Firebase::new("https://firebase_id.firebaseio.com")
+            .unwrap()
+            .with_http_client(settings.clone());
+        admin.set_credential(AuthCredential::AccessToken("ADMIN".into()));
+        admin
+    }
+
+    #[tokio::test]
+    async fn manages_secrets() {
+        let settings = Arc::new(Settings::default());
+        let secrets = admin(&settings).database_secrets();
+
+        let added = secrets.add().await.unwrap();
+        assert_eq!(added.expose(), "secret1");
+        assert_eq!(format!("{:?}", added), "Secret(***)");
+        secrets.add().await.unwrap();
+        assert_eq!(
+            secrets.list().await.unwrap(),
+            vec![Secret::new("secret1"), Secret::new("secret2")]
+        );
+
+        secrets.revoke("secret1").await.unwrap();
+        assert_eq!(*settings.secrets.lock().unwrap(), vec!["secret2"]);
+    }
+
+    #[tokio::test]
+    async fn rotate_switches_and_revokes() {
+        let settings = Arc::new(Settings::default());
+        settings.secrets.lock().unwrap().push("old".to_string());
+        let firebase = Firebase::auth("https://firebase_id.firebaseio.com", "old").unwrap();
+
+        let secret = admin(&settings)
+            .database_secrets()
+            .rotate(&firebase)
+            .await
+            .unwrap();
+        assert_eq!(secret.expose(), "secret2");
+        assert_eq!(
+            firebase.auth.get(),
+            Some(AuthCredential::DatabaseSecret(secret))
+        );
+        assert_eq!(*settings.secrets.lock().unwrap(), vec!["secret2"]);
+    }
+
+    #[tokio::test]
+    async fn failed_rotation_keeps_the_old_secret() {
+        let settings = Arc::new(Settings {
+            secrets: Mutex::new(vec!["old".to_string()]),
+            full: true,
+        });
+        let firebase = Firebase::auth("https://firebase_id.firebaseio.com", "old").unwrap();
+
+        let err = admin(&settings)
+            .database_secrets()
+            .rotate(&firebase)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RequestError::Api { status: 400, .. }));
+        assert_eq!(
+            firebase.auth.get(),
+            Some(AuthCredential::DatabaseSecret(Secret::new("old")))
+        );
+        assert_eq!(*settings.secrets.lock().unwrap(), vec!["old"]);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::constants::{SERVER_VALUE, TIMESTAMP};
+use crate::endpoint::Endpoint;
+use crate::errors::RequestResult;
+use crate::json::from_json;
+use crate::requestable::{Requestable, RequestableExt};
+use serde_json::json;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerTimeOffset {
+    offset_millis: i64,
+}
+
+impl ServerTimeOffset {
+    pub(crate) fn from_probe(sent: SystemTime, received: SystemTime, server_millis: i64) -> Self {
+        let sent = millis_since_epoch(sent);
+        let received = millis_since_epoch(received);
+        let local_midpoint = sent + (received - sent) / 2;
+
+        Self {
+            offset_millis: server_millis - local_midpoint,
+        }
+    }
+
+    pub fn offset_millis(&self) -> i64 {
+        self.offset_millis
+    }
+
+    /// How far the server clock is from the local one; [`is_ahead`](ServerTimeOffset::is_ahead)
+    /// tells in which direction.
+    pub fn offset(&self) -> Duration {
+        Duration::from_millis(self.offset_millis.unsigned_abs())
+    }
+
+    /// Whether the server clock runs ahead of the local one.
+    pub fn is_ahead(&self) -> bool {
+        self.offset_millis > 0
+    }
+
+    /// The current time on the server: the local clock corrected by this offset.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    /// use std::time::Duration;
+    ///
+    /// # async fn run() {
+    /// let probes = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("clock_probes");
+    /// let offset = probes.server_time_offset().await.unwrap();
+    /// let lease_ends = offset.estimated_server_time() + Duration::from_secs(30);
+    /// # }
+    /// ```
+    pub fn estimated_server_time(&self) -> SystemTime {
+        let now = SystemTime::now();
+        if self.is_ahead() {
+            now + self.offset()
+        } else {
+            now - self.offset()
+        }
+    }
+}
+
+fn millis_since_epoch(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_millis() as i64,
+        Err(err) => -(err.duration().as_millis() as i64),
+    }
+}
+
+impl Endpoint {
+    /// Pushes a server timestamp as a new child of the current path, reads it back and removes
+    /// that child again. Existing data at the path is left alone, but the caller needs write
+    /// access to it. Query and output parameters on this endpoint are ignored.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// # async fn run() {
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("clock_probes").at("CLIENT_ID");
+    /// let offset = firebase.server_time_offset().await.unwrap();
+    /// # }
+    /// ```
+    pub async fn server_time_offset(&self) -> RequestResult<ServerTimeOffset> {
+        self.probe_server_time(SystemTime::now).await
+    }
+
+    /// [`server_time_offset`](Endpoint::server_time_offset) with the local clock passed in.
+    async fn probe_server_time<C>(&self, now: C) -> RequestResult<ServerTimeOffset>
+    where
+        C: Fn() -> SystemTime,
+    {
+        // `print=silent`, filters or `download` would change what the probe reads back.
+        let location = self.clone().without_params();
+        let sent = now();
+        let probe = location.push(&json!({ SERVER_VALUE: TIMESTAMP })).await?;
+        let received = now();
+
+        let probe = location.at(&probe.key);
+        let server_millis = from_json::<i64>(probe.get_raw().await?.as_bytes());
+        probe.delete().await?;
+        let server_millis = server_millis?;
+
+        Ok(ServerTimeOffset::from_probe(sent, received, server_millis))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::errors::RequestResult;
+    use crate::hooks::HttpRequest;
+    use crate::params::Paramable;
+    use crate::server_time::ServerTimeOffset;
+    use crate::Firebase;
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn offset_uses_round_trip_midpoint() {
+        let sent = UNIX_EPOCH + Duration::from_millis(1_000);
+        let received = UNIX_EPOCH + Duration::from_millis(1_200);
+
+        let offset = ServerTimeOffset::from_probe(sent, received, 1_600);
+        assert_eq!(offset.offset_millis(), 500);
+
+        let offset = ServerTimeOffset::from_probe(sent, received, 900);
+        assert_eq!(offset.offset_millis(), -200);
+    }
+
+    /// Stores values by path and resolves server timestamps to a fixed time.
+    #[derive(Debug, Default)]
+    struct Database(Mutex<BTreeMap<String, Value>>);
+
+    #[async_trait]
+    impl HttpClient for Database {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            assert_eq!(request.uri().query(), None);
+            let mut data = self.0.lock().unwrap();
+            let path = request.uri().path().trim_end_matches(".json").to_string();
+            let body = match *request.method() {
+                http::Method::POST => {
+                    assert_eq!(
+                        serde_json::from_slice::<Value>(request.body()).unwrap(),
+                        json!({ ".sv": "timestamp" })
+                    );
+                    data.insert(format!("{}/-probe", path), json!(1_700_000_000_000i64));
+                    json!({ "name": "-probe" })
+                }
+                http::Method::GET => data.get(&path).cloned().unwrap_or(Value::Null),
+                http::Method::DELETE => {
+                    data.remove(&path);
+                    Value::Null
+                }
+                _ => panic!("unexpected {} {}", request.method(), path),
+            };
+            Ok(http::Response::new(body.to_string().into_bytes()))
+        }
+    }
+
+    #[tokio::test]
+    async fn probe_leaves_data_alone() {
+        let client = Arc::new(Database::default());
+        client
+            .0
+            .lock()
+            .unwrap()
+            .insert("/users".to_string(), json!({ "ada": 1 }));
+        let users = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone())
+            .at("users");
+
+        // The probe is sent at 1_699_999_999_000 and answered 200ms later, so the server's
+        // 1_700_000_000_000 is 900ms ahead of the midpoint.
+        let clock = Mutex::new(vec![1_699_999_999_200u64, 1_699_999_999_000]);
+        let now = || UNIX_EPOCH + Duration::from_millis(clock.lock().unwrap().pop().unwrap());
+        let offset = users.probe_server_time(now).await.unwrap();
+        assert_eq!(offset.offset_millis(), 900);
+        assert_eq!(offset.offset(), Duration::from_millis(900));
+        assert!(offset.is_ahead());
+        assert_eq!(
+            *client.0.lock().unwrap(),
+            BTreeMap::from([("/users".to_string(), json!({ "ada": 1 }))])
+        );
+    }
+
+    #[tokio::test]
+    async fn probe_ignores_the_endpoint_params() {
+        let users = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(Database::default())
+            .at("users");
+        let endpoints = [
+            users.clone().silent(),
+            users.clone().order_by("age").limit_to_first(2),
+            users.clone().shallow(true),
+            users.clone().download("users.json"),
+        ];
+        for endpoint in endpoints {
+            let clock = Mutex::new(vec![1_700_000_000_000u64, 1_700_000_000_000]);
+            let now = || UNIX_EPOCH + Duration::from_millis(clock.lock().unwrap().pop().unwrap());
+            let offset = endpoint.probe_server_time(now).await.unwrap();
+            assert_eq!(offset.offset_millis(), 0);
+        }
+    }
+
+    #[test]
+    fn offset_direction() {
+        let sent = UNIX_EPOCH + Duration::from_millis(10_000);
+        let received = sent + Duration::from_millis(200);
+
+        let ahead = ServerTimeOffset::from_probe(sent, received, 10_350);
+        assert_eq!(ahead.offset(), Duration::from_millis(250));
+        assert!(ahead.is_ahead());
+
+        let behind = ServerTimeOffset::from_probe(sent, received, 9_900);
+        assert_eq!(behind.offset(), Duration::from_millis(200));
+        assert!(!behind.is_ahead());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::clients::{self, HttpClient};
+use crate::credentials::AccessToken;
+use crate::errors::{CredentialError, CredentialResult};
+use crate::external_account::send_json;
+use crate::secret::Secret;
+use crate::utils::unix_seconds;
+use http::header::CONTENT_TYPE;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, SystemTime};
+
+const JWT_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const ASSERTION_LIFETIME: Duration = Duration::from_secs(3600);
+const CUSTOM_TOKEN_AUDIENCE: &str =
+    "https://identitytoolkit.googleapis.com/google.identity.identitytoolkit.v1.IdentityToolkit";
+
+/// A service account key (`"type": "service_account"`), as downloaded from the Firebase console.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServiceAccount {
+    pub project_id: Option<String>,
+    pub private_key_id: Option<String>,
+    pub private_key: Secret,
+    pub client_email: String,
+    #[serde(default = "default_token_uri")]
+    pub token_uri: String,
+}
+
+fn default_token_uri() -> String {
+    DEFAULT_TOKEN_URI.to_string()
+}
+
+#[derive(Serialize)]
+struct AssertionClaims<'a> {
+    iss: &'a str,
+    scope: String,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Serialize)]
+struct CustomTokenClaims<'a> {
+    iss: &'a str,
+    sub: &'a str,
+    aud: &'static str,
+    iat: u64,
+    exp: u64,
+    uid: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    claims: Option<&'a Value>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+impl ServiceAccount {
+    pub async fn access_token(&self, scopes: &[&str]) -> CredentialResult<AccessToken> {
+        self.access_token_via(clients::default_client().as_ref(), scopes)
+            .await
+    }
+
+    pub(crate) async fn access_token_via(
+        &self,
+        client: &dyn HttpClient,
+        scopes: &[&str],
+    ) -> CredentialResult<AccessToken> {
+        let assertion = self.assertion(scopes, SystemTime::now())?;
+        let form = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("grant_type", JWT_BEARER_GRANT_TYPE)
+            .append_pair("assertion", &assertion)
+            .finish();
+        let request = http::Request::post(&self.token_uri)
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded");
+        let response: TokenResponse = send_json(client, request, form.into_bytes()).await?;
+
+        Ok(AccessToken {
+            token: Secret::new(response.access_token),
+            expires_at: SystemTime::now()
+                + Duration::from_secs(response.expires_in.unwrap_or(3600)),
+        })
+    }
+
+    /// Mints a Firebase custom token for `uid`, valid for one hour. `claims` become the
+    /// `auth.token` fields seen by security rules once the token is exchanged with
+    /// [`Auth::sign_in_with_custom_token`](crate::Auth::sign_in_with_custom_token).
+    ///
+    /// ```
+    /// use firebase_rs::{Credentials, Firebase};
+    /// use serde_json::json;
+    ///
+    /// # async fn run() {
+    /// let Ok(Credentials::ServiceAccount(account)) = Credentials::from_file("service-account.json") else { return };
+    /// let token = account.create_custom_token("user-42", Some(&json!({ "premium": true }))).unwrap();
+    /// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap();
+    /// let session = firebase.authentication("API_KEY").sign_in_with_custom_token(&token).await;
+    /// # }
+    /// ```
+    pub fn create_custom_token(
+        &self,
+        uid: &str,
+        claims: Option<&Value>,
+    ) -> CredentialResult<String> {
+        let iat = unix_seconds(SystemTime::now());
+        self.sign(&CustomTokenClaims {
+            iss: &self.client_email,
+            sub: &self.client_email,
+            aud: CUSTOM_TOKEN_AUDIENCE,
+            iat,
+            exp: iat + ASSERTION_LIFETIME.as_secs(),
+            uid,
+            claims,
+        })
+    }
+
+    /// Signs the RS256 JWT that is exchanged for an access token with `scopes`.
+    fn assertion(&self, scopes: &[&str], now: SystemTime) -> CredentialResult<String> {
+        let iat = unix_seconds(now);
+        self.sign(&AssertionClaims {
+            iss: &self.client_email,
+            scope: scopes.join(" "),
+            aud: &self.token_uri,
+            iat,
+            exp: iat + ASSERTION_LIFETIME.as_secs(),
+        })
+    }
+
+    fn sign<T: Serialize>(&self, claims: &T) -> CredentialResult<String> {
+        let key = match EncodingKey::from_rsa_pem(self.private_key.expose().as_bytes()) {
+            Ok(key) => key,
+            Err(err) => return Err(CredentialError::InvalidKey(err.to_string())),
+        };
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = self.private_key_id.clone();
+
+        match jsonwebtoken::encode(&header, claims, &key) {
+            Ok(token) => Ok(token),
+            Err(err) => Err(CredentialError::InvalidKey(err.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::credentials::{Credentials, DATABASE_SCOPES};
+    use crate::errors::RequestResult;
+    use crate::hooks::HttpRequest;
+    use async_trait::async_trait;
+    use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+    use serde_json::{json, Value};
+
+    const RSA_KEY: &str = include_str!("testdata/rsa.pem");
+
+    #[derive(Debug)]
+    struct TokenEndpoint;
+
+    #[async_trait]
+    impl HttpClient for TokenEndpoint {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            assert_eq!(request.uri(), "https://oauth2.googleapis.com/token");
+            let form: Vec<(String, String)> = url::form_urlencoded::parse(request.body())
+                .into_owned()
+                .collect();
+            assert_eq!(form[0].1, "urn:ietf:params:oauth:grant-type:jwt-bearer");
+
+            let mut validation = Validation::new(Algorithm::RS256);
+            validation.insecure_disable_signature_validation();
+            validation.set_audience(&["https://oauth2.googleapis.com/token"]);
+            let key = DecodingKey::from_secret(&[]);
+            let claims = jsonwebtoken::decode::<Value>(&form[1].1, &key, &validation)
+                .unwrap()
+                .claims;
+            assert_eq!(claims["iss"], "admin@my-project.iam.gserviceaccount.com");
+            assert_eq!(claims["scope"], DATABASE_SCOPES.join(" "));
+
+            let body = r#"{"access_token":"ya29.admin","expires_in":3599,"token_type":"Bearer"}"#;
+            Ok(http::Response::new(body.as_bytes().to_vec()))
+        }
+    }
+
+    #[tokio::test]
+    async fn jwt_bearer_exchange() {
+        let json = json!({
+            "type": "service_account",
+            "project_id": "my-project",
+            "private_key_id": "test",
+            "private_key": RSA_KEY,
+            "client_email": "admin@my-project.iam.gserviceaccount.com",
+        });
+        let credentials = Credentials::from_json(&json.to_string()).unwrap();
+
+        let token = credentials
+            .access_token_via(&TokenEndpoint, &DATABASE_SCOPES)
+            .await
+            .unwrap();
+        assert_eq!(token.token.expose(), "ya29.admin");
+    }
+
+    #[test]
+    fn custom_tokens() {
+        let json = json!({
+            "type": "service_account",
+            "private_key": RSA_KEY,
+            "client_email": "admin@my-project.iam.gserviceaccount.com",
+        });
+        let account = match Credentials::from_json(&json.to_string()).unwrap() {
+            Credentials::ServiceAccount(account) => account,
+            other => panic!("unexpected credentials: {:?}", other),
+        };
+
+        let token = account
+            .create_custom_token("user-42", Some(&json!({ "premium": true })))
+            .unwrap();
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.insecure_disable_signature_validation();
+        validation.set_audience(&[
+            "https://identitytoolkit.googleapis.com/google.identity.identitytoolkit.v1.IdentityToolkit",
+        ]);
+        let claims =
+            jsonwebtoken::decode::<Value>(&token, &DecodingKey::from_secret(&[]), &validation)
+                .unwrap()
+                .claims;
+        assert_eq!(claims["uid"], "user-42");
+        assert_eq!(claims["sub"], "admin@my-project.iam.gserviceaccount.com");
+        assert_eq!(claims["claims"], json!({ "premium": true }));
+    }
+}
@@ -0,0 +1,148 @@
+use async_trait::async_trait;
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use std::future::Future;
+use tokio::sync::mpsc;
+
+/// Receives the events [`forward_to_handler`](EventStreamExt::forward_to_handler) takes off a
+/// stream, one at a time. Async closures returning `Result<(), E>` implement it.
+#[async_trait]
+pub trait EventHandler<E>: Send
+where
+    E: Send + 'static,
+{
+    type Error;
+
+    /// Handles one event; an error stops the forwarding and is returned from it.
+    async fn handle(&mut self, event: E) -> Result<(), Self::Error>;
+}
+
+#[async_trait]
+impl<E, F, Fut, Err> EventHandler<E> for F
+where
+    E: Send + 'static,
+    F: FnMut(E) -> Fut + Send,
+    Fut: Future<Output = Result<(), Err>> + Send,
+{
+    type Error = Err;
+
+    async fn handle(&mut self, event: E) -> Result<(), Self::Error> {
+        self(event).await
+    }
+}
+
+/// Forwards a stream of events, such as a listener's, into a sink, a Tokio channel or an
+/// [`EventHandler`]. Implemented for every [`Stream`].
+pub trait EventStreamExt: Stream + Sized {
+    /// ```
+    /// use firebase_rs::EventStreamExt;
+    /// use futures::{channel::mpsc, stream};
+    ///
+    /// # async fn run() {
+    /// let (tx, rx) = mpsc::unbounded::<u32>();
+    /// stream::iter(vec![1, 2, 3]).forward_to_sink(tx).await.unwrap();
+    /// # }
+    /// ```
+    fn forward_to_sink<K>(self, mut sink: K) -> impl Future<Output = Result<(), K::Error>>
+    where
+        K: Sink<Self::Item> + Unpin,
+    {
+        async move {
+            sink.send_all(&mut Box::pin(self.map(Ok))).await?;
+            sink.close().await
+        }
+    }
+
+    /// ```
+    /// use firebase_rs::EventStreamExt;
+    /// use futures::stream;
+    /// use tokio::sync::mpsc;
+    ///
+    /// # async fn run() {
+    /// let (tx, mut rx) = mpsc::channel::<u32>(16);
+    /// stream::iter(vec![1, 2, 3]).forward_to_channel(tx).await.unwrap();
+    /// # }
+    /// ```
+    fn forward_to_channel(
+        self,
+        sender: mpsc::Sender<Self::Item>,
+    ) -> impl Future<Output = Result<(), mpsc::error::SendError<Self::Item>>> {
+        async move {
+            let mut stream = Box::pin(self);
+            while let Some(event) = stream.next().await {
+                sender.send(event).await?;
+            }
+            Ok(())
+        }
+    }
+
+    /// ```
+    /// use firebase_rs::EventStreamExt;
+    /// use futures::stream;
+    ///
+    /// # async fn run() {
+    /// let mut handler = |event: u32| async move {
+    ///     println!("{}", event);
+    ///     Ok::<(), ()>(())
+    /// };
+    /// stream::iter(vec![1, 2, 3]).forward_to_handler(&mut handler).await.unwrap();
+    /// # }
+    /// ```
+    fn forward_to_handler<H>(self, handler: &mut H) -> impl Future<Output = Result<(), H::Error>>
+    where
+        Self::Item: Send + 'static,
+        H: EventHandler<Self::Item>,
+    {
+        async move {
+            let mut stream = Box::pin(self);
+            while let Some(event) = stream.next().await {
+                handler.handle(event).await?;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl<S> EventStreamExt for S where S: Stream + Sized {}
+
+#[cfg(test)]
+mod tests {
+    use crate::sinks::EventStreamExt;
+    use futures::stream;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn forward_to_channel() {
+        let (tx, mut rx) = mpsc::channel(4);
+        stream::iter(vec![1, 2, 3])
+            .forward_to_channel(tx)
+            .await
+            .unwrap();
+
+        let mut received = Vec::new();
+        while let Some(event) = rx.recv().await {
+            received.push(event);
+        }
+        assert_eq!(received, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn forward_to_handler_stops_on_error() {
+        let mut seen = Vec::new();
+        let mut handler = |event: u32| {
+            seen.push(event);
+            async move {
+                if event == 2 {
+                    Err("stop")
+                } else {
+                    Ok(())
+                }
+            }
+        };
+        let result = stream::iter(vec![1, 2, 3])
+            .forward_to_handler(&mut handler)
+            .await;
+
+        assert_eq!(result, Err("stop"));
+        assert_eq!(seen, vec![1, 2]);
+    }
+}
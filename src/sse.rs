@@ -0,0 +1,139 @@
+use crate::clients::{BodyStream, Timeout};
+use crate::constants::Method;
+use crate::endpoint::{check_streaming_status, Endpoint};
+use crate::errors::RequestResult;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use http::header::ACCEPT;
+use std::collections::VecDeque;
+
+/// One server-sent event as delivered by the database: the `event:` name and the `data:` payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: String,
+    pub data: String,
+}
+
+pub type SseStream = BoxStream<'static, RequestResult<SseEvent>>;
+
+/// Splits a byte stream into events following the `text/event-stream` format.
+#[derive(Debug, Default)]
+pub(crate) struct SseParser {
+    buffer: Vec<u8>,
+    event: String,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                if !self.event.is_empty() || !self.data.is_empty() {
+                    events.push(SseEvent {
+                        event: std::mem::take(&mut self.event),
+                        data: std::mem::take(&mut self.data).join("\n"),
+                    });
+                }
+                continue;
+            }
+
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "event" => self.event = value.to_string(),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+
+        events
+    }
+}
+
+pub(crate) fn parse(body: BodyStream) -> SseStream {
+    let state = (body, SseParser::default(), VecDeque::new());
+    Box::pin(futures::stream::unfold(
+        state,
+        |(mut body, mut parser, mut pending)| async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    return Some((Ok(event), (body, parser, pending)));
+                }
+                match body.next().await {
+                    Some(Ok(chunk)) => pending.extend(parser.feed(&chunk)),
+                    Some(Err(err)) => return Some((Err(err), (body, parser, pending))),
+                    None => return None,
+                }
+            }
+        },
+    ))
+}
+
+impl Endpoint {
+    /// Opens a `text/event-stream` connection and yields changes to this location as they
+    /// happen. The stream ends when the server closes the connection.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run() {
+    /// let users = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("users");
+    /// let mut events = users.stream().await.unwrap();
+    /// while let Some(event) = events.next().await {
+    ///     println!("{:?}", event);
+    /// }
+    /// # }
+    /// ```
+    pub async fn stream(&self) -> RequestResult<SseStream> {
+        self.auth().refresh(self.client()).await?;
+        let mut request = self.build_request(Method::GET, None)?;
+        // A whole-request timeout would cut the connection, which is meant to stay open.
+        request.extensions_mut().remove::<Timeout>();
+        if let Ok(value) = "text/event-stream".parse() {
+            request.headers_mut().insert(ACCEPT, value);
+        }
+
+        let response =
+            check_streaming_status(self.client().execute_streaming(request).await?).await?;
+
+        Ok(parse(response.into_body()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sse::{SseEvent, SseParser};
+
+    #[test]
+    fn split_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser
+            .feed(b"event: put\r\ndata: {\"path\":\"/\",")
+            .is_empty());
+
+        let events = parser.feed(b"\"data\":1}\r\n\r\nevent: keep-alive\ndata: null\n\n");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: "put".to_string(),
+                    data: "{\"path\":\"/\",\"data\":1}".to_string(),
+                },
+                SseEvent {
+                    event: "keep-alive".to_string(),
+                    data: "null".to_string(),
+                },
+            ]
+        );
+    }
+}
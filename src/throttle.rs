@@ -0,0 +1,198 @@
+use crate::clients::{self, BodyStream, HttpClient, HttpResponse, StreamingResponse};
+use crate::errors::RequestResult;
+use crate::hooks::HttpRequest;
+use crate::Firebase;
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Bytes per second allowed in each direction; `None` or `Some(0)` leaves that direction
+/// unthrottled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BandwidthLimits {
+    pub upload: Option<u64>,
+    pub download: Option<u64>,
+}
+
+/// Token bucket holding at most one second worth of bytes. Going into debt is allowed, so a
+/// large body passes immediately and the following transfers wait it off.
+#[derive(Debug)]
+struct RateLimiter {
+    rate: u64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate,
+            state: Mutex::new((rate as f64, Instant::now())),
+        }
+    }
+
+    fn reserve(&self, bytes: usize, now: Instant) -> Duration {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let (tokens, last) = *state;
+
+        let elapsed = now.saturating_duration_since(last).as_secs_f64();
+        let rate = self.rate as f64;
+        let available = (tokens + elapsed * rate).min(rate) - bytes as f64;
+        *state = (available, now);
+
+        if available >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-available / rate)
+        }
+    }
+
+    async fn acquire(&self, bytes: usize) {
+        let wait = self.reserve(bytes, Instant::now());
+        if !wait.is_zero() {
+            clients::sleep(wait).await;
+        }
+    }
+}
+
+/// Wraps a transport and keeps its average throughput under [`BandwidthLimits`]. Limits are
+/// shared by every request going through the wrapper.
+#[derive(Debug)]
+pub struct Throttled<C> {
+    inner: C,
+    upload: Option<Arc<RateLimiter>>,
+    download: Option<Arc<RateLimiter>>,
+}
+
+fn limiter(rate: Option<u64>) -> Option<Arc<RateLimiter>> {
+    match rate {
+        Some(rate) if rate > 0 => Some(Arc::new(RateLimiter::new(rate))),
+        _ => None,
+    }
+}
+
+impl<C> Throttled<C> {
+    pub fn new(inner: C, limits: BandwidthLimits) -> Self {
+        Self {
+            inner,
+            upload: limiter(limits.upload),
+            download: limiter(limits.download),
+        }
+    }
+}
+
+#[async_trait]
+impl<C> HttpClient for Throttled<C>
+where
+    C: HttpClient,
+{
+    async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+        if let Some(upload) = &self.upload {
+            upload.acquire(request.body().len()).await;
+        }
+        let response = self.inner.execute(request).await?;
+        if let Some(download) = &self.download {
+            download.acquire(response.body().len()).await;
+        }
+
+        Ok(response)
+    }
+
+    async fn execute_streaming(&self, request: HttpRequest) -> RequestResult<StreamingResponse> {
+        if let Some(upload) = &self.upload {
+            upload.acquire(request.body().len()).await;
+        }
+        let response = self.inner.execute_streaming(request).await?;
+        let download = match &self.download {
+            Some(download) => download.clone(),
+            None => return Ok(response),
+        };
+
+        let (parts, body) = response.into_parts();
+        let body: BodyStream = Box::pin(body.then(move |chunk| {
+            let download = download.clone();
+            async move {
+                if let Ok(chunk) = &chunk {
+                    download.acquire(chunk.len()).await;
+                }
+                chunk
+            }
+        }));
+        Ok(http::Response::from_parts(parts, body))
+    }
+}
+
+impl Firebase {
+    /// ```
+    /// use firebase_rs::{BandwidthLimits, Firebase};
+    ///
+    /// let backup = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_bandwidth_limits(BandwidthLimits {
+    ///     upload: None,
+    ///     download: Some(512 * 1024),
+    /// });
+    /// ```
+    pub fn with_bandwidth_limits(mut self, limits: BandwidthLimits) -> Self {
+        self.client = Arc::new(Throttled::new(self.client, limits));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::errors::RequestResult;
+    use crate::hooks::HttpRequest;
+    use crate::throttle::{BandwidthLimits, RateLimiter, Throttled};
+    use async_trait::async_trait;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn token_bucket() {
+        let limiter = RateLimiter::new(1000);
+        let start = Instant::now();
+        *limiter.state.lock().unwrap() = (1000.0, start);
+
+        assert_eq!(limiter.reserve(600, start), Duration::ZERO);
+        assert_eq!(limiter.reserve(900, start), Duration::from_millis(500));
+
+        let later = start + Duration::from_millis(500);
+        assert_eq!(limiter.reserve(0, later), Duration::ZERO);
+        assert_eq!(
+            limiter.reserve(100, later + Duration::from_secs(10)),
+            Duration::ZERO
+        );
+        assert_eq!(
+            limiter.reserve(1000, later + Duration::from_secs(10)),
+            Duration::from_millis(100)
+        );
+    }
+
+    #[derive(Debug)]
+    struct Echo;
+
+    #[async_trait]
+    impl HttpClient for Echo {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            Ok(http::Response::new(request.into_body()))
+        }
+    }
+
+    #[tokio::test]
+    async fn zero_is_unthrottled() {
+        let client = Throttled::new(
+            Echo,
+            BandwidthLimits {
+                upload: Some(0),
+                download: Some(0),
+            },
+        );
+        assert!(client.upload.is_none() && client.download.is_none());
+
+        let request = http::Request::new(vec![0; 4096]);
+        let response = client.execute(request).await.unwrap();
+        assert_eq!(response.body().len(), 4096);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::credentials::AccessToken;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+/// Tokens this close to expiry are treated as expired.
+pub const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+    principal: String,
+    scopes: String,
+}
+
+impl CacheKey {
+    pub fn new(principal: String, scopes: &[&str]) -> Self {
+        let mut scopes = scopes.to_vec();
+        scopes.sort_unstable();
+        scopes.dedup();
+
+        Self {
+            principal,
+            scopes: scopes.join(" "),
+        }
+    }
+}
+
+fn cache() -> &'static Mutex<HashMap<CacheKey, AccessToken>> {
+    static CACHE: OnceLock<Mutex<HashMap<CacheKey, AccessToken>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+pub(crate) fn get(key: &CacheKey) -> Option<AccessToken> {
+    let cache = match cache().lock() {
+        Ok(cache) => cache,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    cache
+        .get(key)
+        .filter(|token| token.expires_at > SystemTime::now() + EXPIRY_MARGIN)
+        .cloned()
+}
+
+/// Lock held while a token for `key` is fetched, so that concurrent cache misses wait for one
+/// fetch instead of each starting their own.
+pub(crate) fn flight(key: &CacheKey) -> Arc<tokio::sync::Mutex<()>> {
+    static FLIGHTS: OnceLock<Mutex<HashMap<CacheKey, Arc<tokio::sync::Mutex<()>>>>> =
+        OnceLock::new();
+    let mut flights = match FLIGHTS.get_or_init(Default::default).lock() {
+        Ok(flights) => flights,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    flights.entry(key.clone()).or_default().clone()
+}
+
+pub(crate) fn insert(key: CacheKey, token: AccessToken) {
+    let mut cache = match cache().lock() {
+        Ok(cache) => cache,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    cache.insert(key, token);
+}
+
+pub(crate) fn remove(key: &CacheKey) {
+    let mut cache = match cache().lock() {
+        Ok(cache) => cache,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    cache.remove(key);
+}
+
+/// Drops every cached access token, forcing the next request to fetch a new one.
+pub fn clear_token_cache() {
+    let mut cache = match cache().lock() {
+        Ok(cache) => cache,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    cache.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::credentials::AccessToken;
+    use crate::secret::Secret;
+    use crate::token_cache::{get, insert, CacheKey};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn scopes_are_order_independent() {
+        assert_eq!(
+            CacheKey::new("principal".to_string(), &["b", "a"]),
+            CacheKey::new("principal".to_string(), &["a", "b", "a"])
+        );
+    }
+
+    #[test]
+    fn expired_tokens_are_ignored() {
+        let key = CacheKey::new("expired_principal".to_string(), &["scope"]);
+        insert(
+            key.clone(),
+            AccessToken {
+                token: Secret::new("token"),
+                expires_at: SystemTime::now() + Duration::from_secs(10),
+            },
+        );
+
+        assert_eq!(get(&key), None);
+    }
+}
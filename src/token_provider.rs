@@ -0,0 +1,63 @@
+use crate::clients::{self, HttpClient};
+use crate::credential::AuthCredential;
+use crate::credentials::{Credentials, DATABASE_SCOPES};
+use crate::errors::{RequestError, RequestResult};
+use async_trait::async_trait;
+use std::fmt::Debug;
+
+/// Source of the credential sent with each database request. [`Firebase`](crate::Firebase)
+/// asks the provider right before every request, so implementations should cache tokens and
+/// only renew them when they are about to expire.
+///
+/// ```
+/// use async_trait::async_trait;
+/// use firebase_rs::{AuthCredential, Firebase, RequestResult, TokenProvider};
+///
+/// #[derive(Debug)]
+/// struct Vault;
+///
+/// #[async_trait]
+/// impl TokenProvider for Vault {
+///     async fn token(&self) -> RequestResult<AuthCredential> {
+///         Ok(AuthCredential::DatabaseSecret("secret_from_vault".into()))
+///     }
+/// }
+///
+/// let firebase = Firebase::new("https://myfirebase.firebaseio.com").unwrap().with_token_provider(Vault);
+/// ```
+#[async_trait]
+pub trait TokenProvider: Debug + Send + Sync {
+    /// The credential to authenticate the next request with. The variant decides whether it is
+    /// sent as `auth=` or `access_token=`.
+    async fn token(&self) -> RequestResult<AuthCredential>;
+
+    /// Like [`token`](TokenProvider::token), for providers that fetch their credential over HTTP:
+    /// `client` is the transport of the [`Firebase`](crate::Firebase) asking, with its throttling
+    /// and concurrency limit. Defaults to calling `token`.
+    async fn token_via(&self, _client: &dyn HttpClient) -> RequestResult<AuthCredential> {
+        self.token().await
+    }
+}
+
+/// A fixed database secret or token.
+#[async_trait]
+impl TokenProvider for AuthCredential {
+    async fn token(&self) -> RequestResult<AuthCredential> {
+        Ok(self.clone())
+    }
+}
+
+/// Access tokens for [`DATABASE_SCOPES`], shared through the process-wide token cache.
+#[async_trait]
+impl TokenProvider for Credentials {
+    async fn token(&self) -> RequestResult<AuthCredential> {
+        self.token_via(clients::default_client().as_ref()).await
+    }
+
+    async fn token_via(&self, client: &dyn HttpClient) -> RequestResult<AuthCredential> {
+        match self.access_token_via(client, &DATABASE_SCOPES).await {
+            Ok(token) => Ok(AuthCredential::AccessToken(token.token)),
+            Err(err) => Err(RequestError::Credentials(err)),
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use crate::clients::HttpResponse;
+use crate::constants::Method;
+use crate::endpoint::{check_status, Endpoint};
+use crate::errors::{RequestError, RequestResult};
+use crate::json::from_json;
+use http::header::{HeaderName, HeaderValue, ETAG, IF_MATCH};
+use http::{HeaderMap, StatusCode};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Serialize;
+
+pub(crate) const ETAG_REQUEST_HEADER: &str = "x-firebase-etag";
+
+/// Attempts made by [`Endpoint::transaction`] before giving up, as in the official SDKs.
+pub const DEFAULT_TRANSACTION_RETRIES: u32 = 25;
+
+impl Endpoint {
+    /// Atomically replaces the value at this location with `update(current)`. The current value
+    /// is read together with its ETag and written back with `if-match`; when another client
+    /// wrote in between, the server answers `412` with the newer value and `update` runs again
+    /// on it, up to [`DEFAULT_TRANSACTION_RETRIES`] times.
+    ///
+    /// Returning `None` from `update` aborts without writing. The committed value is returned,
+    /// or `None` if the transaction was aborted.
+    ///
+    /// ```
+    /// use firebase_rs::Firebase;
+    ///
+    /// # async fn run() {
+    /// let likes = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("posts").at("POST_ID").at("likes");
+    /// let total = likes.transaction(|current: Option<u64>| Some(current.unwrap_or(0) + 1)).await;
+    /// # }
+    /// ```
+    pub async fn transaction<T, F>(&self, update: F) -> RequestResult<Option<T>>
+    where
+        T: Serialize + DeserializeOwned + Send,
+        F: FnMut(Option<T>) -> Option<T> + Send,
+    {
+        self.transaction_with_retries(DEFAULT_TRANSACTION_RETRIES, update)
+            .await
+    }
+
+    /// Like [`transaction`](Endpoint::transaction) with at most `max_attempts` writes before
+    /// failing with [`RequestError::TransactionConflict`].
+    pub async fn transaction_with_retries<T, F>(
+        &self,
+        max_attempts: u32,
+        mut update: F,
+    ) -> RequestResult<Option<T>>
+    where
+        T: Serialize + DeserializeOwned + Send,
+        F: FnMut(Option<T>) -> Option<T> + Send,
+    {
+        let response = self
+            .send_with_header(
+                Method::GET,
+                None,
+                HeaderName::from_static(ETAG_REQUEST_HEADER),
+                HeaderValue::from_static("true"),
+            )
+            .await?;
+        let (mut etag, mut current) = versioned::<T>(check_status(response)?)?;
+
+        for _ in 0..max_attempts {
+            let next = match update(current) {
+                Some(next) => next,
+                None => return Ok(None),
+            };
+            let body = match serde_json::to_vec(&next) {
+                Ok(body) => body,
+                Err(err) => return Err(RequestError::Serialize(err)),
+            };
+
+            let response = self
+                .send_with_header(Method::PUT, Some(body), IF_MATCH, etag)
+                .await?;
+            // Someone else wrote first; the response carries their value and its ETag.
+            if response.status() == StatusCode::PRECONDITION_FAILED {
+                (etag, current) = versioned::<T>(response)?;
+                continue;
+            }
+            check_status(response)?;
+            return Ok(Some(next));
+        }
+
+        Err(RequestError::TransactionConflict(max_attempts))
+    }
+
+    /// Writes `data` only if nothing is stored at this location yet, failing with
+    /// [`RequestError::AlreadyExists`] otherwise, e.g. to claim a username.
+    ///
+    /// The database ignores `if-none-match`, so this reads the ETag of the empty location and
+    /// writes with `if-match`; a concurrent writer turns the write into a `412` and it fails.
+    ///
+    /// ```
+    /// use firebase_rs::{Firebase, RequestError};
+    ///
+    /// # async fn run() {
+    /// let username = Firebase::new("https://myfirebase.firebaseio.com").unwrap().at("usernames").at("ada");
+    /// match username.create(&"USER_ID").await {
+    ///     Ok(()) => println!("claimed"),
+    ///     Err(RequestError::AlreadyExists) => println!("taken"),
+    ///     Err(err) => println!("{}", err),
+    /// }
+    /// # }
+    /// ```
+    pub async fn create<T>(&self, data: &T) -> RequestResult<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let body = match serde_json::to_vec(data) {
+            Ok(body) => body,
+            Err(err) => return Err(RequestError::Serialize(err)),
+        };
+
+        let response = self
+            .send_with_header(
+                Method::GET,
+                None,
+                HeaderName::from_static(ETAG_REQUEST_HEADER),
+                HeaderValue::from_static("true"),
+            )
+            .await?;
+        let (etag, current) = versioned::<IgnoredAny>(check_status(response)?)?;
+        if current.is_some() {
+            return Err(RequestError::AlreadyExists);
+        }
+
+        let response = self
+            .send_with_header(Method::PUT, Some(body), IF_MATCH, etag)
+            .await?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Err(RequestError::AlreadyExists);
+        }
+        check_status(response).map(|_| ())
+    }
+
+    pub(crate) async fn send_with_header(
+        &self,
+        method: Method,
+        body: Option<Vec<u8>>,
+        name: HeaderName,
+        value: HeaderValue,
+    ) -> RequestResult<HttpResponse> {
+        self.auth().refresh(self.client()).await?;
+        let mut headers = HeaderMap::new();
+        headers.insert(name, value);
+        let request = self.build_request_with(method.as_http(), body, headers)?;
+        self.client().execute(request).await
+    }
+}
+
+/// Splits a response into its ETag and decoded body.
+fn versioned<T: DeserializeOwned>(
+    response: HttpResponse,
+) -> RequestResult<(HeaderValue, Option<T>)> {
+    let etag = match response.headers().get(ETAG) {
+        Some(etag) => etag.clone(),
+        None => return Err(RequestError::NetworkError),
+    };
+    Ok((etag, from_json(response.body())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::{HttpClient, HttpResponse};
+    use crate::errors::{RequestError, RequestResult};
+    use crate::hooks::HttpRequest;
+    use crate::Firebase;
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    /// A counter that another client bumps right after each of the first `contended` reads.
+    #[derive(Debug)]
+    struct Counter {
+        state: Mutex<(u64, u32)>,
+        contended: u32,
+    }
+
+    impl Counter {
+        fn respond(value: u64, status: http::StatusCode) -> HttpResponse {
+            let mut response = http::Response::new(value.to_string().into_bytes());
+            *response.status_mut() = status;
+            let etag = format!("etag-{}", value).parse().unwrap();
+            response.headers_mut().insert(http::header::ETAG, etag);
+            response
+        }
+    }
+
+    #[async_trait]
+    impl HttpClient for Counter {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            let mut state = self.state.lock().unwrap();
+            if request.method() == http::Method::GET {
+                assert_eq!(request.headers()["x-firebase-etag"], "true");
+                return Ok(Self::respond(state.0, http::StatusCode::OK));
+            }
+
+            if state.1 < self.contended {
+                state.1 += 1;
+                state.0 += 10;
+            }
+            if request.headers()["if-match"] != format!("etag-{}", state.0).as_str() {
+                return Ok(Self::respond(
+                    state.0,
+                    http::StatusCode::PRECONDITION_FAILED,
+                ));
+            }
+            state.0 = serde_json::from_slice(request.body()).unwrap();
+            Ok(Self::respond(state.0, http::StatusCode::OK))
+        }
+    }
+
+    fn counter(contended: u32) -> std::sync::Arc<Counter> {
+        std::sync::Arc::new(Counter {
+            state: Mutex::new((1, 0)),
+            contended,
+        })
+    }
+
+    #[tokio::test]
+    async fn retries_on_conflict() {
+        let client = counter(2);
+        let likes = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone())
+            .at("likes");
+
+        let mut calls = 0;
+        let committed = likes
+            .transaction(|current: Option<u64>| {
+                calls += 1;
+                Some(current.unwrap_or(0) + 1)
+            })
+            .await
+            .unwrap();
+        assert_eq!(committed, Some(22));
+        assert_eq!(calls, 3);
+        assert_eq!(client.state.lock().unwrap().0, 22);
+
+        let aborted = likes.transaction(|_: Option<u64>| None).await.unwrap();
+        assert_eq!(aborted, None);
+
+        let busy = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(counter(5))
+            .at("likes")
+            .transaction_with_retries(3, |current: Option<u64>| current.map(|n| n + 1))
+            .await;
+        assert!(matches!(busy, Err(RequestError::TransactionConflict(3))));
+    }
+
+    #[tokio::test]
+    async fn hooks_see_version_headers() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        let likes = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(counter(0))
+            .with_request_hook(move |request| {
+                recorded.lock().unwrap().push((
+                    request.method().to_string(),
+                    request.headers().contains_key("x-firebase-etag"),
+                    request.headers().contains_key("if-match"),
+                ));
+            })
+            .at("likes");
+
+        likes
+            .transaction(|current: Option<u64>| current.map(|n| n + 1))
+            .await
+            .unwrap();
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                ("GET".to_string(), true, false),
+                ("PUT".to_string(), false, true)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn create_only_once() {
+        let client = std::sync::Arc::new(Counter {
+            state: Mutex::new((0, 0)),
+            contended: 0,
+        });
+        let name = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(client.clone())
+            .at("usernames/ada");
+        assert!(matches!(
+            name.create(&7).await,
+            Err(RequestError::AlreadyExists)
+        ));
+
+        let empty = Firebase::new("https://myfirebase.firebaseio.com")
+            .unwrap()
+            .with_http_client(Empty(Mutex::default()))
+            .at("usernames/ada");
+        empty.create(&7).await.unwrap();
+        assert!(matches!(
+            empty.create(&8).await,
+            Err(RequestError::AlreadyExists)
+        ));
+    }
+
+    /// Starts out empty and honours `if-match` on writes.
+    #[derive(Debug)]
+    struct Empty(Mutex<Option<Vec<u8>>>);
+
+    #[async_trait]
+    impl HttpClient for Empty {
+        async fn execute(&self, request: HttpRequest) -> RequestResult<HttpResponse> {
+            let mut stored = self.0.lock().unwrap();
+            let body = stored.clone().unwrap_or_else(|| b"null".to_vec());
+            let etag = format!("etag-{}", body.len());
+            let matches = request
+                .headers()
+                .get("if-match")
+                .is_none_or(|tag| tag == etag.as_str());
+
+            let mut response = http::Response::new(body);
+            if request.method() == http::Method::PUT {
+                if matches {
+                    *stored = Some(request.body().clone());
+                } else {
+                    *response.status_mut() = http::StatusCode::PRECONDITION_FAILED;
+                }
+            }
+            response
+                .headers_mut()
+                .insert(http::header::ETAG, etag.parse().unwrap());
+            Ok(response)
+        }
+    }
+}
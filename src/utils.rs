@@ -1,5 +1,6 @@
 use crate::errors::UrlParseResult;
 use crate::UrlParseError;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use url::Url;
 
 pub fn check_uri(uri: &str) -> UrlParseResult<Url> {
@@ -16,3 +17,105 @@ pub fn check_uri(uri: &str) -> UrlParseResult<Url> {
 
     Ok(uri)
 }
+
+pub fn unix_seconds(time: SystemTime) -> u64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs(),
+        Err(_) => 0,
+    }
+}
+
+/// Formats `time` as a compact ISO 8601 UTC timestamp, e.g. `20150830T123600Z`.
+pub fn format_basic_utc(time: SystemTime) -> String {
+    let secs = unix_seconds(time);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil-from-days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+/// Parses an RFC 3339 UTC timestamp as the Google APIs return it, e.g.
+/// `2015-08-30T12:36:00.5Z`.
+pub fn parse_rfc3339_utc(timestamp: &str) -> Option<SystemTime> {
+    let (date, time) = timestamp.strip_suffix('Z')?.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, fraction),
+        None => (time, ""),
+    };
+    let mut time = time.splitn(3, ':').map(str::parse::<u64>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+    let nanos = match fraction {
+        "" => 0,
+        fraction if fraction.len() <= 9 => {
+            fraction.parse::<u32>().ok()? * 10u32.pow(9 - fraction.len() as u32)
+        }
+        _ => return None,
+    };
+
+    // Days-from-civil, the inverse of the conversion in `format_basic_utc`.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = u64::try_from(era * 146_097 + doe - 719_468).ok()?;
+
+    let secs = days * 86_400 + hour * 3_600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{format_basic_utc, parse_rfc3339_utc};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn basic_utc() {
+        assert_eq!(format_basic_utc(UNIX_EPOCH), "19700101T000000Z");
+        assert_eq!(
+            format_basic_utc(UNIX_EPOCH + Duration::from_secs(1_440_938_160)),
+            "20150830T123600Z"
+        );
+        assert_eq!(
+            format_basic_utc(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "20000229T000000Z"
+        );
+    }
+
+    #[test]
+    fn rfc3339_utc() {
+        assert_eq!(
+            parse_rfc3339_utc("2015-08-30T12:36:00Z"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_440_938_160))
+        );
+        assert_eq!(
+            parse_rfc3339_utc("2000-02-29T00:00:00.25Z"),
+            Some(UNIX_EPOCH + Duration::new(951_782_400, 250_000_000))
+        );
+        assert_eq!(parse_rfc3339_utc("2015-08-30T12:36:00+02:00"), None);
+        assert_eq!(parse_rfc3339_utc("2015-13-30T12:36:00Z"), None);
+    }
+}